@@ -5,7 +5,14 @@ use std::path::Path;
 use std::process;
 
 use blueprint::docker::jobs::{
-    create_docker_rollup, delete_docker_rollup, start_docker_rollup, stop_docker_rollup,
+    check_docker_rollup_health, create_docker_rollup, decommission_docker_rollup,
+    delete_docker_rollup, diagnose_host, diagnose_network, exec_docker_rollup,
+    get_docker_rollup_config_diff, get_docker_rollup_endpoints, get_docker_rollup_info,
+    get_docker_rollup_logs, get_docker_rollup_metrics, get_docker_rollup_status,
+    list_docker_rollups, list_docker_rollups_paged, prune_docker_rollups,
+    regenerate_docker_rollup_config, restart_docker_rollup, rotate_docker_rollup_keys,
+    start_docker_rollup, start_docker_rollups_for_service, stop_docker_rollup,
+    stop_docker_rollups_for_service, update_docker_rollup, upgrade_docker_rollup,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -26,6 +33,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             start_docker_rollup,
             stop_docker_rollup,
             delete_docker_rollup,
+            get_docker_rollup_status,
+            diagnose_network,
+            decommission_docker_rollup,
+            list_docker_rollups,
+            get_docker_rollup_config_diff,
+            restart_docker_rollup,
+            update_docker_rollup,
+            check_docker_rollup_health,
+            start_docker_rollups_for_service,
+            stop_docker_rollups_for_service,
+            exec_docker_rollup,
+            get_docker_rollup_info,
+            list_docker_rollups_paged,
+            get_docker_rollup_logs,
+            upgrade_docker_rollup,
+            get_docker_rollup_endpoints,
+            prune_docker_rollups,
+            rotate_docker_rollup_keys,
+            get_docker_rollup_metrics,
+            regenerate_docker_rollup_config,
+            diagnose_host,
         ],
     };
 
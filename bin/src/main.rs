@@ -33,39 +33,110 @@ async fn main() -> Result<()> {
     // Consumer
     let tangle_consumer = TangleConsumer::new(tangle_client.rpc_client.clone(), st25519_signer);
 
-    let service_id = env.protocol_settings.tangle()?.service_id.unwrap();
     let context = blueprint::ServiceContext::new(env.clone());
+    let rollups = context.rollups.clone();
+
+    // Bring persisted rollup statuses in line with the containers actually running
+    if let Err(e) = blueprint::reconcile_rollups(&rollups).await {
+        sdk::error!("Failed to reconcile rollups: {e:?}");
+    }
+    // ...and keep them in line while the blueprint runs
+    blueprint::spawn_reconciler(rollups.clone());
+    blueprint::metrics::spawn_metrics_server();
+
+    let service_id = env.protocol_settings.tangle()?.service_id.unwrap();
     let router = sdk::Router::new()
         .route(0, blueprint::docker::jobs::create_docker_rollup)
         .route(1, blueprint::docker::jobs::start_docker_rollup)
         .route(2, blueprint::docker::jobs::stop_docker_rollup)
         .route(3, blueprint::docker::jobs::delete_docker_rollup)
+        .route(4, blueprint::docker::jobs::get_docker_rollup_status)
+        .route(5, blueprint::docker::jobs::diagnose_network)
+        .route(6, blueprint::docker::jobs::decommission_docker_rollup)
+        .route(7, blueprint::docker::jobs::list_docker_rollups)
+        .route(8, blueprint::docker::jobs::get_docker_rollup_config_diff)
+        .route(9, blueprint::docker::jobs::restart_docker_rollup)
+        .route(10, blueprint::docker::jobs::update_docker_rollup)
+        .route(11, blueprint::docker::jobs::check_docker_rollup_health)
+        .route(
+            12,
+            blueprint::docker::jobs::start_docker_rollups_for_service,
+        )
+        .route(13, blueprint::docker::jobs::stop_docker_rollups_for_service)
+        .route(14, blueprint::docker::jobs::exec_docker_rollup)
+        .route(15, blueprint::docker::jobs::get_docker_rollup_info)
+        .route(16, blueprint::docker::jobs::list_docker_rollups_paged)
+        .route(17, blueprint::docker::jobs::get_docker_rollup_logs)
+        .route(18, blueprint::docker::jobs::upgrade_docker_rollup)
+        .route(19, blueprint::docker::jobs::get_docker_rollup_endpoints)
+        .route(20, blueprint::docker::jobs::prune_docker_rollups)
+        .route(21, blueprint::docker::jobs::rotate_docker_rollup_keys)
+        .route(22, blueprint::docker::jobs::get_docker_rollup_metrics)
+        .route(23, blueprint::docker::jobs::regenerate_docker_rollup_config)
+        .route(24, blueprint::docker::jobs::diagnose_host)
         .layer(TangleLayer)
         .layer(FilterLayer::new(MatchesServiceId(service_id)))
         .with_context(context);
     sdk::info!("Starting the event watcher ...");
-    let result = BlueprintRunner::builder(config, env)
+    let runner = BlueprintRunner::builder(config, env)
         .router(router)
         .producer(tangle_producer)
         .consumer(tangle_consumer)
-        .run()
-        .await;
+        .run();
+    let result = tokio::select! {
+        result = runner => result,
+        () = shutdown_signal() => {
+            sdk::info!("Received shutdown signal");
+            Ok(())
+        }
+    };
     if let Err(e) = result {
         sdk::error!("Runner failed! {e:?}");
     }
+
+    // Don't leave containers behind that nothing tracks anymore
+    blueprint::shutdown_rollups(&rollups).await;
     Ok(())
 }
 
+/// Wait for SIGTERM or Ctrl-C
+async fn shutdown_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            sdk::error!("Failed to listen for SIGTERM: {e:?}");
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+}
+
+/// Set up logging, as JSON when `LOG_FORMAT=json`
 pub fn setup_log() {
     use tracing_subscriber::util::SubscriberInitExt;
 
-    let _ = tracing_subscriber::fmt::SubscriberBuilder::default()
+    let filter = tracing_subscriber::EnvFilter::builder()
+        .with_default_directive(tracing::metadata::LevelFilter::INFO.into())
+        .from_env_lossy();
+    let builder = tracing_subscriber::fmt::SubscriberBuilder::default()
         .with_span_events(tracing_subscriber::fmt::format::FmtSpan::NONE)
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::builder()
-                .with_default_directive(tracing::metadata::LevelFilter::INFO.into())
-                .from_env_lossy(),
-        )
-        .finish()
-        .try_init();
+        .with_env_filter(filter);
+
+    // Span fields such as `rollup_id` become keys of the `span` and `spans` objects
+    let _ = match std::env::var("LOG_FORMAT") {
+        Ok(format) if format.eq_ignore_ascii_case("json") => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .finish()
+            .try_init(),
+        _ => builder.finish().try_init(),
+    };
 }
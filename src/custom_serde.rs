@@ -6,6 +6,7 @@ pub mod hex_list {
     use serde::Serialize;
     use serde::de::{SeqAccess, Visitor};
 
+    /// Serialize as a list of unprefixed hex strings
     pub fn serialize<S, T>(list: &List<T>, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
@@ -18,16 +19,17 @@ pub mod hex_list {
         List(out).serialize(serializer)
     }
 
+    /// Deserialize a list of hex strings, with or without a `0x` prefix
     pub fn deserialize<'de, D, T>(deserializer: D) -> Result<List<T>, D::Error>
     where
         D: serde::Deserializer<'de>,
-        T: serde::Deserialize<'de> + Default + hex::FromHex,
+        T: serde::Deserialize<'de> + Default + AsRef<[u8]> + hex::FromHex,
         <T as hex::FromHex>::Error: fmt::Display,
     {
         struct HexStrVisitor<T>(PhantomData<T>);
         impl<'de, T> Visitor<'de> for HexStrVisitor<T>
         where
-            T: serde::Deserialize<'de> + Default + hex::FromHex,
+            T: serde::Deserialize<'de> + Default + AsRef<[u8]> + hex::FromHex,
             <T as hex::FromHex>::Error: fmt::Display,
         {
             type Value = List<T>;
@@ -40,10 +42,30 @@ pub mod hex_list {
             where
                 A: SeqAccess<'de>,
             {
+                // Fixed-size values such as addresses default to their full length
+                let expected = T::default().as_ref().len();
                 let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
                 while let Some(item) = seq.next_element::<String>()? {
-                    let bytes = hex::FromHex::from_hex(item).map_err(serde::de::Error::custom)?;
-                    out.push(bytes);
+                    let index = out.len();
+                    let item = item
+                        .strip_prefix("0x")
+                        .or_else(|| item.strip_prefix("0X"))
+                        .unwrap_or(&item);
+                    let bytes = hex::decode(item).map_err(|e| {
+                        serde::de::Error::custom(format!(
+                            "address at index {} is not valid hex: {}",
+                            index, e
+                        ))
+                    })?;
+                    if bytes.len() != expected {
+                        return Err(serde::de::Error::custom(format!(
+                            "address at index {} must be {} bytes, got {}",
+                            index,
+                            expected,
+                            bytes.len()
+                        )));
+                    }
+                    out.push(hex::FromHex::from_hex(item).map_err(serde::de::Error::custom)?);
                 }
                 Ok(List(out))
             }
@@ -1,47 +1,342 @@
 use blueprint_sdk as sdk;
 
-use anyhow::Result;
+use crate::NetworkType;
+use crate::deployer::DeploymentResult;
+use crate::deployer::node_flags::NodeFlags;
+use crate::docker::health::{NODE_FEED_PORT, NODE_RPC_PORT, NODE_WS_PORT};
+use alloy::primitives::Address;
+use alloy::transports::http::reqwest::Url;
+use anyhow::{Result, anyhow};
+use regex::Regex;
 use sdk::info;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 
 /// Configuration generator for Espresso node
 pub struct ConfigGenerator {
     config_dir: PathBuf,
     workspace_dir: PathBuf,
-    chain_id: u64,
-    rollup_address: String,
-    upgrade_executor_address: String,
-    deployment_block: u64,
+    network: NetworkType,
+    initial_chain_owner: String,
+    deployment: DeploymentResult,
     validator_key: String,
     batch_poster_key: String,
-    arbitrum_rpc_url: String,
+    parent_chain_rpc_url: String,
+    node_flags: NodeFlags,
+    jwt_secret: Option<String>,
+    rpc_host_port: u16,
+    ws_host_port: u16,
+    feed_host_port: u16,
+    validation_host_port: u16,
+    node_image: Option<String>,
+    node_image_tag: Option<String>,
+    espresso_sequencer_url: Option<String>,
+    espresso_namespace: Option<u64>,
+    data_dir: Option<PathBuf>,
+    extra_env: HashMap<String, String>,
+}
+
+/// Host port the validation node was published on before rollups got ports of their own
+pub const DEFAULT_VALIDATION_HOST_PORT: u16 = 8949;
+
+/// Image of the nitro node and validation node containers, without its tag
+pub const NITRO_NODE_IMAGE: &str = "ghcr.io/espressosystems/nitro-espresso-integration/nitro-node";
+
+/// Tag of [`NITRO_NODE_IMAGE`] rollups run until they are upgraded
+pub const DEFAULT_NITRO_NODE_TAG: &str = "integration";
+
+/// Check that `tag` is a valid Docker image tag
+///
+/// Tags are up to 128 letters, digits, `_`, `.` and `-`, not starting with `.` or `-`.
+pub fn validate_image_tag(tag: &str) -> Result<()> {
+    let valid = tag.len() <= 128
+        && tag
+            .chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_alphanumeric() || first == '_')
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if !valid {
+        return Err(anyhow!("Invalid image tag {:?}", tag));
+    }
+    Ok(())
+}
+
+/// Line of the compose templates starting the node, the node's environment follows it
+const NODE_COMMAND_LINE: &str = "    command: --conf.file /config/full_node.json\n";
+
+/// Parts of environment variable names reaching the node's keys, wallets or JWT secret,
+/// compared case-insensitively without `_` and `-`
+const PROTECTED_ENV_PARTS: [&str; 6] = [
+    "privatekey",
+    "wallet",
+    "jwt",
+    "secret",
+    "password",
+    "keystore",
+];
+
+/// Environment variable names compose passes on as they are
+static ENV_VAR_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").expect("valid regex"));
+
+/// Check that `extra_env` only sets variables the node may be tweaked with
+///
+/// Names must be plain environment variable names that don't reach the node's keys,
+/// wallets or JWT secret, such as `NITRO_NODE_BATCH__POSTER_PARENT__CHAIN__WALLET_PRIVATE__KEY`,
+/// nor replace its config file through `NITRO_CONF_*`.
+pub fn validate_extra_env(extra_env: &HashMap<String, String>) -> Result<()> {
+    let mut names: Vec<&String> = extra_env.keys().collect();
+    names.sort();
+    for name in names {
+        if !ENV_VAR_NAME.is_match(name) {
+            return Err(anyhow!("Invalid environment variable name {:?}", name));
+        }
+        let lowercase = name.to_lowercase();
+        let normalized = lowercase.replace(['_', '-'], "");
+        let protected = PROTECTED_ENV_PARTS
+            .iter()
+            .any(|part| normalized.contains(part))
+            || lowercase.split('_').any(|segment| segment == "conf");
+        if protected {
+            return Err(anyhow!(
+                "{} would override a security-critical node setting",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Directory of the node container the chain data is kept in
+pub const NODE_DATA_MOUNT: &str = "/home/user/.arbitrum";
+
+/// Espresso query service the batch poster reads from unless the rollup joins another network
+pub const DEFAULT_ESPRESSO_SEQUENCER_URL: &str = "https://query.decaf.testnet.espresso.network/v0";
+
+/// Check that `url` is an absolute `http` or `https` URL of an Espresso query service
+pub fn validate_sequencer_url(url: &str) -> Result<()> {
+    validate_url(url, "sequencer", &["http", "https"])
+}
+
+/// Check that `url` is an absolute `http(s)` or `ws(s)` URL of a parent chain RPC
+pub fn validate_rpc_url(url: &str) -> Result<()> {
+    validate_url(url, "RPC", &["http", "https", "ws", "wss"])
+}
+
+/// Check that `url` has a host and one of `schemes`, naming it a `kind` URL in errors
+fn validate_url(url: &str, kind: &str, schemes: &[&str]) -> Result<()> {
+    let parsed = url
+        .parse::<Url>()
+        .map_err(|e| anyhow!("Invalid {} URL {:?}: {}", kind, url, e))?;
+    if !schemes.contains(&parsed.scheme()) || parsed.host_str().is_none() {
+        return Err(anyhow!(
+            "Invalid {} URL {:?}, expected {} with a host",
+            kind,
+            url,
+            schemes.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Docker image references: `[registry[:port]/]name[:tag][@digest]`
+static IMAGE_REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:[a-zA-Z0-9.-]+(?::[0-9]+)?/)?[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*(?:/[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*)*(?::[A-Za-z0-9_][A-Za-z0-9_.-]{0,127})?(?:@[a-z0-9]+(?:[+._-][a-z0-9]+)*:[a-fA-F0-9]{32,})?$",
+    )
+    .expect("valid regex")
+});
+
+/// Check that `image` is a Docker image reference, e.g. `registry.local:5000/nitro-node:v3`
+pub fn validate_image_reference(image: &str) -> Result<()> {
+    if image.len() > 255 || !IMAGE_REFERENCE.is_match(image) {
+        return Err(anyhow!("Invalid image reference {:?}", image));
+    }
+    Ok(())
+}
+
+/// `image` without its tag or digest
+fn image_repository(image: &str) -> &str {
+    let image = image.split('@').next().unwrap_or(image);
+    match image.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => image,
+    }
+}
+
+/// Whether `address` is a 0x-prefixed 20-byte hex address
+pub(crate) fn is_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|digits| digits.len() == 40 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// EIP-55 checksummed, 0x-prefixed form of an address, which some nitro tooling requires
+pub fn checksum_address(address: &[u8; 20]) -> String {
+    Address::from(*address).to_checksum(None)
+}
+
+/// [`checksum_address`] of a hex address, with or without a `0x` prefix
+pub(crate) fn checksum_hex_address(address: &str) -> Result<String> {
+    let mut bytes = [0; 20];
+    hex::decode_to_slice(address.strip_prefix("0x").unwrap_or(address), &mut bytes)
+        .map_err(|e| anyhow!("Invalid address {:?}: {}", address, e))?;
+    Ok(checksum_address(&bytes))
+}
+
+/// Generate a random 32-byte JWT secret, hex-encoded
+pub fn generate_jwt_secret() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Check that a JWT secret is 32 bytes of hex, with or without a `0x` prefix
+pub fn validate_jwt_secret(secret: &str) -> Result<()> {
+    let digits = secret.strip_prefix("0x").unwrap_or(secret);
+    if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("JWT secret must be exactly 64 hex characters"));
+    }
+    Ok(())
 }
 
 impl ConfigGenerator {
-    /// Create a new config generator
+    /// Create a new config generator for the rollup deployed as `deployment` on `network`
+    ///
+    /// The node follows the parent chain on `parent_chain_rpc_url`, which needn't be the
+    /// endpoint the contracts were deployed through, see [`NetworkType::node_rpc_url`].
+    #[allow(clippy::too_many_arguments)]
     pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(
         config_dir: P,
         workspace_dir: Q,
-        chain_id: u64,
-        rollup_address: String,
-        upgrade_executor_address: String,
-        deployment_block: u64,
+        network: NetworkType,
+        initial_chain_owner: String,
+        deployment: DeploymentResult,
         validator_key: String,
         batch_poster_key: String,
-        arbitrum_rpc_url: String,
+        parent_chain_rpc_url: String,
     ) -> Self {
         Self {
             config_dir: config_dir.as_ref().to_path_buf(),
             workspace_dir: workspace_dir.as_ref().to_path_buf(),
-            chain_id,
-            rollup_address,
-            upgrade_executor_address,
-            deployment_block,
+            network,
+            initial_chain_owner,
+            deployment,
             validator_key,
             batch_poster_key,
-            arbitrum_rpc_url,
+            parent_chain_rpc_url,
+            node_flags: NodeFlags::default(),
+            jwt_secret: None,
+            rpc_host_port: NODE_RPC_PORT,
+            ws_host_port: NODE_WS_PORT,
+            feed_host_port: NODE_FEED_PORT,
+            validation_host_port: DEFAULT_VALIDATION_HOST_PORT,
+            node_image: None,
+            node_image_tag: None,
+            espresso_sequencer_url: None,
+            espresso_namespace: None,
+            data_dir: None,
+            extra_env: HashMap::new(),
+        }
+    }
+
+    /// Set the nitro node flags merged into `full_node.json`
+    pub fn with_node_flags(mut self, node_flags: NodeFlags) -> Self {
+        self.node_flags = node_flags;
+        self
+    }
+
+    /// Use `jwt_secret` between the node and the validation node instead of a random one
+    pub fn with_jwt_secret(mut self, jwt_secret: String) -> Self {
+        self.jwt_secret = Some(jwt_secret);
+        self
+    }
+
+    /// Publish the node RPC on `port` of the host instead of the container port
+    pub fn with_rpc_host_port(mut self, port: u16) -> Self {
+        self.rpc_host_port = port;
+        self
+    }
+
+    /// Publish the node WebSocket RPC on `port` of the host instead of the container port
+    pub fn with_ws_host_port(mut self, port: u16) -> Self {
+        self.ws_host_port = port;
+        self
+    }
+
+    /// Publish the node sequencer feed on `port` of the host instead of the container port
+    pub fn with_feed_host_port(mut self, port: u16) -> Self {
+        self.feed_host_port = port;
+        self
+    }
+
+    /// Publish the validation node on `port` of the host instead of
+    /// [`DEFAULT_VALIDATION_HOST_PORT`]
+    pub fn with_validation_host_port(mut self, port: u16) -> Self {
+        self.validation_host_port = port;
+        self
+    }
+
+    /// Run the nodes on `image` instead of [`NITRO_NODE_IMAGE`]
+    pub fn with_node_image(mut self, image: String) -> Self {
+        self.node_image = Some(image);
+        self
+    }
+
+    /// Run the nodes on `tag` of the node image instead of its own tag
+    pub fn with_node_image_tag(mut self, tag: String) -> Self {
+        self.node_image_tag = Some(tag);
+        self
+    }
+
+    /// Post batches through the Espresso query service at `url` instead of
+    /// [`DEFAULT_ESPRESSO_SEQUENCER_URL`]
+    pub fn with_espresso_sequencer_url(mut self, url: String) -> Self {
+        self.espresso_sequencer_url = Some(url);
+        self
+    }
+
+    /// Sequence the rollup's transactions under `namespace` of the Espresso network
+    pub fn with_espresso_namespace(mut self, namespace: u64) -> Self {
+        self.espresso_namespace = Some(namespace);
+        self
+    }
+
+    /// Keep the node's chain data in `data_dir` instead of `database` next to the compose file
+    ///
+    /// The directory is bind-mounted into the node container, so the chain survives the
+    /// container being recreated.
+    pub fn with_data_dir<P: AsRef<Path>>(mut self, data_dir: P) -> Self {
+        self.data_dir = Some(data_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Pass `extra_env` to the node container, see [`validate_extra_env`]
+    pub fn with_extra_env(mut self, extra_env: HashMap<String, String>) -> Self {
+        self.extra_env = extra_env;
+        self
+    }
+
+    /// Image reference of the node containers
+    ///
+    /// The tag set with [`Self::with_node_image_tag`] replaces the tag of the node image.
+    pub fn node_image(&self) -> Result<String> {
+        let image = match (&self.node_image, &self.node_image_tag) {
+            (Some(image), None) => image.clone(),
+            (Some(image), Some(tag)) => format!("{}:{}", image_repository(image), tag),
+            (None, tag) => format!(
+                "{}:{}",
+                NITRO_NODE_IMAGE,
+                tag.as_deref().unwrap_or(DEFAULT_NITRO_NODE_TAG)
+            ),
+        };
+        if let Some(tag) = &self.node_image_tag {
+            validate_image_tag(tag)?;
         }
+        validate_image_reference(&image)?;
+        Ok(image)
     }
 
     /// Generate all configuration files
@@ -54,7 +349,7 @@ impl ConfigGenerator {
         self.copy_and_update_l2_chain_info()?;
         self.copy_and_update_full_node()?;
         self.copy_and_update_validation_node_config()?;
-        self.copy_jwt_file()?;
+        self.write_jwt_file()?;
         self.copy_docker_compose()?;
         self.make_docker_mounts()?;
 
@@ -69,40 +364,52 @@ impl ConfigGenerator {
         // Read the template file
         let template = include_str!("config/l2_chain_info.json");
 
-        // Replace placeholders with actual values
-        let content = template
-            .replace("10000000", &self.chain_id.to_string())
-            .replace(
-                "INITIAL_CHAIN_OWNER_ADDRESS",
-                &std::env::var("INITIAL_CHAIN_OWNER").unwrap_or_default(),
-            )
-            .replace(
-                "BRIDGE_ADDRESS",
-                &std::env::var("BRIDGE_ADDRESS").unwrap_or_default(),
-            )
-            .replace(
-                "INBOX_ADDRESS",
-                &std::env::var("INBOX_ADDRESS").unwrap_or_default(),
-            )
-            .replace(
+        // `SEQUENCER_INBOX_ADDRESS` contains `INBOX_ADDRESS`, so it has to be replaced first
+        let deployment = &self.deployment;
+        let addresses = [
+            ("INITIAL_CHAIN_OWNER_ADDRESS", &self.initial_chain_owner),
+            (
                 "SEQUENCER_INBOX_ADDRESS",
-                &std::env::var("SEQUENCER_INBOX_ADDRESS").unwrap_or_default(),
-            )
-            .replace("100000000", &self.deployment_block.to_string())
-            .replace("ROLLUP_ADDRESS", &self.rollup_address)
-            .replace("UPGRADE_EXECUTOR_ADDRESS", &self.upgrade_executor_address)
-            .replace(
+                &deployment.sequencer_inbox_address,
+            ),
+            ("BRIDGE_ADDRESS", &deployment.bridge_address),
+            ("INBOX_ADDRESS", &deployment.inbox_address),
+            ("ROLLUP_ADDRESS", &deployment.rollup_proxy_address),
+            (
+                "UPGRADE_EXECUTOR_ADDRESS",
+                &deployment.upgrade_executor_address,
+            ),
+            (
                 "VALIDATOR_UTILS_ADDRESS",
-                &std::env::var("VALIDATOR_UTILS_ADDRESS").unwrap_or_default(),
-            )
-            .replace(
+                &deployment.validator_utils_address,
+            ),
+            (
                 "VALIDATOR_WALLET_CREATOR_ADDRESS",
-                &std::env::var("VALIDATOR_WALLET_CREATOR_ADDRESS").unwrap_or_default(),
-            );
+                &deployment.validator_wallet_creator_address,
+            ),
+        ];
+
+        // An empty or malformed address only breaks the node once it starts
+        let mut content = template.to_string();
+        for (placeholder, address) in addresses {
+            if !is_address(address) {
+                return Err(anyhow!(
+                    "Invalid address for {} in l2_chain_info.json: {:?}",
+                    placeholder,
+                    address
+                ));
+            }
+            content = content.replace(placeholder, &checksum_hex_address(address)?);
+        }
+
+        // The numbers are set on the parsed file, their placeholders overlap
+        let mut chain_info = serde_json::from_str::<serde_json::Value>(&content)?;
+        chain_info[0]["chain-config"]["chainId"] = deployment.chain_id.into();
+        chain_info[0]["rollup"]["deployed-at"] = deployment.deployment_block.into();
 
         // Write to the output file
         let output_path = self.config_dir.join("l2_chain_info.json");
-        fs::write(&output_path, content)?;
+        fs::write(&output_path, serde_json::to_string_pretty(&chain_info)?)?;
 
         info!("Generated l2_chain_info.json at {}", output_path.display());
         Ok(())
@@ -112,9 +419,35 @@ impl ConfigGenerator {
         // Read the template file
         let template = include_str!("config/full_node.json");
 
+        // The node polls the parent chain for as long as it runs
+        let mut config = serde_json::from_str::<serde_json::Value>(template)?;
+        validate_rpc_url(&self.parent_chain_rpc_url)
+            .map_err(|e| anyhow!("Parent chain RPC of the node: {}", e))?;
+        config["parent-chain"]["connection"]["url"] = self.parent_chain_rpc_url.as_str().into();
+
+        // The staker and batch poster sign with the rollup's keys, which nitro takes unprefixed
+        let unprefixed = |key: &str| key.trim_start_matches("0x").to_string();
+        config["node"]["staker"]["parent-chain-wallet"]["private-key"] =
+            unprefixed(&self.validator_key).into();
+        config["node"]["batch-poster"]["parent-chain-wallet"]["private-key"] =
+            unprefixed(&self.batch_poster_key).into();
+
+        // Point the batch poster at the Espresso network the rollup shares
+        let batch_poster = &mut config["node"]["batch-poster"];
+        if let Some(url) = &self.espresso_sequencer_url {
+            validate_sequencer_url(url)?;
+            batch_poster["hotshot-url"] = url.as_str().into();
+        }
+        if let Some(namespace) = self.espresso_namespace {
+            batch_poster["espresso-namespace"] = namespace.into();
+        }
+
+        // Merge the node flags over the template defaults
+        self.node_flags.apply(&mut config)?;
+
         // Write to the output file
         let output_path = self.config_dir.join("full_node.json");
-        fs::write(&output_path, template)?;
+        fs::write(&output_path, serde_json::to_string_pretty(&config)?)?;
 
         info!("Generated full_node.json at {}", output_path.display());
         Ok(())
@@ -135,21 +468,63 @@ impl ConfigGenerator {
         Ok(())
     }
 
-    fn copy_jwt_file(&self) -> Result<()> {
-        // Read the template file
-        let template = include_str!("config/val_jwt.hex");
+    fn write_jwt_file(&self) -> Result<()> {
+        // Every rollup gets its own secret unless one was provided
+        let secret = match &self.jwt_secret {
+            Some(secret) => {
+                validate_jwt_secret(secret)?;
+                secret.clone()
+            }
+            None => generate_jwt_secret(),
+        };
 
         // Write to the output file
         let output_path = self.config_dir.join("val_jwt.hex");
-        fs::write(&output_path, template)?;
+        fs::write(&output_path, secret)?;
 
         info!("Generated JWT file at {}", output_path.display());
         Ok(())
     }
 
+    /// The docker-compose template for the parent chain network
+    fn docker_compose_template(&self) -> &'static str {
+        match self.network {
+            // A devnet also runs its own geth
+            NetworkType::Geth => include_str!("config/docker-compose.geth.yml"),
+            _ => include_str!("config/docker-compose.yml"),
+        }
+    }
+
     fn copy_docker_compose(&self) -> Result<()> {
-        // Read the docker-compose template
-        let template = include_str!("config/docker-compose.yml");
+        // Read the docker-compose template and substitute the host ports and the image of
+        // the nitro and validation node services
+        let mut template = self
+            .docker_compose_template()
+            .replace("RPC_HOST_PORT", &self.rpc_host_port.to_string())
+            .replace("WS_HOST_PORT", &self.ws_host_port.to_string())
+            .replace("FEED_HOST_PORT", &self.feed_host_port.to_string())
+            .replace(
+                "VALIDATION_HOST_PORT",
+                &self.validation_host_port.to_string(),
+            )
+            .replace(
+                &format!("{}:{}", NITRO_NODE_IMAGE, DEFAULT_NITRO_NODE_TAG),
+                &self.node_image()?,
+            );
+        // Compose reads paths that don't start with `.` or `/` as volume names
+        if let Some(data_dir) = &self.data_dir {
+            template = template.replace(
+                &format!("./database:{}", NODE_DATA_MOUNT),
+                &format!(
+                    "{}:{}",
+                    std::path::absolute(data_dir)?.display(),
+                    NODE_DATA_MOUNT
+                ),
+            );
+        }
+        if !self.extra_env.is_empty() {
+            template = self.with_node_environment(&template)?;
+        }
 
         // Write to the parent directory of the workspace directory
         let output_path = self
@@ -165,13 +540,39 @@ impl ConfigGenerator {
         Ok(())
     }
 
+    /// `template` with the extra environment set on the node service
+    fn with_node_environment(&self, template: &str) -> Result<String> {
+        validate_extra_env(&self.extra_env)?;
+        if !template.contains(NODE_COMMAND_LINE) {
+            return Err(anyhow!(
+                "Compose template has no node service to set the environment of"
+            ));
+        }
+
+        let mut environment = String::from("    environment:\n");
+        let variables: BTreeMap<&String, &String> = self.extra_env.iter().collect();
+        for (name, value) in variables {
+            // JSON strings are YAML strings, and `$$` keeps compose from interpolating
+            let value = serde_json::to_string(&value.replace('$', "$$"))?;
+            environment.push_str(&format!("      {}: {}\n", name, value));
+        }
+        Ok(template.replacen(
+            NODE_COMMAND_LINE,
+            &format!("{}{}", NODE_COMMAND_LINE, environment),
+            1,
+        ))
+    }
+
     fn make_docker_mounts(&self) -> Result<()> {
         // Create the docker mounts directory
         let mounts_dir = self.workspace_dir.parent().ok_or_else(|| {
             std::io::Error::new(std::io::ErrorKind::NotFound, "Parent directory not found")
         })?;
         fs::create_dir_all(mounts_dir.join("wasm"))?;
-        fs::create_dir_all(mounts_dir.join("database"))?;
+        match &self.data_dir {
+            Some(data_dir) => fs::create_dir_all(data_dir)?,
+            None => fs::create_dir_all(mounts_dir.join("database"))?,
+        }
         info!("Created docker mounts at {}", mounts_dir.display());
         Ok(())
     }
@@ -0,0 +1,241 @@
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Directory holding the encrypted keystore files of the rollup keys
+pub const KEYSTORE_ENV_VAR: &str = "ESPRESSO_KEYSTORE";
+
+/// Passphrase the keystore files are encrypted with
+pub const KEYSTORE_PASSWORD_ENV_VAR: &str = "ESPRESSO_KEYSTORE_PASSWORD";
+
+/// A private key used to deploy or operate a rollup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollupKey {
+    /// Pays for the contract deployments
+    Deployer,
+    /// Signs the assertions of the validator
+    Validator,
+    /// Signs the batches posted to the parent chain
+    BatchPoster,
+}
+
+impl RollupKey {
+    /// Every key, in a fixed order
+    pub const ALL: [RollupKey; 3] = [
+        RollupKey::Deployer,
+        RollupKey::Validator,
+        RollupKey::BatchPoster,
+    ];
+
+    /// Environment variable holding the raw key when no keystore is used
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            RollupKey::Deployer => "DEPLOYER_PRIVATE_KEY",
+            RollupKey::Validator => "VALIDATOR_PRIVATE_KEY",
+            RollupKey::BatchPoster => "BATCH_POSTER_PRIVATE_KEY",
+        }
+    }
+
+    /// Name of the key's file in the keystore directory
+    pub fn keystore_file(&self) -> &'static str {
+        match self {
+            RollupKey::Deployer => "deployer.json",
+            RollupKey::Validator => "validator.json",
+            RollupKey::BatchPoster => "batch_poster.json",
+        }
+    }
+
+    /// Load the key, hex-encoded with a `0x` prefix
+    ///
+    /// Keys are decrypted from the Ethereum keystore file in `ESPRESSO_KEYSTORE` with the
+    /// passphrase in `ESPRESSO_KEYSTORE_PASSWORD`, so they never have to be put in the
+    /// environment. Keys without a keystore file are read from [`RollupKey::env_var`].
+    pub fn load(&self) -> Result<String> {
+        match keystore_path(*self) {
+            Some(path) => decrypt(&path),
+            None => std::env::var(self.env_var())
+                .map_err(|_| anyhow!("{} environment variable not set", self.env_var())),
+        }
+    }
+}
+
+/// Directory of a rollup its own node keys are kept in
+pub const NODE_KEYS_DIR_NAME: &str = "keys";
+
+/// Files holding the validator and batch poster keys of one rollup
+///
+/// Rollups record these paths rather than the keys, which stay in owner-only files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeKeys {
+    /// File holding the validator key
+    pub validator: PathBuf,
+    /// File holding the batch poster key
+    pub batch_poster: PathBuf,
+}
+
+/// Addresses of the node keys of a rollup, EIP-55 checksummed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeKeyAddresses {
+    /// Address of the validator key
+    pub validator: String,
+    /// Address of the batch poster key
+    pub batch_poster: String,
+}
+
+impl NodeKeys {
+    /// Key files of a rollup keeping its keys in `dir`
+    pub fn in_dir<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            validator: dir.as_ref().join("validator.key"),
+            batch_poster: dir.as_ref().join("batch_poster.key"),
+        }
+    }
+
+    /// The keys already provisioned in `dir`, or freshly generated ones if there are none
+    pub fn load_or_generate<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let keys = Self::in_dir(dir);
+        if !keys.validator.exists() && !keys.batch_poster.exists() {
+            keys.write(&random_key(), &random_key())?;
+        }
+        keys.addresses()?;
+        Ok(keys)
+    }
+
+    /// Keep `validator_key` and `batch_poster_key` in `dir`, replacing the keys there
+    pub fn import<P: AsRef<Path>>(
+        dir: P,
+        validator_key: &str,
+        batch_poster_key: &str,
+    ) -> Result<Self> {
+        let keys = Self::in_dir(dir);
+        keys.write(validator_key, batch_poster_key)?;
+        keys.addresses()?;
+        Ok(keys)
+    }
+
+    /// Replace the keys with freshly generated ones
+    ///
+    /// The previous keys are kept next to the new ones until [`NodeKeys::roll_back`] or the
+    /// next rotation.
+    pub fn rotate(&self) -> Result<()> {
+        for path in [&self.validator, &self.batch_poster] {
+            std::fs::rename(path, previous(path))
+                .map_err(|e| anyhow!("Failed to back up key {}: {}", path.display(), e))?;
+        }
+        self.write(&random_key(), &random_key())
+    }
+
+    /// Put back the keys replaced by the last [`NodeKeys::rotate`]
+    pub fn roll_back(&self) -> Result<()> {
+        for path in [&self.validator, &self.batch_poster] {
+            std::fs::rename(previous(path), path)
+                .map_err(|e| anyhow!("Failed to restore key {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Load `key`, hex-encoded with a `0x` prefix
+    pub fn load(&self, key: RollupKey) -> Result<String> {
+        let path = match key {
+            RollupKey::Validator => &self.validator,
+            RollupKey::BatchPoster => &self.batch_poster,
+            RollupKey::Deployer => {
+                return Err(anyhow!("Rollups have no deployer key of their own"));
+            }
+        };
+        let key = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read key {}: {}", path.display(), e))?;
+        Ok(key.trim().to_string())
+    }
+
+    /// Addresses of the keys, which must differ
+    pub fn addresses(&self) -> Result<NodeKeyAddresses> {
+        let address = |key| -> Result<String> {
+            let signer = self
+                .load(key)?
+                .parse::<PrivateKeySigner>()
+                .map_err(|e| anyhow!("Invalid {:?} key: {}", key, e))?;
+            Ok(signer.address().to_checksum(None))
+        };
+        let addresses = NodeKeyAddresses {
+            validator: address(RollupKey::Validator)?,
+            batch_poster: address(RollupKey::BatchPoster)?,
+        };
+        if addresses.validator == addresses.batch_poster {
+            return Err(anyhow!(
+                "The validator and batch poster must have distinct keys"
+            ));
+        }
+        Ok(addresses)
+    }
+
+    /// Write both keys to their files
+    fn write(&self, validator_key: &str, batch_poster_key: &str) -> Result<()> {
+        for (path, key) in [
+            (&self.validator, validator_key),
+            (&self.batch_poster, batch_poster_key),
+        ] {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            write_secret_file(path, key)
+                .map_err(|e| anyhow!("Failed to write key {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// A new random private key, hex-encoded with a `0x` prefix
+fn random_key() -> String {
+    format!("0x{}", hex::encode(PrivateKeySigner::random().to_bytes()))
+}
+
+/// Where the key file at `path` is kept during a rotation
+fn previous(path: &Path) -> PathBuf {
+    path.with_extension("key.previous")
+}
+
+/// Write a file holding secrets, readable by the owner only
+pub(crate) fn write_secret_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files, tighten an existing one first
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+/// Keystore file of `key`, if a keystore is configured and holds it
+fn keystore_path(key: RollupKey) -> Option<PathBuf> {
+    let dir = std::env::var(KEYSTORE_ENV_VAR).ok()?;
+    let path = PathBuf::from(dir).join(key.keystore_file());
+    path.is_file().then_some(path)
+}
+
+#[cfg(feature = "keystore")]
+fn decrypt(path: &Path) -> Result<String> {
+    use alloy::signers::local::PrivateKeySigner;
+
+    let password = std::env::var(KEYSTORE_PASSWORD_ENV_VAR)
+        .map_err(|_| anyhow!("{} environment variable not set", KEYSTORE_PASSWORD_ENV_VAR))?;
+    let signer = PrivateKeySigner::decrypt_keystore(path, password)
+        .map_err(|e| anyhow!("Failed to decrypt keystore {}: {}", path.display(), e))?;
+    Ok(format!("0x{}", hex::encode(signer.to_bytes())))
+}
+
+#[cfg(not(feature = "keystore"))]
+fn decrypt(path: &Path) -> Result<String> {
+    Err(anyhow!(
+        "Can't decrypt keystore {}, built without the `keystore` feature",
+        path.display()
+    ))
+}
@@ -0,0 +1,72 @@
+use crate::deployer::{Deploy, DeploymentResult};
+use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// [`Deploy`] returning canned addresses, for exercising rollups without deploying anything
+#[derive(Debug)]
+pub struct MockDeployer {
+    result: Result<DeploymentResult, String>,
+    delay: Duration,
+    deployments: AtomicUsize,
+}
+
+impl MockDeployer {
+    /// A deployer reporting `result` for every deployment
+    pub fn new(result: DeploymentResult) -> Self {
+        Self {
+            result: Ok(result),
+            delay: Duration::ZERO,
+            deployments: AtomicUsize::new(0),
+        }
+    }
+
+    /// A deployer failing every deployment with `error`
+    pub fn failing(error: &str) -> Self {
+        Self {
+            result: Err(error.to_string()),
+            delay: Duration::ZERO,
+            deployments: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take `delay` to deploy, like a build would
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Number of deployments run so far
+    pub fn deployments(&self) -> usize {
+        self.deployments.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for MockDeployer {
+    /// A deployer reporting distinct placeholder addresses at block 1
+    fn default() -> Self {
+        let address = |byte: u8| format!("0x{}", hex::encode([byte; 20]));
+        Self::new(DeploymentResult {
+            rollup_creator_address: address(0x11),
+            rollup_proxy_address: address(0x22),
+            upgrade_executor_address: address(0x33),
+            deployment_block: 1,
+            chain_id: 412346,
+            bridge_address: address(0x44),
+            inbox_address: address(0x55),
+            sequencer_inbox_address: address(0x66),
+            validator_utils_address: address(0x77),
+            validator_wallet_creator_address: address(0x88),
+            artifact: None,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Deploy for MockDeployer {
+    async fn deploy(&self) -> Result<DeploymentResult> {
+        self.deployments.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        self.result.clone().map_err(|e| anyhow!(e))
+    }
+}
@@ -1,16 +1,47 @@
+use serde::{Deserialize, Serialize};
+
 pub mod config;
+pub mod keys;
+pub mod mock;
+pub mod node_flags;
 pub mod rollup;
 
 // Re-export important types
 pub use config::ConfigGenerator;
+pub use keys::RollupKey;
+pub use mock::MockDeployer;
+pub use node_flags::{LogLevel, NodeFlags, PruneMode};
 pub use rollup::RollupDeployer;
 
 /// Structure to hold deployment results
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeploymentResult {
     pub rollup_creator_address: String,
     pub rollup_proxy_address: String,
     pub upgrade_executor_address: String,
     pub deployment_block: u64,
     pub chain_id: u64,
+    // Core contracts, missing from results persisted by older versions
+    #[serde(default)]
+    pub bridge_address: String,
+    #[serde(default)]
+    pub inbox_address: String,
+    #[serde(default)]
+    pub sequencer_inbox_address: String,
+    #[serde(default)]
+    pub validator_utils_address: String,
+    #[serde(default)]
+    pub validator_wallet_creator_address: String,
+    /// Contents of the deployments file the scripts wrote, unset if they only printed addresses
+    #[serde(default)]
+    pub artifact: Option<serde_json::Value>,
+}
+
+/// Deploys the contracts of a rollup
+///
+/// Implemented by [`RollupDeployer`] and, without any toolchain or funds, [`MockDeployer`].
+#[async_trait::async_trait]
+pub trait Deploy: Send + Sync {
+    /// Run the whole deployment
+    async fn deploy(&self) -> anyhow::Result<DeploymentResult>;
 }
@@ -0,0 +1,126 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Database pruning mode applied by nitro on startup
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneMode {
+    /// Keep only the state needed to serve the latest blocks
+    Minimal,
+    /// Prune everything that isn't needed to follow the chain
+    Full,
+    /// Keep the state the block validator still needs
+    Validator,
+}
+
+/// Log verbosity of the nitro node
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Crit,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Typed subset of the nitro node flags that are commonly tweaked per rollup
+///
+/// Every field is optional; unset fields keep whatever the bundled
+/// `full_node.json` template specifies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NodeFlags {
+    /// Keep all historical state (`execution.caching.archive`)
+    pub archive: Option<bool>,
+    /// Size of the clean trie cache in MB (`execution.caching.trie-clean-cache`)
+    pub trie_clean_cache_mb: Option<u32>,
+    /// Prune the database on startup (`init.prune`)
+    pub prune: Option<PruneMode>,
+    /// RPC modules exposed over HTTP and WS (`http.api`, `ws.api`)
+    pub rpc_modules: Option<Vec<String>>,
+    /// Log level (`log-level`)
+    pub log_level: Option<LogLevel>,
+}
+
+impl NodeFlags {
+    /// Check that the flags can be applied together
+    pub fn validate(&self) -> Result<()> {
+        if self.archive == Some(true) && self.prune.is_some() {
+            return Err(anyhow!(
+                "Node flags `archive` and `prune` are mutually exclusive"
+            ));
+        }
+        if self
+            .rpc_modules
+            .as_ref()
+            .is_some_and(|modules| modules.is_empty())
+        {
+            return Err(anyhow!("Node flag `rpc_modules` must not be empty"));
+        }
+        Ok(())
+    }
+
+    /// Merge the flags over a parsed `full_node.json`, overriding template defaults
+    pub fn apply(&self, config: &mut Value) -> Result<()> {
+        self.validate()?;
+        merge(config, self.to_overrides());
+        Ok(())
+    }
+
+    /// Build the nitro config fragment for the flags that are set
+    fn to_overrides(&self) -> Value {
+        let mut overrides = Map::new();
+
+        let mut caching = Map::new();
+        if let Some(archive) = self.archive {
+            caching.insert("archive".to_string(), archive.into());
+        }
+        if let Some(cache) = self.trie_clean_cache_mb {
+            caching.insert("trie-clean-cache".to_string(), cache.into());
+        }
+        if !caching.is_empty() {
+            let mut execution = Map::new();
+            execution.insert("caching".to_string(), Value::Object(caching));
+            overrides.insert("execution".to_string(), Value::Object(execution));
+        }
+
+        if let Some(prune) = self.prune {
+            let mut init = Map::new();
+            init.insert("prune".to_string(), to_value(prune));
+            overrides.insert("init".to_string(), Value::Object(init));
+        }
+
+        if let Some(modules) = &self.rpc_modules {
+            for endpoint in ["http", "ws"] {
+                let mut api = Map::new();
+                api.insert("api".to_string(), modules.clone().into());
+                overrides.insert(endpoint.to_string(), Value::Object(api));
+            }
+        }
+
+        if let Some(level) = self.log_level {
+            overrides.insert("log-level".to_string(), to_value(level));
+        }
+
+        Value::Object(overrides)
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Value {
+    // Unit enums always serialize to a string
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Recursively merge `overrides` into `base`, replacing non-object values
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
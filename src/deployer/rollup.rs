@@ -1,17 +1,341 @@
 use blueprint_sdk as sdk;
 
-use crate::RollupConfig;
+use crate::deployer::config::{
+    checksum_address, checksum_hex_address, is_address, validate_rpc_url,
+};
+use crate::deployer::keys::{RollupKey, write_secret_file};
+use crate::deployer::{Deploy, DeploymentResult};
+use crate::metrics::metrics;
+use crate::{NetworkType, RollupConfig};
+use alloy::primitives::utils::format_ether;
+use alloy::primitives::{Address, U256};
+use alloy::signers::local::PrivateKeySigner;
 use anyhow::{Result, anyhow};
-use sdk::{error, info};
+use regex::Regex;
+use sdk::{error, info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::{mpsc, watch};
 
 // Constants for deployment
 const NITRO_CONTRACTS_REPO: &str = "https://github.com/EspressoSystems/nitro-contracts.git";
 const NITRO_CONTRACTS_BRANCH: &str = "develop";
-const TEE_VERIFIER_ADDRESS: &str = "0x8354db765810dF8F24f1477B06e91E5b17a408bF";
+pub(crate) const TEE_VERIFIER_ADDRESS: &str = "0x8354db765810dF8F24f1477B06e91E5b17a408bF";
+pub(crate) const LIGHT_CLIENT_ADDRESS: &str = "0x08d16cb8243b3e172dddcdf1a1a5dacca1cd7098";
+
+/// Default upper bound for a single git, yarn, forge or hardhat invocation
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Default number of parent chain blocks, the deployment's included, to wait for
+/// before generating configs. Geth devnets only mine on demand and don't wait
+pub const DEFAULT_CONFIRMATIONS: u64 = 5;
+
+/// Default upper bound for waiting for the confirmations of a deployment
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(900);
+
+/// Default least balance, in wei, the deployer needs before anything is built: 0.05 ETH
+pub const DEFAULT_MIN_DEPLOYER_BALANCE: U256 = U256::from_limbs([50_000_000_000_000_000, 0, 0, 0]);
+
+/// File in a rollup's config directory the deployment artifact is copied to
+pub const DEPLOYMENT_ARTIFACT_FILE_NAME: &str = "deployment.json";
+
+/// File in the workspace recording the deployment phases that completed
+pub const DEPLOY_STATE_FILE_NAME: &str = "deploy_state.json";
+
+/// Time between two checks of the parent chain head while waiting for confirmations
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// File in the contracts cache recording the repository and ref it was built from
+const CONTRACTS_CACHE_MARKER: &str = ".nitro-contracts-source";
+
+/// Serializes builds of the shared contracts cache
+static CONTRACTS_CACHE_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// Keys a [`DeploymentConfig::from_env_file`] file must set
+pub const ENV_FILE_REQUIRED_KEYS: [&str; 9] = [
+    "DEPLOYER_PRIVATE_KEY",
+    "ARBISCAN_API_KEY",
+    "CHAIN_ID",
+    "NETWORK",
+    "INITIAL_CHAIN_OWNER",
+    "VALIDATORS",
+    "BATCH_POSTER_ADDRESS",
+    "BATCH_POSTER_MANAGER",
+    "WORKSPACE_DIR",
+];
+
+/// Keys a [`DeploymentConfig::from_env_file`] file may set, defaulting like [`DeploymentConfig::new`]
+pub const ENV_FILE_OPTIONAL_KEYS: [&str; 15] = [
+    "RPC_URL",
+    "IS_MAINNET",
+    "DRY_RUN",
+    "NITRO_CONTRACTS_REPO",
+    "NITRO_CONTRACTS_REF",
+    "COMMAND_TIMEOUT_SECS",
+    "RETRY_MAX_ATTEMPTS",
+    "RETRY_BASE_DELAY_SECS",
+    "ESPRESSO_TEE_VERIFIER_ADDRESS",
+    "ESPRESSO_CONTRACTS_CACHE",
+    "ESPRESSO_DEPLOYMENT_CONFIRMATIONS",
+    "CONFIRMATION_TIMEOUT_SECS",
+    "ESPRESSO_MIN_DEPLOYER_BALANCE_WEI",
+    "ESPRESSO_MAX_FEE_PER_GAS_WEI",
+    "ESPRESSO_MAX_PRIORITY_FEE_PER_GAS_WEI",
+];
+
+/// Fail unless `deployer` holds at least `minimum` wei
+pub fn check_balance(deployer: Address, balance: U256, minimum: U256) -> Result<()> {
+    if balance < minimum {
+        return Err(anyhow!(
+            "Deployer {} has {} ETH, needs at least {} ETH",
+            deployer,
+            format_ether(balance),
+            format_ether(minimum)
+        ));
+    }
+    Ok(())
+}
+
+/// Environment variable the deployment scripts read the max fee per gas from, in wei
+pub const MAX_FEE_PER_GAS_ENV_VAR: &str = "MAX_FEE_PER_GAS";
+
+/// Environment variable the deployment scripts read the max priority fee per gas from, in wei
+pub const MAX_PRIORITY_FEE_PER_GAS_ENV_VAR: &str = "MAX_PRIORITY_FEE_PER_GAS";
+
+/// Check that the fee caps of the deployment transactions, in wei, are nonzero and that the
+/// priority fee doesn't exceed the max fee
+pub fn validate_fees(
+    max_fee_per_gas: Option<u128>,
+    max_priority_fee_per_gas: Option<u128>,
+) -> Result<()> {
+    if max_fee_per_gas == Some(0) {
+        return Err(anyhow!("Max fee per gas must be nonzero"));
+    }
+    if max_priority_fee_per_gas == Some(0) {
+        return Err(anyhow!("Max priority fee per gas must be nonzero"));
+    }
+    if let (Some(max_fee), Some(priority_fee)) = (max_fee_per_gas, max_priority_fee_per_gas)
+        && priority_fee > max_fee
+    {
+        return Err(anyhow!(
+            "Max priority fee per gas {} exceeds the max fee per gas {}",
+            priority_fee,
+            max_fee
+        ));
+    }
+    Ok(())
+}
+
+/// Errors from the deployment scripts that retrying can't fix
+const FATAL_ERRORS: [&str; 4] = [
+    "revert",
+    "insufficient funds",
+    "invalid opcode",
+    "out of gas",
+];
+
+/// Errors from the deployment scripts caused by the RPC or the network
+const RETRYABLE_ERRORS: [&str; 14] = [
+    "timeout",
+    "timed out",
+    "etimedout",
+    "econnreset",
+    "econnrefused",
+    "enotfound",
+    "socket hang up",
+    "network error",
+    "nonce too low",
+    "nonce has already been used",
+    "replacement transaction underpriced",
+    "rate limit",
+    "too many requests",
+    "429",
+];
+
+/// Address reported for every contract by a dry run
+const DRY_RUN_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Number of trailing output lines included when parsing deployment output fails
+const OUTPUT_TAIL_LINES: usize = 20;
+
+// Patterns for the deployment script output
+static ANSI_ESCAPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").expect("valid regex"));
+static ROLLUP_CREATOR_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"RollupCreator deployed to:?\s+(0x[a-fA-F0-9]{40})").expect("valid regex")
+});
+static ROLLUP_PROXY_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"RollupProxy Contract created at address:?\s+(0x[a-fA-F0-9]{40})")
+        .expect("valid regex")
+});
+static UPGRADE_EXECUTOR_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"UpgradeExecutor(?: \(proxy\))? Contract created at address:?\s+(0x[a-fA-F0-9]{40})",
+    )
+    .expect("valid regex")
+});
+static BRIDGE_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\bBridge(?: \(proxy\))? Contract created at address:?\s+(0x[a-fA-F0-9]{40})")
+        .expect("valid regex")
+});
+static INBOX_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\bInbox(?: \(proxy\))? Contract created at address:?\s+(0x[a-fA-F0-9]{40})")
+        .expect("valid regex")
+});
+static SEQUENCER_INBOX_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"SequencerInbox(?: \(proxy\))? Contract created at address:?\s+(0x[a-fA-F0-9]{40})")
+        .expect("valid regex")
+});
+static VALIDATOR_UTILS_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"ValidatorUtils Contract created at address:?\s+(0x[a-fA-F0-9]{40})")
+        .expect("valid regex")
+});
+static VALIDATOR_WALLET_CREATOR_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"ValidatorWalletCreator Contract created at address:?\s+(0x[a-fA-F0-9]{40})")
+        .expect("valid regex")
+});
+static DEPLOYMENT_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"All deployed at block number:?\s+(\d+)").expect("valid regex"));
+
+/// How often a failing deployment script, or Docker connection, is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts before giving up, including the first one
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for every further one
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait after the failed `attempt`, counting from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+/// Wait until `block` has `confirmations` blocks on top of it, counting itself
+///
+/// `latest_block` is polled every `poll_interval`. Its errors are retried, the RPC
+/// being unreachable for a moment shouldn't fail a deployment that already succeeded.
+/// Fails once `timeout` elapsed without enough confirmations.
+pub async fn wait_for_confirmations<F, Fut>(
+    block: u64,
+    confirmations: u64,
+    timeout: Duration,
+    poll_interval: Duration,
+    mut latest_block: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<u64>>,
+{
+    let target = block.saturating_add(confirmations.saturating_sub(1));
+    let started = Instant::now();
+    let mut latest = None;
+    loop {
+        match latest_block().await {
+            Ok(number) if number >= target => return Ok(()),
+            Ok(number) => latest = Some(number),
+            Err(e) => warn!("Failed to get the latest parent chain block: {}", e),
+        }
+        if started.elapsed() >= timeout {
+            return Err(anyhow!(
+                "Block {} didn't reach {} confirmations within {}s, latest block: {}",
+                block,
+                confirmations,
+                timeout.as_secs(),
+                latest.map_or_else(|| "unknown".to_string(), |number| number.to_string())
+            ));
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Whether a deployment script failing with `error` is worth running again
+///
+/// Reverts and missing funds are fatal even when the output also mentions a
+/// network problem.
+pub fn is_retryable(error: &str) -> bool {
+    let error = error.to_lowercase();
+    !FATAL_ERRORS.iter().any(|fatal| error.contains(fatal))
+        && RETRYABLE_ERRORS
+            .iter()
+            .any(|retryable| error.contains(retryable))
+}
+
+/// Error a deployment fails with once cancelled, see [`RollupDeployer::with_cancellation`]
+#[derive(Debug, thiserror::Error)]
+#[error("Deployment cancelled")]
+pub struct DeployCancelled;
+
+/// Wait until `cancel` is set, forever if it's dropped without being set
+pub async fn cancelled(mut cancel: watch::Receiver<bool>) {
+    if cancel.wait_for(|cancelled| *cancelled).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Step of a deployment run by [`RollupDeployer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeployPhase {
+    /// Checking the config and cloning the contracts, [`RollupDeployer::prepare`]
+    Prepare,
+    /// Building the contracts, [`RollupDeployer::build`]
+    Build,
+    /// Deploying the rollup creator, [`RollupDeployer::deploy_creator`]
+    DeployCreator,
+    /// Creating the rollup, [`RollupDeployer::deploy_rollup`]
+    DeployRollup,
+    /// Waiting for confirmations, [`RollupDeployer::confirm_deployment`]
+    Confirm,
+}
+
+/// Event of a deployment in progress, see [`RollupDeployer::with_progress`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeployProgress {
+    /// The phase started
+    PhaseStarted(DeployPhase),
+    /// The phase completed
+    PhaseFinished(DeployPhase),
+    /// The phase failed with the error
+    PhaseFailed(DeployPhase, String),
+    /// A line a deployment command printed
+    Output(String),
+    /// Hash of a transaction the deployment scripts reported
+    Transaction(String),
+}
+
+/// A 32-byte hash on a line about a transaction
+static TRANSACTION_HASH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:tx|transaction)\b.*?\b(0x[0-9a-f]{64})\b").expect("valid regex")
+});
+
+/// Hash of the transaction `line` of script output reports, if any
+pub fn transaction_hash(line: &str) -> Option<String> {
+    TRANSACTION_HASH
+        .captures(&strip_ansi(line))
+        .and_then(|captures| captures.get(1))
+        .map(|hash| hash.as_str().to_string())
+}
 
 // Deployment configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,12 +343,45 @@ pub struct DeploymentConfig {
     pub private_key: String,
     pub arbiscan_api_key: String,
     pub chain_id: u64,
-    pub network: String,
+    pub network: NetworkType,
+    /// RPC endpoint of the parent chain the contracts are deployed through, handed to hardhat
+    ///
+    /// The nodes may use another one, see [`NetworkType::node_rpc_url`].
+    pub rpc_url: String,
+    /// Confirms that deploying to a mainnet network is intended
+    pub is_mainnet: bool,
     pub initial_chain_owner: [u8; 20],
     pub validators: Vec<[u8; 20]>,
     pub batch_poster_address: [u8; 20],
     pub batch_poster_manager: [u8; 20],
     pub workspace_dir: PathBuf,
+    /// Git URL of the nitro-contracts repository
+    pub contracts_repo: String,
+    /// Branch, tag or commit SHA of the contracts to deploy
+    pub contracts_ref: String,
+    /// Time after which a deployment command is killed
+    pub command_timeout: Duration,
+    /// Retries of the hardhat deployment scripts on transient errors
+    pub retry_policy: RetryPolicy,
+    /// Stop short of the deployment scripts and report zero addresses
+    pub dry_run: bool,
+    /// Espresso TEE verifier to use instead of the default one
+    pub tee_verifier_address: Option<String>,
+    /// Directory the contracts are cloned and built in once, then copied from
+    pub contracts_cache: Option<PathBuf>,
+    /// Parent chain blocks, the deployment's included, to wait for before generating configs
+    pub confirmations: u64,
+    /// Time after which waiting for the confirmations fails the deployment
+    pub confirmation_timeout: Duration,
+    /// Least balance, in wei, the deployer needs on the parent chain, zero skips the check
+    pub min_deployer_balance: U256,
+    /// Redo every phase instead of resuming after the ones a previous attempt completed
+    pub force_clean: bool,
+    /// Max fee per gas of the deployment transactions in wei, the scripts estimate it when unset
+    pub max_fee_per_gas: Option<u128>,
+    /// Max priority fee per gas of the deployment transactions in wei, the scripts estimate it
+    /// when unset
+    pub max_priority_fee_per_gas: Option<u128>,
 }
 
 impl DeploymentConfig {
@@ -42,72 +399,757 @@ impl DeploymentConfig {
             validators: rollup_config.validators.to_vec(),
             batch_poster_address: rollup_config.batch_poster_address,
             batch_poster_manager: rollup_config.batch_poster_manager,
-            network: rollup_config.network.to_string(),
+            network: rollup_config.network,
+            rpc_url: rollup_config.network.resolved_rpc_url(),
+            is_mainnet: rollup_config.is_mainnet,
             workspace_dir,
+            contracts_repo: std::env::var("NITRO_CONTRACTS_REPO")
+                .unwrap_or_else(|_| NITRO_CONTRACTS_REPO.to_string()),
+            contracts_ref: std::env::var("NITRO_CONTRACTS_REF")
+                .unwrap_or_else(|_| NITRO_CONTRACTS_BRANCH.to_string()),
+            command_timeout: DEFAULT_COMMAND_TIMEOUT,
+            retry_policy: RetryPolicy::default(),
+            dry_run: rollup_config.dry_run,
+            tee_verifier_address: tee_verifier_address_override(),
+            contracts_cache: std::env::var("ESPRESSO_CONTRACTS_CACHE")
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+            confirmations: std::env::var("ESPRESSO_DEPLOYMENT_CONFIRMATIONS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(match rollup_config.network {
+                    NetworkType::Geth => 0,
+                    _ => DEFAULT_CONFIRMATIONS,
+                }),
+            confirmation_timeout: DEFAULT_CONFIRMATION_TIMEOUT,
+            min_deployer_balance: std::env::var("ESPRESSO_MIN_DEPLOYER_BALANCE_WEI")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_MIN_DEPLOYER_BALANCE),
+            force_clean: false,
+            max_fee_per_gas: std::env::var("ESPRESSO_MAX_FEE_PER_GAS_WEI")
+                .ok()
+                .and_then(|value| value.parse().ok()),
+            max_priority_fee_per_gas: std::env::var("ESPRESSO_MAX_PRIORITY_FEE_PER_GAS_WEI")
+                .ok()
+                .and_then(|value| value.parse().ok()),
+        }
+    }
+
+    /// Read the config from a dotenv-style file
+    ///
+    /// See [`ENV_FILE_REQUIRED_KEYS`] and [`ENV_FILE_OPTIONAL_KEYS`] for the keys. Addresses
+    /// are 0x-prefixed hex, `VALIDATORS` is comma-separated. Unknown keys are rejected so a
+    /// typo can't silently fall back to a default, and the process environment is never read.
+    pub fn from_env_file(path: &Path) -> Result<Self> {
+        let mut values = HashMap::new();
+        let entries = dotenv::from_path_iter(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        for entry in entries {
+            let (key, value) =
+                entry.map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+            values.insert(key, value);
+        }
+
+        let mut unknown: Vec<&str> = values
+            .keys()
+            .map(String::as_str)
+            .filter(|key| {
+                !ENV_FILE_REQUIRED_KEYS.contains(key) && !ENV_FILE_OPTIONAL_KEYS.contains(key)
+            })
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort_unstable();
+            return Err(anyhow!(
+                "Unknown keys in {}: {}",
+                path.display(),
+                unknown.join(", ")
+            ));
+        }
+        let missing: Vec<&str> = ENV_FILE_REQUIRED_KEYS
+            .into_iter()
+            .filter(|key| values.get(*key).is_none_or(|value| value.is_empty()))
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "Missing keys in {}: {}",
+                path.display(),
+                missing.join(", ")
+            ));
+        }
+
+        let get = |key: &str| {
+            values
+                .get(key)
+                .map(String::as_str)
+                .filter(|v| !v.is_empty())
+        };
+        let required = |key: &str| get(key).unwrap_or_default();
+        let address = |key: &str, value: &str| -> Result<[u8; 20]> {
+            let address: Address = value
+                .trim()
+                .parse()
+                .map_err(|e| anyhow!("Invalid {} {:?}: {}", key, value, e))?;
+            Ok(address.into())
+        };
+        let number = |key: &str| -> Result<Option<u64>> {
+            get(key)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|e| anyhow!("Invalid {} {:?}: {}", key, value, e))
+                })
+                .transpose()
+        };
+        let wei = |key: &str| -> Result<Option<u128>> {
+            get(key)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|e| anyhow!("Invalid {} {:?}: {}", key, value, e))
+                })
+                .transpose()
+        };
+        let flag = |key: &str| -> Result<bool> {
+            match get(key) {
+                None | Some("false" | "0") => Ok(false),
+                Some("true" | "1") => Ok(true),
+                Some(value) => Err(anyhow!(
+                    "Invalid {} {:?}, expected true or false",
+                    key,
+                    value
+                )),
+            }
+        };
+
+        let network: NetworkType = required("NETWORK").parse()?;
+        let config = Self {
+            private_key: required("DEPLOYER_PRIVATE_KEY").to_string(),
+            arbiscan_api_key: required("ARBISCAN_API_KEY").to_string(),
+            chain_id: number("CHAIN_ID")?.unwrap_or_default(),
+            network,
+            rpc_url: get("RPC_URL")
+                .map(str::to_string)
+                .unwrap_or_else(|| network.rpc_url().to_string()),
+            is_mainnet: flag("IS_MAINNET")?,
+            initial_chain_owner: address("INITIAL_CHAIN_OWNER", required("INITIAL_CHAIN_OWNER"))?,
+            validators: required("VALIDATORS")
+                .split(',')
+                .map(|validator| address("VALIDATORS", validator))
+                .collect::<Result<_>>()?,
+            batch_poster_address: address(
+                "BATCH_POSTER_ADDRESS",
+                required("BATCH_POSTER_ADDRESS"),
+            )?,
+            batch_poster_manager: address(
+                "BATCH_POSTER_MANAGER",
+                required("BATCH_POSTER_MANAGER"),
+            )?,
+            workspace_dir: PathBuf::from(required("WORKSPACE_DIR")),
+            contracts_repo: get("NITRO_CONTRACTS_REPO")
+                .unwrap_or(NITRO_CONTRACTS_REPO)
+                .to_string(),
+            contracts_ref: get("NITRO_CONTRACTS_REF")
+                .unwrap_or(NITRO_CONTRACTS_BRANCH)
+                .to_string(),
+            command_timeout: number("COMMAND_TIMEOUT_SECS")?
+                .map_or(DEFAULT_COMMAND_TIMEOUT, Duration::from_secs),
+            retry_policy: RetryPolicy {
+                max_attempts: match number("RETRY_MAX_ATTEMPTS")? {
+                    Some(attempts) => u32::try_from(attempts)
+                        .map_err(|_| anyhow!("Invalid RETRY_MAX_ATTEMPTS {}", attempts))?,
+                    None => RetryPolicy::default().max_attempts,
+                },
+                base_delay: number("RETRY_BASE_DELAY_SECS")?
+                    .map_or(RetryPolicy::default().base_delay, Duration::from_secs),
+            },
+            dry_run: flag("DRY_RUN")?,
+            tee_verifier_address: get("ESPRESSO_TEE_VERIFIER_ADDRESS").map(str::to_string),
+            contracts_cache: get("ESPRESSO_CONTRACTS_CACHE").map(PathBuf::from),
+            confirmations: number("ESPRESSO_DEPLOYMENT_CONFIRMATIONS")?.unwrap_or(match network {
+                NetworkType::Geth => 0,
+                _ => DEFAULT_CONFIRMATIONS,
+            }),
+            confirmation_timeout: number("CONFIRMATION_TIMEOUT_SECS")?
+                .map_or(DEFAULT_CONFIRMATION_TIMEOUT, Duration::from_secs),
+            min_deployer_balance: match get("ESPRESSO_MIN_DEPLOYER_BALANCE_WEI") {
+                Some(value) => value.parse().map_err(|e| {
+                    anyhow!(
+                        "Invalid ESPRESSO_MIN_DEPLOYER_BALANCE_WEI {:?}: {}",
+                        value,
+                        e
+                    )
+                })?,
+                None => DEFAULT_MIN_DEPLOYER_BALANCE,
+            },
+            force_clean: false,
+            max_fee_per_gas: wei("ESPRESSO_MAX_FEE_PER_GAS_WEI")?,
+            max_priority_fee_per_gas: wei("ESPRESSO_MAX_PRIORITY_FEE_PER_GAS_WEI")?,
+        };
+        config.tee_verifier_address()?;
+        validate_fees(config.max_fee_per_gas, config.max_priority_fee_per_gas)?;
+        Ok(config)
+    }
+
+    /// The TEE verifier the rollup is deployed against, checked to be an address
+    pub fn tee_verifier_address(&self) -> Result<&str> {
+        match &self.tee_verifier_address {
+            Some(address) if !is_address(address) => Err(anyhow!(
+                "Invalid TEE verifier address {:?}, expected a 0x-prefixed 20-byte hex address",
+                address
+            )),
+            Some(address) => Ok(address),
+            None => Ok(TEE_VERIFIER_ADDRESS),
+        }
+    }
+}
+
+/// TEE verifier set in `ESPRESSO_TEE_VERIFIER_ADDRESS`, if any
+pub fn tee_verifier_address_override() -> Option<String> {
+    std::env::var("ESPRESSO_TEE_VERIFIER_ADDRESS")
+        .ok()
+        .filter(|address| !address.is_empty())
+}
+
+/// Deployment phases completed in a workspace, see [`DEPLOY_STATE_FILE_NAME`]
+///
+/// A retried deployment skips the phases recorded here, as long as it deploys the same
+/// contracts to the same network.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeployState {
+    /// Contracts repository and ref the phases ran with, as `<repo>@<ref>`
+    pub contracts: String,
+    /// Network the phases ran against
+    pub network: String,
+    /// The contracts repository is cloned
+    #[serde(default)]
+    pub repo_cloned: bool,
+    /// The contracts are built and the files the scripts read are written
+    #[serde(default)]
+    pub contracts_built: bool,
+    /// Address of the deployed rollup creator
+    #[serde(default)]
+    pub rollup_creator_address: Option<String>,
+}
+
+impl DeployState {
+    /// An empty state for the contracts and network of `config`
+    pub fn for_config(config: &DeploymentConfig) -> Self {
+        Self {
+            contracts: format!("{}@{}", config.contracts_repo, config.contracts_ref),
+            network: config.network.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the phases recorded here apply to a deployment with `config`
+    pub fn matches(&self, config: &DeploymentConfig) -> bool {
+        let fresh = Self::for_config(config);
+        self.contracts == fresh.contracts && self.network == fresh.network
+    }
+
+    /// Read a state file, returning `None` if it doesn't exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        let state = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+        Ok(Some(state))
+    }
+
+    /// Write the state file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Addresses recorded in `espresso-deployments/<network>.json`
+///
+/// Older scripts wrote PascalCase keys, which are accepted as aliases.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EspressoDeployments {
+    /// RollupCreator contract address
+    #[serde(alias = "RollupCreator")]
+    pub rollup_creator: Option<String>,
+    /// UpgradeExecutor contract address
+    #[serde(alias = "UpgradeExecutor")]
+    pub upgrade_executor: Option<String>,
+    /// Rollup proxy contract address
+    #[serde(alias = "RollupProxy")]
+    pub rollup_proxy: Option<String>,
+    /// Block the rollup was deployed at
+    #[serde(alias = "DeploymentBlock")]
+    pub deployment_block: Option<u64>,
+    /// Bridge contract address
+    #[serde(alias = "Bridge")]
+    pub bridge: Option<String>,
+    /// Inbox contract address
+    #[serde(alias = "Inbox")]
+    pub inbox: Option<String>,
+    /// SequencerInbox contract address
+    #[serde(alias = "SequencerInbox")]
+    pub sequencer_inbox: Option<String>,
+    /// ValidatorUtils contract address
+    #[serde(alias = "ValidatorUtils")]
+    pub validator_utils: Option<String>,
+    /// ValidatorWalletCreator contract address
+    #[serde(alias = "ValidatorWalletCreator")]
+    pub validator_wallet_creator: Option<String>,
+}
+
+impl EspressoDeployments {
+    /// Read a deployments file, returning `None` if it doesn't exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
         }
+
+        let content = fs::read_to_string(path)?;
+        let deployments = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+        Ok(Some(deployments))
+    }
+
+    /// Collect whatever addresses the deployment scripts printed
+    pub fn from_output(output: &str) -> Self {
+        Self {
+            rollup_creator: extract_rollup_creator_address(output).ok(),
+            upgrade_executor: extract_upgrade_executor_address(output).ok(),
+            rollup_proxy: extract_rollup_proxy_address(output).ok(),
+            deployment_block: extract_deployment_block(output).ok(),
+            bridge: capture(&BRIDGE_ADDRESS, output, "bridge address").ok(),
+            inbox: capture(&INBOX_ADDRESS, output, "inbox address").ok(),
+            sequencer_inbox: capture(&SEQUENCER_INBOX_ADDRESS, output, "sequencer inbox address")
+                .ok(),
+            validator_utils: capture(&VALIDATOR_UTILS_ADDRESS, output, "validator utils address")
+                .ok(),
+            validator_wallet_creator: capture(
+                &VALIDATOR_WALLET_CREATOR_ADDRESS,
+                output,
+                "validator wallet creator address",
+            )
+            .ok(),
+        }
+    }
+
+    /// Build the result of a deployment, failing if any address is missing
+    pub fn into_deployment_result(
+        self,
+        rollup_creator_address: String,
+        chain_id: u64,
+    ) -> Result<DeploymentResult> {
+        let required = |value: Option<String>, what| value.ok_or_else(|| missing_deployment(what));
+        Ok(DeploymentResult {
+            rollup_creator_address,
+            rollup_proxy_address: required(self.rollup_proxy, "rollup proxy address")?,
+            upgrade_executor_address: required(self.upgrade_executor, "upgrade executor address")?,
+            deployment_block: self
+                .deployment_block
+                .ok_or_else(|| missing_deployment("deployment block"))?,
+            chain_id,
+            bridge_address: required(self.bridge, "bridge address")?,
+            inbox_address: required(self.inbox, "inbox address")?,
+            sequencer_inbox_address: required(self.sequencer_inbox, "sequencer inbox address")?,
+            validator_utils_address: required(self.validator_utils, "validator utils address")?,
+            validator_wallet_creator_address: required(
+                self.validator_wallet_creator,
+                "validator wallet creator address",
+            )?,
+            artifact: None,
+        })
     }
 }
 
 /// Automated deployer for rollup contracts
 pub struct RollupDeployer {
     config: DeploymentConfig,
+    progress: Option<mpsc::Sender<DeployProgress>>,
+    cancel: Option<watch::Receiver<bool>>,
 }
 
 impl RollupDeployer {
     pub fn new(config: DeploymentConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            progress: None,
+            cancel: None,
+        }
+    }
+
+    /// Send [`DeployProgress`] events to `progress` as the deployment goes
+    ///
+    /// Events are dropped rather than holding up the deployment when the receiver
+    /// falls behind or is gone.
+    pub fn with_progress(mut self, progress: mpsc::Sender<DeployProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Abort the deployment once `cancel` is set
+    ///
+    /// No further phase is started and the running command is killed, the deployment
+    /// then fails with [`DeployCancelled`].
+    pub fn with_cancellation(mut self, cancel: watch::Receiver<bool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Whether the deployment was cancelled
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|cancel| *cancel.borrow())
+    }
+
+    /// Send `event` to the progress receiver, if any
+    fn report(&self, event: DeployProgress) {
+        if let Some(progress) = &self.progress {
+            let _ = progress.try_send(event);
+        }
+    }
+
+    /// Run `phase`, reporting its start and its outcome
+    async fn phase<T>(
+        &self,
+        phase: DeployPhase,
+        run: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if self.is_cancelled() {
+            info!("Deployment cancelled before {:?}", phase);
+            return Err(DeployCancelled.into());
+        }
+        self.report(DeployProgress::PhaseStarted(phase));
+        let result = run.await;
+        self.report(match &result {
+            Ok(_) => DeployProgress::PhaseFinished(phase),
+            Err(e) => DeployProgress::PhaseFailed(phase, e.to_string()),
+        });
+        result
+    }
+
+    /// Report a line a command printed, and the transaction hash it mentions, if any
+    fn report_output(&self, line: &str) {
+        if let Some(hash) = transaction_hash(line) {
+            self.report(DeployProgress::Transaction(hash));
+        }
+        self.report(DeployProgress::Output(line.to_string()));
     }
 
     /// Execute the full deployment process
+    ///
+    /// Phases a previous attempt completed in the same workspace are skipped, see
+    /// [`DeployState`], unless `force_clean` is set.
     pub async fn deploy(&self) -> Result<DeploymentResult> {
         info!("Starting rollup contract deployment process");
+        let started = Instant::now();
+        let result = async {
+            self.prepare().await?;
+            self.build().await?;
+            let rollup_creator_address = self.deploy_creator().await?;
+            let deployment = self.deploy_rollup(rollup_creator_address).await?;
+            self.confirm_deployment(&deployment).await?;
+            Ok(deployment)
+        }
+        .await;
+        metrics().record_deploy(started.elapsed(), result.is_ok());
+        result
+    }
+
+    /// Check the config and clone the contracts repository
+    pub async fn prepare(&self) -> Result<()> {
+        self.phase(DeployPhase::Prepare, async {
+            // Spending real funds must be asked for explicitly
+            if self.config.network.is_mainnet() && !self.config.is_mainnet {
+                return Err(anyhow!(
+                    "Refusing to deploy to {} without is_mainnet set",
+                    self.config.network
+                ));
+            }
+
+            // config.ts needs a validator, so fail before cloning and building anything
+            validate_rpc_url(&self.config.rpc_url).map_err(|e| anyhow!("Deployment RPC: {}", e))?;
+            self.first_validator()?;
+            self.config.tee_verifier_address()?;
+            validate_fees(
+                self.config.max_fee_per_gas,
+                self.config.max_priority_fee_per_gas,
+            )?;
+            // An unfunded deployer would only revert after the build
+            self.check_deployer_balance().await?;
+
+            // Start over instead of resuming if asked to
+            if self.config.force_clean {
+                self.clean_workspace()?;
+            }
 
-        // Step 0: Create workspace directory if it doesn't exist
-        fs::create_dir_all(&self.config.workspace_dir)?;
+            // Step 0: Create workspace directory if it doesn't exist
+            fs::create_dir_all(&self.config.workspace_dir)?;
 
-        // Step 1: Clone and set up the contracts repository
-        self.clone_contracts_repo()?;
+            // Step 1: Clone and set up the contracts repository, cached ones are copied in `build`
+            if self.config.contracts_cache.is_some() {
+                return Ok(());
+            }
+            let nitro_contracts_dir = self.config.workspace_dir.join("nitro-contracts");
+            if self.deploy_state().repo_cloned && nitro_contracts_dir.exists() {
+                info!("Contracts repository already cloned, resuming");
+                return Ok(());
+            }
+            // A clone a previous attempt didn't finish would make git refuse to clone again
+            if nitro_contracts_dir.exists() {
+                fs::remove_dir_all(&nitro_contracts_dir)?;
+            }
+            self.clone_contracts_repo(&self.config.workspace_dir)
+                .await?;
+            self.record_phase(|state| state.repo_cloned = true)
+        })
+        .await
+    }
+
+    /// Forget the completed phases and remove what they left in the workspace
+    fn clean_workspace(&self) -> Result<()> {
+        let state_file = self.deploy_state_path();
+        if state_file.exists() {
+            fs::remove_file(&state_file)?;
+        }
+        let nitro_contracts_dir = self.config.workspace_dir.join("nitro-contracts");
+        if nitro_contracts_dir.exists() {
+            info!("Removing {} to start over", nitro_contracts_dir.display());
+            fs::remove_dir_all(&nitro_contracts_dir)?;
+        }
+        Ok(())
+    }
 
-        // Step 2: Install dependencies and build
-        self.build_contracts()?;
+    /// Path of the [`DeployState`] of this deployment
+    pub fn deploy_state_path(&self) -> PathBuf {
+        self.config.workspace_dir.join(DEPLOY_STATE_FILE_NAME)
+    }
 
-        // Step 3: Create environment files
-        self.create_env_file()?;
+    /// Phases a previous attempt of this deployment completed
+    pub fn deploy_state(&self) -> DeployState {
+        match DeployState::load(&self.deploy_state_path()) {
+            Ok(Some(state)) if state.matches(&self.config) => state,
+            Ok(_) => DeployState::for_config(&self.config),
+            Err(e) => {
+                warn!("Ignoring the recorded deployment phases: {}", e);
+                DeployState::for_config(&self.config)
+            }
+        }
+    }
 
-        // Step 4: Create config.ts
-        self.create_config_file()?;
+    /// Record a completed phase in the [`DeployState`]
+    fn record_phase(&self, update: impl FnOnce(&mut DeployState)) -> Result<()> {
+        let mut state = self.deploy_state();
+        update(&mut state);
+        state.save(&self.deploy_state_path())
+    }
 
-        // Step 5: Run deployment script
-        let rollup_creator_address = self.deploy_contracts()?;
+    /// Check the deployer holds at least `min_deployer_balance` on the parent chain
+    pub async fn check_deployer_balance(&self) -> Result<()> {
+        if self.config.dry_run || self.config.min_deployer_balance.is_zero() {
+            return Ok(());
+        }
+        let deployer = self
+            .config
+            .private_key
+            .parse::<PrivateKeySigner>()
+            .map_err(|_| anyhow!("Invalid deployer private key"))?
+            .address();
+        let balance = crate::diagnostics::balance(&self.config.rpc_url, deployer)
+            .await
+            .map_err(|e| anyhow!("Failed to get the balance of deployer {}: {}", deployer, e))?;
+        check_balance(deployer, balance, self.config.min_deployer_balance)
+    }
 
-        // Step 6: Update .env with rollup creator address
-        self.update_env_with_creator(rollup_creator_address.clone())?;
+    /// Build the contracts and write the files the deployment scripts read
+    pub async fn build(&self) -> Result<()> {
+        self.phase(DeployPhase::Build, async {
+            if self.deploy_state().contracts_built {
+                info!("Contracts already built, resuming");
+                return Ok(());
+            }
 
-        // Step 7: Deploy rollup proxy contract
-        let (rollup_proxy_address, upgrade_executor_address, deployment_block) =
-            self.deploy_rollup_proxy()?;
+            // Step 2: Install dependencies and build, or copy the cached build
+            match &self.config.contracts_cache {
+                Some(cache_dir) => self.copy_cached_contracts(cache_dir).await?,
+                None => {
+                    self.build_contracts(&self.config.workspace_dir.join("nitro-contracts"))
+                        .await?
+                }
+            }
 
-        info!("Rollup deployment completed successfully");
+            // Step 3: Create environment files
+            self.create_env_file()?;
 
-        Ok(DeploymentResult {
-            rollup_creator_address,
-            rollup_proxy_address,
-            upgrade_executor_address,
-            deployment_block,
-            chain_id: self.config.chain_id,
+            // Step 4: Create config.ts
+            self.create_config_file()?;
+            self.record_phase(|state| state.contracts_built = true)
+        })
+        .await
+    }
+
+    /// Deploy the rollup creator, returning its address
+    pub async fn deploy_creator(&self) -> Result<String> {
+        self.phase(DeployPhase::DeployCreator, async {
+            // Without gas to spend, only check that the scripts could have run
+            if self.config.dry_run {
+                self.check_deployment_inputs()?;
+                return Ok(DRY_RUN_ADDRESS.to_string());
+            }
+
+            if let Some(rollup_creator_address) = self.deploy_state().rollup_creator_address {
+                info!(
+                    "Rollup creator already deployed at {}, resuming",
+                    rollup_creator_address
+                );
+                return Ok(rollup_creator_address);
+            }
+
+            // Step 5: Run deployment script
+            let rollup_creator_address = self.deploy_contracts().await?;
+
+            // Step 6: Update .env with rollup creator address
+            self.update_env_with_creator(rollup_creator_address.clone())?;
+            self.record_phase(|state| {
+                state.rollup_creator_address = Some(rollup_creator_address.clone())
+            })?;
+            Ok(rollup_creator_address)
+        })
+        .await
+    }
+
+    /// Create the rollup through the rollup creator at `rollup_creator_address`
+    pub async fn deploy_rollup(&self, rollup_creator_address: String) -> Result<DeploymentResult> {
+        self.phase(DeployPhase::DeployRollup, async {
+            if self.config.dry_run {
+                info!("Dry run completed, no transactions were sent");
+                return Ok(DeploymentResult {
+                    rollup_creator_address,
+                    rollup_proxy_address: DRY_RUN_ADDRESS.to_string(),
+                    upgrade_executor_address: DRY_RUN_ADDRESS.to_string(),
+                    deployment_block: 0,
+                    chain_id: self.config.chain_id,
+                    bridge_address: DRY_RUN_ADDRESS.to_string(),
+                    inbox_address: DRY_RUN_ADDRESS.to_string(),
+                    sequencer_inbox_address: DRY_RUN_ADDRESS.to_string(),
+                    validator_utils_address: DRY_RUN_ADDRESS.to_string(),
+                    validator_wallet_creator_address: DRY_RUN_ADDRESS.to_string(),
+                    artifact: None,
+                });
+            }
+
+            // Step 7: Deploy rollup proxy contract
+            let deployments = self.deploy_rollup_proxy().await?;
+            let mut result =
+                deployments.into_deployment_result(rollup_creator_address, self.config.chain_id)?;
+            result.artifact = self.load_deployment_artifact()?;
+
+            info!("Rollup deployment completed successfully");
+            Ok(result)
+        })
+        .await
+    }
+
+    /// Wait until the block the rollup was deployed at has enough confirmations
+    ///
+    /// Configs generated earlier could reference a block the parent chain reorgs away.
+    pub async fn confirm_deployment(&self, deployment: &DeploymentResult) -> Result<()> {
+        self.phase(DeployPhase::Confirm, async {
+            if self.config.dry_run || self.config.confirmations == 0 {
+                return Ok(());
+            }
+
+            info!(
+                "Waiting for {} confirmations of deployment block {}",
+                self.config.confirmations, deployment.deployment_block
+            );
+            let rpc_url = self.config.rpc_url.as_str();
+            wait_for_confirmations(
+                deployment.deployment_block,
+                self.config.confirmations,
+                self.config.confirmation_timeout,
+                CONFIRMATION_POLL_INTERVAL,
+                || crate::diagnostics::block_number(rpc_url),
+            )
+            .await?;
+            info!(
+                "Deployment block {} is confirmed",
+                deployment.deployment_block
+            );
+            Ok(())
         })
+        .await
     }
 
-    /// Clone the nitro-contracts repository
-    fn clone_contracts_repo(&self) -> Result<()> {
-        info!("Cloning contracts repository");
+    /// Copy the contracts built in `cache_dir` into the workspace, building them first if needed
+    ///
+    /// The cache is rebuilt when the repository or ref differ from the ones it was built from.
+    async fn copy_cached_contracts(&self, cache_dir: &Path) -> Result<()> {
+        let source = format!(
+            "{}\n{}\n",
+            self.config.contracts_repo, self.config.contracts_ref
+        );
+        let marker = cache_dir.join(CONTRACTS_CACHE_MARKER);
+        let cached = cache_dir.join("nitro-contracts");
+
+        {
+            // Concurrent creations wait for a single build of the cache
+            let _lock = CONTRACTS_CACHE_LOCK.lock().await;
+            if fs::read_to_string(&marker).ok().as_deref() == Some(source.as_str()) {
+                info!("Using cached contracts in {}", cached.display());
+            } else {
+                info!("Building the contracts cache in {}", cache_dir.display());
+                let _ = fs::remove_file(&marker);
+                if cached.exists() {
+                    fs::remove_dir_all(&cached)?;
+                }
+                fs::create_dir_all(cache_dir)?;
+                self.clone_contracts_repo(cache_dir).await?;
+                self.build_contracts(&cached).await?;
+                // Only a complete build is marked, a failed one is redone next time
+                fs::write(&marker, &source)?;
+            }
+        }
+
+        // A copy rather than hardlinks, the deployment rewrites files of the repository
+        let target = self.config.workspace_dir.join("nitro-contracts");
+        // cp would nest the cache inside a copy left by an earlier attempt
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        let output = self
+            .output(Command::new("cp").arg("-a").arg(&cached).arg(&target))
+            .await?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Failed to copy cached contracts to {}: {}",
+                target.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        info!("Copied cached contracts to {}", target.display());
+        Ok(())
+    }
+
+    /// Clone the nitro-contracts repository into `parent_dir`
+    async fn clone_contracts_repo(&self, parent_dir: &Path) -> Result<()> {
+        info!(
+            "Cloning contracts repository {}",
+            self.config.contracts_repo
+        );
 
+        // Clone into a fixed directory name so forks with another name work too
         let mut cmd = Command::new("git");
-        cmd.current_dir(&self.config.workspace_dir)
+        cmd.current_dir(parent_dir)
             .arg("clone")
-            .arg(NITRO_CONTRACTS_REPO);
+            .arg(&self.config.contracts_repo)
+            .arg("nitro-contracts");
 
-        let output = cmd.output()?;
+        let output = self.output(&mut cmd).await?;
         if !output.status.success() {
             error!(
                 "Failed to clone contracts repository: {}",
@@ -116,77 +1158,206 @@ impl RollupDeployer {
             return Err(anyhow!("Failed to clone contracts repository"));
         }
 
-        // Checkout specific branch
-        let nitro_contracts_dir = self.config.workspace_dir.join("nitro-contracts");
+        // Checkout the configured branch, tag or commit
+        let nitro_contracts_dir = parent_dir.join("nitro-contracts");
         let mut cmd = Command::new("git");
         cmd.current_dir(&nitro_contracts_dir)
             .arg("checkout")
-            .arg(NITRO_CONTRACTS_BRANCH);
+            .arg(&self.config.contracts_ref);
 
-        let output = cmd.output()?;
+        let output = self.output(&mut cmd).await?;
         if !output.status.success() {
             error!(
-                "Failed to checkout branch: {}",
+                "Failed to checkout {}: {}",
+                self.config.contracts_ref,
                 String::from_utf8_lossy(&output.stderr)
             );
-            return Err(anyhow!("Failed to checkout branch"));
+            return Err(anyhow!("Failed to checkout {}", self.config.contracts_ref));
         }
 
         info!("Contracts repository cloned successfully");
         Ok(())
     }
 
-    /// Build the contracts
-    fn build_contracts(&self) -> Result<()> {
+    /// Build the contracts cloned in `dir`
+    async fn build_contracts(&self, dir: &PathBuf) -> Result<()> {
         info!("Installing dependencies and building contracts");
-        let dir = &self.config.workspace_dir.join("nitro-contracts");
 
-        // First check if we're on develop branch
-        info!("Verifying we're on the develop branch");
-        let output = Command::new("git")
-            .current_dir(dir)
-            .args(["branch", "--show-current"])
-            .output()?;
-
-        let current_branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        info!("Current branch: {}", current_branch);
-        if current_branch != NITRO_CONTRACTS_BRANCH {
+        // First check that the configured ref is checked out
+        info!("Verifying {} is checked out", self.config.contracts_ref);
+        let head = self.rev_parse("HEAD", dir).await?;
+        let expected = self
+            .rev_parse(&format!("{}^{{commit}}", self.config.contracts_ref), dir)
+            .await?;
+        info!("Current commit: {}", head);
+        if head != expected {
             error!(
-                "Not on {} branch, currently on {}",
-                NITRO_CONTRACTS_BRANCH, current_branch
+                "Not on {} ({}), currently on {}",
+                self.config.contracts_ref, expected, head
             );
-            return Err(anyhow!("Not on {} branch", NITRO_CONTRACTS_BRANCH));
+            return Err(anyhow!("Not on {}", self.config.contracts_ref));
         }
 
-        // Run yarn install for package dependencies
-        info!("Installing yarn dependencies");
-        self.run_command("yarn", &["install"], dir)?;
-
-        info!("Installing forge dependencies");
-        self.run_command("forge", &["install"], dir)?;
+        // The package and forge dependencies don't depend on each other
+        let started = Instant::now();
+        info!("Installing yarn and forge dependencies");
+        let (yarn, forge) = tokio::join!(
+            self.run_command("yarn", &["install"], dir),
+            self.run_command("forge", &["install"], dir)
+        );
+        yarn?;
+        forge?;
+        info!(
+            "Dependencies installed in {:.1}s",
+            started.elapsed().as_secs_f64()
+        );
 
         info!("Building contracts with yarn build:all");
-        self.run_command("yarn", &["build:all"], dir)?;
+        self.run_command("yarn", &["build:all"], dir).await?;
 
-        info!("Contracts built successfully");
+        info!(
+            "Contracts built successfully in {:.1}s",
+            started.elapsed().as_secs_f64()
+        );
 
         Ok(())
     }
 
+    /// Resolve a git revision to a commit SHA
+    async fn rev_parse(&self, rev: &str, dir: &PathBuf) -> Result<String> {
+        let output = self
+            .output(
+                Command::new("git")
+                    .current_dir(dir)
+                    .args(["rev-parse", "--verify", rev]),
+            )
+            .await?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Failed to resolve {}: {}",
+                rev,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Run a command to completion, killing it once it exceeds the command timeout or the
+    /// deployment is cancelled
+    async fn output(&self, cmd: &mut Command) -> Result<Output> {
+        let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+        let args = cmd
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // Dropping the future on timeout or cancellation kills the child
+        cmd.kill_on_drop(true);
+        let cancel = self.cancel.clone().map(cancelled);
+        let run = tokio::time::timeout(self.config.command_timeout, self.run_reporting(cmd));
+        let result = tokio::select! {
+            result = run => result,
+            () = async {
+                match cancel {
+                    Some(cancel) => cancel.await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                warn!("Killed '{} {}', the deployment was cancelled", program, args);
+                return Err(DeployCancelled.into());
+            }
+        };
+        match result {
+            Ok(output) => output,
+            Err(_) => {
+                error!(
+                    "Command '{} {}' timed out after {}s",
+                    program,
+                    args,
+                    self.config.command_timeout.as_secs()
+                );
+                Err(anyhow!(
+                    "Command '{} {}' timed out after {}s and was killed",
+                    program,
+                    args,
+                    self.config.command_timeout.as_secs()
+                ))
+            }
+        }
+    }
+
+    /// Run a command to completion, reporting each line it prints when progress is reported
+    async fn run_reporting(&self, cmd: &mut Command) -> Result<Output> {
+        if self.progress.is_none() {
+            return Ok(cmd.output().await?);
+        }
+
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let (stdout, stderr, status) = tokio::try_join!(
+            self.read_reporting(child.stdout.take()),
+            self.read_reporting(child.stderr.take()),
+            child.wait()
+        )?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Read `stream` to the end, reporting each line
+    async fn read_reporting(
+        &self,
+        stream: Option<impl AsyncRead + Unpin>,
+    ) -> std::io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        let Some(stream) = stream else {
+            return Ok(output);
+        };
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).await? > 0 {
+            self.report_output(String::from_utf8_lossy(&line).trim_end());
+            output.append(&mut line);
+        }
+        Ok(output)
+    }
+
     /// Helper function to run a command and handle errors consistently
-    fn run_command(&self, cmd: &str, args: &[&str], dir: &PathBuf) -> Result<()> {
-        let output = Command::new(cmd).current_dir(dir).args(args).output()?;
+    async fn run_command(&self, cmd: &str, args: &[&str], dir: &PathBuf) -> Result<()> {
+        let output = self
+            .output(Command::new(cmd).current_dir(dir).args(args))
+            .await?;
 
+        let command = format!("{} {}", cmd, args.join(" "));
         if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr);
-            error!("Command '{}' failed: {}", cmd, err);
-            return Err(anyhow!("Command '{}' failed: {}", cmd, err));
+            let err = command_error(&command, &output);
+            error!("{}", err);
+            return Err(err);
+        }
+
+        // Only the exit code tells a failure apart, whatever the command prints
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            warn!(
+                "Command '{}' succeeded with warnings:\n{}",
+                command,
+                tail(&stderr).join("\n")
+            );
         }
 
         Ok(())
     }
 
-    /// Create the .env file with required variables
+    /// Create the .env file with required variables, readable by the owner only
     fn create_env_file(&self) -> Result<()> {
         info!("Creating .env file");
 
@@ -196,10 +1367,12 @@ impl RollupDeployer {
              DEVNET_PRIVKEY=\"{}\"\n\
              IGNORE_MAX_DATA_SIZE_WARNING=true\n\
              ESPRESSO_TEE_VERIFIER_ADDRESS=\"{}\"\n",
-            self.config.arbiscan_api_key, self.config.private_key, TEE_VERIFIER_ADDRESS
+            self.config.arbiscan_api_key,
+            self.config.private_key,
+            checksum_hex_address(self.config.tee_verifier_address()?)?
         );
 
-        fs::write(nitro_contracts_dir.join(".env"), env_content)?;
+        write_secret_file(&nitro_contracts_dir.join(".env"), &env_content)?;
 
         info!(".env file created successfully");
         Ok(())
@@ -217,22 +1390,21 @@ impl RollupDeployer {
         let template = fs::read_to_string(&template_path)
             .map_err(|e| anyhow!("Failed to read config template: {}", e))?;
 
-        // Replace placeholder values with actual config
+        // Replace placeholder values with actual config, the template carries the 0x prefix
+        let address = |address: &[u8; 20]| checksum_address(address)[2..].to_string();
+        let validator = self.first_validator()?;
         let config = template
-            .replace(
-                "OWNER_ADDRESS",
-                &hex::encode(self.config.initial_chain_owner),
-            )
+            .replace("OWNER_ADDRESS", &address(&self.config.initial_chain_owner))
             .replace("YOUR_CHAIN_ID", &self.config.chain_id.to_string())
             .replace("ChainID", &self.config.chain_id.to_string())
             .replace(
                 "YOUR_OWNED_ADDRESS",
-                &hex::encode(self.config.initial_chain_owner),
+                &address(&self.config.initial_chain_owner),
             )
-            .replace("AN_OWNED_ADDRESS", &hex::encode(self.config.validators[0]))
+            .replace("AN_OWNED_ADDRESS", &address(&validator))
             .replace(
                 "ANOTHER_OWNED_ADDRESS",
-                &hex::encode(self.config.batch_poster_address),
+                &address(&self.config.batch_poster_address),
             );
 
         fs::write(&config_path, config).map_err(|e| anyhow!("Failed to write config.ts: {}", e))?;
@@ -241,40 +1413,170 @@ impl RollupDeployer {
         Ok(())
     }
 
+    /// The validator written into config.ts
+    fn first_validator(&self) -> Result<[u8; 20]> {
+        self.config
+            .validators
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow!("at least one validator is required"))
+    }
+
+    /// Check that the deployment scripts and the files they read are in place
+    fn check_deployment_inputs(&self) -> Result<()> {
+        let dir = self.config.workspace_dir.join("nitro-contracts");
+        for file in [
+            "scripts/deployment.ts",
+            "scripts/createEthRollup.ts",
+            "scripts/config.ts",
+            ".env",
+        ] {
+            if !dir.join(file).is_file() {
+                return Err(anyhow!(
+                    "Dry run failed: {} not found",
+                    dir.join(file).display()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a hardhat script, retrying transient failures with exponential backoff
+    async fn run_hardhat_script(&self, script: &str) -> Result<Output> {
+        let dir = self.config.workspace_dir.join("nitro-contracts");
+        let policy = self.config.retry_policy;
+        let mut attempt = 1;
+        loop {
+            info!(
+                "Running {} (attempt {}/{})",
+                script, attempt, policy.max_attempts
+            );
+            let mut command = Command::new("npx");
+            command
+                .current_dir(&dir)
+                .arg("hardhat")
+                .arg("run")
+                .arg(script)
+                .arg("--network")
+                .arg(self.config.network.hardhat_network_name())
+                .env(self.config.network.rpc_url_env_var(), &self.config.rpc_url)
+                // The deployer key is read from .env, keep raw keys out of the environment
+                .env_remove(RollupKey::Deployer.env_var())
+                .env_remove(RollupKey::Validator.env_var())
+                .env_remove(RollupKey::BatchPoster.env_var());
+            // Unset fees are left to the scripts, which estimate them
+            for (var, fee) in [
+                (MAX_FEE_PER_GAS_ENV_VAR, self.config.max_fee_per_gas),
+                (
+                    MAX_PRIORITY_FEE_PER_GAS_ENV_VAR,
+                    self.config.max_priority_fee_per_gas,
+                ),
+            ] {
+                match fee {
+                    Some(fee) => command.env(var, fee.to_string()),
+                    None => command.env_remove(var),
+                };
+            }
+            let result = self.output(&mut command).await;
+            let err = match result {
+                Ok(output) if output.status.success() => return Ok(output),
+                Ok(output) => String::from_utf8_lossy(&output.stderr).into_owned(),
+                Err(e) if e.is::<DeployCancelled>() => return Err(e),
+                Err(e) => e.to_string(),
+            };
+            error!("{} failed on attempt {}: {}", script, attempt, err);
+
+            if attempt >= policy.max_attempts || !is_retryable(&err) {
+                return Err(anyhow!(err));
+            }
+            let delay = policy.delay(attempt);
+            info!("Retrying {} in {}s", script, delay.as_secs());
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
     /// Deploy contracts using hardhat
-    fn deploy_contracts(&self) -> Result<String> {
+    async fn deploy_contracts(&self) -> Result<String> {
         info!("Deploying contracts");
-        let dir = &self.config.workspace_dir.join("nitro-contracts");
 
         // Run deployment script
-        let output = Command::new("npx")
-            .current_dir(dir)
-            .arg("hardhat")
-            .arg("run")
-            .arg("scripts/deployment.ts")
-            .arg("--network")
-            .arg(&self.config.network)
-            .output()?;
+        let output = self
+            .run_hardhat_script("scripts/deployment.ts")
+            .await
+            .map_err(|e| anyhow!("Deployment failed: {}", e))?;
+
+        // Read the rollup creator address from the deployments file
+        let output = String::from_utf8_lossy(&output.stdout);
+        let deployments = self.load_deployments(&output)?;
+        deployments
+            .rollup_creator
+            .ok_or_else(|| missing_deployment("rollup creator address"))
+    }
 
-        if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr);
-            error!("Deployment failed: {}", err);
-            return Err(anyhow!("Deployment failed: {}", err));
+    /// Read the deployments file, falling back to the script `output` if it's missing
+    fn load_deployments(&self, output: &str) -> Result<EspressoDeployments> {
+        let deployment_json = self.deployment_artifact_path();
+
+        match EspressoDeployments::load(&deployment_json)? {
+            Some(deployments) => {
+                info!("Deployment JSON found at {}", deployment_json.display());
+                Ok(deployments)
+            }
+            None => {
+                info!(
+                    "Deployment JSON not found at {}, reading the script output",
+                    deployment_json.display()
+                );
+                Ok(EspressoDeployments::from_output(output))
+            }
         }
+    }
+
+    /// The `espresso-deployments/<network>.json` file the deployment scripts write
+    pub fn deployment_artifact_path(&self) -> PathBuf {
+        self.config
+            .workspace_dir
+            .join("nitro-contracts")
+            .join("espresso-deployments")
+            .join(format!(
+                "{}.json",
+                self.config.network.hardhat_network_name()
+            ))
+    }
 
-        // Extract rollup creator address from output
-        let deployments = dir.join("espresso-deployments");
-        let deployment_json = deployments.join(format!("{}.json", self.config.network));
-        if deployment_json.exists() {
-            info!("Deployment JSON found at {}", deployment_json.display());
-        } else {
-            error!("Deployment JSON not found at {}", deployment_json.display());
-            return Err(anyhow!("Deployment JSON not found"));
+    /// Parse the deployment artifact, returning `None` if the scripts didn't write one
+    fn load_deployment_artifact(&self) -> Result<Option<serde_json::Value>> {
+        let path = self.deployment_artifact_path();
+        if !path.exists() {
+            return Ok(None);
         }
-        // Read the file and extract the rollup creator address
-        let output_json = fs::read_to_string(&deployment_json)?;
-        let output = serde_json::from_str::<serde_json::Value>(&output_json)?;
-        self.extract_rollup_creator_address(&output)
+        let content = fs::read_to_string(&path)?;
+        let artifact = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+        Ok(Some(artifact))
+    }
+
+    /// Copy the deployment artifact into `config_dir` as [`DEPLOYMENT_ARTIFACT_FILE_NAME`]
+    ///
+    /// Returns the path of the copy, or `None` if the scripts didn't write an artifact.
+    pub fn copy_deployment_artifact(&self, config_dir: &Path) -> Result<Option<PathBuf>> {
+        let path = self.deployment_artifact_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        fs::create_dir_all(config_dir)?;
+        let copy = config_dir.join(DEPLOYMENT_ARTIFACT_FILE_NAME);
+        fs::copy(&path, &copy).map_err(|e| {
+            anyhow!(
+                "Failed to copy {} to {}: {}",
+                path.display(),
+                copy.display(),
+                e
+            )
+        })?;
+        info!("Copied deployment artifact to {}", copy.display());
+        Ok(Some(copy))
     }
 
     /// Update .env with the rollup creator address
@@ -287,126 +1589,114 @@ impl RollupDeployer {
         let mut env_content = fs::read_to_string(&env_path)?;
         env_content.push_str(&format!(
             "ROLLUP_CREATOR_ADDRESS=\"{}\"\n",
-            rollup_creator_address
+            checksum_hex_address(&rollup_creator_address)?
         ));
 
-        fs::write(env_path, env_content)?;
+        write_secret_file(&env_path, &env_content)?;
 
         info!(".env updated with rollup creator address");
         Ok(())
     }
 
     /// Deploy rollup proxy after setting the creator address in .env
-    fn deploy_rollup_proxy(&self) -> Result<(String, String, u64)> {
+    async fn deploy_rollup_proxy(&self) -> Result<EspressoDeployments> {
         info!("Deploying rollup proxy");
-        let dir = &self.config.workspace_dir.join("nitro-contracts");
-
-        // Read deployment json file for additional addresses if needed
-        let deployment_json_path = dir
-            .join("espresso-deployments")
-            .join(format!("{}.json", self.config.network));
-        if !deployment_json_path.exists() {
-            error!(
-                "Deployment JSON not found at {}",
-                deployment_json_path.display()
-            );
-            return Err(anyhow!("Deployment JSON not found"));
-        }
-
-        let deployment_json = fs::read_to_string(&deployment_json_path)?;
-        let deployment = serde_json::from_str::<serde_json::Value>(&deployment_json)?;
 
         // Run deployment script
-        let output = Command::new("npx")
-            .current_dir(dir)
-            .arg("hardhat")
-            .arg("run")
-            .arg("scripts/createEthRollup.ts")
-            .arg("--network")
-            .arg(&self.config.network)
-            .output()?;
-
-        if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr);
-            error!("Rollup proxy deployment failed: {}", err);
-            return Err(anyhow!("Rollup proxy deployment failed: {}", err));
-        }
+        let output = self
+            .run_hardhat_script("scripts/createEthRollup.ts")
+            .await
+            .map_err(|e| anyhow!("Rollup proxy deployment failed: {}", e))?;
+
+        // Extract addresses and block number from the deployments file
+        let output = String::from_utf8_lossy(&output.stdout);
+        self.load_deployments(&output)
+    }
+}
 
-        let output_str = String::from_utf8_lossy(&output.stdout);
+#[async_trait::async_trait]
+impl Deploy for RollupDeployer {
+    async fn deploy(&self) -> Result<DeploymentResult> {
+        RollupDeployer::deploy(self).await
+    }
+}
 
-        // Extract addresses and block number from output
-        let upgrade_executor = self.extract_upgrade_executor_address(&deployment)?;
-        let rollup_proxy = self.extract_rollup_proxy_address(&output_str)?;
-        let deployment_block = self.extract_deployment_block(&output_str)?;
+/// Remove ANSI color and cursor escape sequences from script output
+pub fn strip_ansi(output: &str) -> String {
+    ANSI_ESCAPE.replace_all(output, "").into_owned()
+}
 
-        Ok((rollup_proxy, upgrade_executor, deployment_block))
-    }
+/// Extract the rollup creator address from the deployment script output
+pub fn extract_rollup_creator_address(output: &str) -> Result<String> {
+    capture(&ROLLUP_CREATOR_ADDRESS, output, "rollup creator address")
+}
 
-    /// Extract the rollup creator address from the output
-    fn extract_rollup_creator_address(&self, output: &serde_json::Value) -> Result<String> {
-        output
-            .get("RollupCreator")
-            .and_then(|creator| creator.as_str())
-            .map(|address| address.to_string())
-            .ok_or_else(|| anyhow!("Could not extract rollup creator address from output"))
-    }
+/// Extract the upgrade executor address from the rollup creation script output
+pub fn extract_upgrade_executor_address(output: &str) -> Result<String> {
+    capture(
+        &UPGRADE_EXECUTOR_ADDRESS,
+        output,
+        "upgrade executor address",
+    )
+}
 
-    /// Extract the rollup proxy address from the output
-    fn extract_rollup_proxy_address(&self, output: &str) -> Result<String> {
-        // Simplified implementation
-        for line in output.lines() {
-            if line.contains("RollupProxy Contract created at address:") {
-                let parts: Vec<&str> = line
-                    .split("RollupProxy Contract created at address:")
-                    .collect();
-                if parts.len() > 1 {
-                    let address = parts[1].trim();
-                    return Ok(address.to_string());
-                }
-            }
-        }
+/// Extract the rollup proxy address from the rollup creation script output
+pub fn extract_rollup_proxy_address(output: &str) -> Result<String> {
+    capture(&ROLLUP_PROXY_ADDRESS, output, "rollup proxy address")
+}
 
-        Err(anyhow!(
-            "Could not extract rollup proxy address from output"
-        ))
-    }
+/// Extract the deployment block number from the rollup creation script output
+pub fn extract_deployment_block(output: &str) -> Result<u64> {
+    capture(&DEPLOYMENT_BLOCK, output, "deployment block")?
+        .parse()
+        .map_err(|e| anyhow!("Invalid deployment block: {}", e))
+}
 
-    /// Extract the upgrade executor address from the deployments file
-    fn extract_upgrade_executor_address(&self, content: &serde_json::Value) -> Result<String> {
-        content
-            .get("UpgradeExecutor")
-            .and_then(|executor| executor.as_str())
-            .map(|address| address.to_string())
-            .ok_or_else(|| {
-                anyhow!("Could not extract upgrade executor address from deployments file")
-            })
-    }
+/// The last [`OUTPUT_TAIL_LINES`] lines of `output`
+fn tail(output: &str) -> Vec<&str> {
+    let lines: Vec<&str> = output.lines().collect();
+    lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].to_vec()
+}
 
-    /// Extract the deployment block number from the output
-    fn extract_deployment_block(&self, output: &str) -> Result<u64> {
-        // Simplified implementation
-        for line in output.lines() {
-            if line.contains("All deployed at block number:") {
-                let parts: Vec<&str> = line.split("All deployed at block number:").collect();
-                if parts.len() > 1 {
-                    let block_str = parts[1].trim();
-                    return Ok(block_str.parse()?);
-                }
-            }
-        }
+/// Error for `command` exiting unsuccessfully, with the end of its stdout and stderr
+///
+/// Build tools such as yarn print their errors to stdout, so both are included.
+pub fn command_error(command: &str, output: &Output) -> anyhow::Error {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = tail(&stdout);
+    let stderr = tail(&stderr);
+    anyhow!(
+        "Command '{}' failed with {}. Last {} lines of stdout:\n{}\nLast {} lines of stderr:\n{}",
+        command,
+        output.status,
+        stdout.len(),
+        stdout.join("\n"),
+        stderr.len(),
+        stderr.join("\n")
+    )
+}
 
-        // Default to 0 if not found - in a real implementation, you might want to handle this differently
-        Ok(0)
-    }
+fn missing_deployment(what: &str) -> anyhow::Error {
+    anyhow!("No {} in the deployments file or script output", what)
 }
 
-/// Structure to hold deployment results
-pub struct DeploymentResult {
-    pub rollup_creator_address: String,
-    pub rollup_proxy_address: String,
-    pub upgrade_executor_address: String,
-    pub deployment_block: u64,
-    pub chain_id: u64,
+/// Get the first capture group of `pattern` in `output`, ignoring ANSI escapes
+fn capture(pattern: &Regex, output: &str, what: &str) -> Result<String> {
+    let output = strip_ansi(output);
+    pattern
+        .captures(&output)
+        .and_then(|captures| captures.get(1))
+        .map(|value| value.as_str().to_string())
+        .ok_or_else(|| {
+            let tail = tail(&output);
+            anyhow!(
+                "Could not find {} in deployment output. Last {} lines:\n{}",
+                what,
+                tail.len(),
+                tail.join("\n")
+            )
+        })
 }
 
 /// The Deployer module for managing contract deployments and node setup
@@ -433,12 +1723,11 @@ impl Deployer {
         info!("Deploying contracts with address {}", self.address);
 
         // Execute the deployment script
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(format!(
-                "export PRIVATE_KEY={} && export RPC_URL={} && npm run deploy",
-                self.private_key, self.rpc_url
-            ))
+        // Secrets go in the environment, never on the command line
+        let output = std::process::Command::new("npm")
+            .args(["run", "deploy"])
+            .env("PRIVATE_KEY", &self.private_key)
+            .env("RPC_URL", &self.rpc_url)
             .output()?;
 
         if !output.status.success() {
@@ -466,6 +1755,12 @@ impl Deployer {
             upgrade_executor_address: "0x1234567890123456789012345678901234567890".to_string(),
             deployment_block: 0,
             chain_id: self.chain_id,
+            bridge_address: String::new(),
+            inbox_address: String::new(),
+            sequencer_inbox_address: String::new(),
+            validator_utils_address: String::new(),
+            validator_wallet_creator_address: String::new(),
+            artifact: None,
         })
     }
 }
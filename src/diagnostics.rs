@@ -0,0 +1,318 @@
+use blueprint_sdk as sdk;
+
+use crate::NetworkType;
+use crate::deployer::keys::RollupKey;
+use crate::deployer::rollup::{
+    LIGHT_CLIENT_ADDRESS, TEE_VERIFIER_ADDRESS, command_error, tee_verifier_address_override,
+};
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, RootProvider};
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{Result, anyhow};
+use sdk::info;
+use serde::{Deserialize, Serialize};
+use std::future::IntoFuture;
+use std::time::Duration;
+
+/// Upper bound for a single RPC round-trip during diagnostics
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound for a single host check during preflight
+const HOST_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tools a deployment with the toolchain runs on the host
+pub const REQUIRED_TOOLS: [&str; 4] = ["git", "yarn", "forge", "npx"];
+
+/// Outcome of a single diagnostic check
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CheckResult {
+    /// Name of the check
+    pub name: String,
+    /// Whether the check passed
+    pub passed: bool,
+    /// Human readable detail
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &str, result: Result<String>) -> Self {
+        let (passed, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, e.to_string()),
+        };
+        Self {
+            name: name.to_string(),
+            passed,
+            detail,
+        }
+    }
+}
+
+/// Parent-chain diagnostics report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkDiagnostics {
+    /// Network that was checked
+    pub network: NetworkType,
+    /// RPC endpoint that was used
+    pub rpc_url: String,
+    /// Individual check results
+    pub checks: Vec<CheckResult>,
+}
+
+impl NetworkDiagnostics {
+    /// Whether every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Get a check by name
+    pub fn check(&self, name: &str) -> Option<&CheckResult> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    fn record(&mut self, name: &str, result: Result<String>) {
+        self.checks.push(CheckResult::new(name, result));
+    }
+}
+
+/// Host prerequisites report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreflightReport {
+    /// Individual check results, one per tool and one for the Docker daemon
+    pub checks: Vec<CheckResult>,
+}
+
+impl PreflightReport {
+    /// Whether every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Get a check by name
+    pub fn check(&self, name: &str) -> Option<&CheckResult> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// The failed checks, as `name: detail`
+    pub fn failures(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| format!("{}: {}", check.name, check.detail))
+            .collect()
+    }
+}
+
+/// Get the deployer address from the keystore or `DEPLOYER_PRIVATE_KEY`, if set and valid
+pub fn deployer_address_from_env() -> Option<Address> {
+    RollupKey::Deployer
+        .load()
+        .ok()
+        .and_then(|key| key.parse::<PrivateKeySigner>().ok())
+        .map(|signer| signer.address())
+}
+
+/// Check that the parent chain of `network` is usable for a rollup deployment
+///
+/// `rpc_url` overrides the network's resolved RPC endpoint. The deployer
+/// funding check fails when no `deployer` address is given.
+pub async fn diagnose_network(
+    network: NetworkType,
+    rpc_url: Option<String>,
+    deployer: Option<Address>,
+) -> NetworkDiagnostics {
+    let rpc_url = rpc_url.unwrap_or_else(|| network.resolved_rpc_url());
+    info!(
+        "Running network diagnostics for {} via {}",
+        network, rpc_url
+    );
+
+    let mut report = NetworkDiagnostics {
+        network,
+        rpc_url: rpc_url.clone(),
+        checks: Vec::new(),
+    };
+
+    let provider: RootProvider = match rpc_url.parse() {
+        Ok(url) => RootProvider::new_http(url),
+        Err(e) => {
+            let reason = format!("Invalid RPC URL: {}", e);
+            for name in [
+                "rpc_reachable",
+                "chain_id",
+                "tee_verifier",
+                "light_client",
+                "deployer_funded",
+            ] {
+                report.record(name, Err(anyhow!(reason.clone())));
+            }
+            return report;
+        }
+    };
+
+    // Reachability and chain ID are answered by the same call
+    let chain_id = with_timeout(provider.get_chain_id()).await;
+    let reachable = chain_id.is_ok();
+    match chain_id {
+        Ok(chain_id) => {
+            report.record("rpc_reachable", Ok(format!("{} is reachable", rpc_url)));
+            let expected = network.chain_id();
+            let check = if chain_id == expected {
+                Ok(format!("Chain ID {} matches {}", chain_id, network))
+            } else {
+                Err(anyhow!(
+                    "Chain ID {} does not match {} (expected {})",
+                    chain_id,
+                    network,
+                    expected
+                ))
+            };
+            report.record("chain_id", check);
+        }
+        Err(e) => {
+            report.record("rpc_reachable", Err(e));
+            report.record("chain_id", Err(anyhow!("Skipped: RPC unreachable")));
+        }
+    }
+
+    let tee_verifier =
+        tee_verifier_address_override().unwrap_or_else(|| TEE_VERIFIER_ADDRESS.to_string());
+    for (name, address) in [
+        ("tee_verifier", tee_verifier.as_str()),
+        ("light_client", LIGHT_CLIENT_ADDRESS),
+    ] {
+        if !reachable {
+            report.record(name, Err(anyhow!("Skipped: RPC unreachable")));
+            continue;
+        }
+        let check = async {
+            let address: Address = address.parse()?;
+            let code = with_timeout(provider.get_code_at(address)).await?;
+            if code.is_empty() {
+                Err(anyhow!("No contract deployed at {}", address))
+            } else {
+                Ok(format!("Contract deployed at {}", address))
+            }
+        };
+        report.record(name, check.await);
+    }
+
+    let funded = match deployer {
+        None => Err(anyhow!("DEPLOYER_PRIVATE_KEY is not set or invalid")),
+        Some(_) if !reachable => Err(anyhow!("Skipped: RPC unreachable")),
+        Some(deployer) => match with_timeout(provider.get_balance(deployer)).await {
+            Ok(balance) if balance.is_zero() => Err(anyhow!("Deployer {} has no funds", deployer)),
+            Ok(balance) => Ok(format!("Deployer {} has {} wei", deployer, balance)),
+            Err(e) => Err(e),
+        },
+    };
+    report.record("deployer_funded", funded);
+
+    report
+}
+
+/// Check that `tools` are on the PATH and, with `docker`, that the Docker daemon answers
+///
+/// Every check runs, so the report lists everything missing at once.
+pub async fn preflight(tools: &[&str], docker: bool) -> PreflightReport {
+    info!("Running preflight checks of {:?}", tools);
+
+    let versions = futures::future::join_all(tools.iter().map(|tool| tool_version(tool))).await;
+    let mut checks: Vec<CheckResult> = tools
+        .iter()
+        .zip(versions)
+        .map(|(tool, version)| CheckResult::new(tool, version))
+        .collect();
+    if docker {
+        checks.push(CheckResult::new("docker", docker_version().await));
+    }
+    PreflightReport { checks }
+}
+
+/// Get the version `tool --version` reports, failing if it isn't on the PATH
+pub async fn tool_version(tool: &str) -> Result<String> {
+    let command = format!("{} --version", tool);
+    let output = tokio::process::Command::new(tool)
+        .arg("--version")
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(HOST_CHECK_TIMEOUT, output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(anyhow!("{} not found on PATH", tool));
+        }
+        Ok(Err(e)) => return Err(anyhow!("Failed to run '{}': {}", command, e)),
+        Err(_) => {
+            return Err(anyhow!(
+                "'{}' timed out after {}s",
+                command,
+                HOST_CHECK_TIMEOUT.as_secs()
+            ));
+        }
+    };
+    if !output.status.success() {
+        return Err(command_error(&command, &output));
+    }
+
+    // Some tools print their version on stderr
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("unknown version")
+        .to_string())
+}
+
+/// Get the version of the Docker daemon, failing if it doesn't answer
+pub async fn docker_version() -> Result<String> {
+    let docker = bollard::Docker::connect_with_local_defaults()
+        .map_err(|e| anyhow!("Failed to connect to the Docker daemon: {}", e))?;
+    match tokio::time::timeout(HOST_CHECK_TIMEOUT, docker.version()).await {
+        Ok(Ok(version)) => Ok(format!(
+            "Docker {}",
+            version.version.as_deref().unwrap_or("unknown version")
+        )),
+        Ok(Err(e)) => Err(anyhow!("Docker daemon unreachable: {}", e)),
+        Err(_) => Err(anyhow!(
+            "Docker daemon didn't answer within {}s",
+            HOST_CHECK_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Get the latest block number served by `rpc_url`
+pub async fn block_number(rpc_url: &str) -> Result<u64> {
+    let provider: RootProvider = RootProvider::new_http(
+        rpc_url
+            .parse()
+            .map_err(|e| anyhow!("Invalid RPC URL: {}", e))?,
+    );
+    with_timeout(provider.get_block_number()).await
+}
+
+/// Get the balance of `address` on `rpc_url`, in wei
+pub async fn balance(rpc_url: &str, address: Address) -> Result<U256> {
+    let provider: RootProvider = RootProvider::new_http(
+        rpc_url
+            .parse()
+            .map_err(|e| anyhow!("Invalid RPC URL: {}", e))?,
+    );
+    with_timeout(provider.get_balance(address)).await
+}
+
+async fn with_timeout<T, E, F>(call: F) -> Result<T>
+where
+    F: IntoFuture<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    match tokio::time::timeout(RPC_TIMEOUT, call).await {
+        Ok(result) => result.map_err(|e| anyhow!("RPC request failed: {}", e)),
+        Err(_) => Err(anyhow!(
+            "RPC request timed out after {}s",
+            RPC_TIMEOUT.as_secs()
+        )),
+    }
+}
@@ -1,14 +1,407 @@
 use blueprint_sdk as sdk;
 
+use crate::deployer::rollup::RetryPolicy;
+use crate::docker::health::NODE_SERVICE;
 use anyhow::{Result, anyhow};
+use bollard::container::{ListContainersOptions, RemoveContainerOptions};
+use bollard::errors::Error as BollardError;
+use bollard::models::HealthStatusEnum;
+use bollard::network::ListNetworksOptions;
+use bollard::volume::{CreateVolumeOptions, ListVolumesOptions};
 use dockworker::parser::ComposeParser;
-use dockworker::{ComposeConfig, DockerBuilder, Service};
-use sdk::{error, info};
+use dockworker::{ComposeConfig, DockerBuilder};
+use regex::Regex;
+use sdk::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, OnceLock};
 use std::time::Duration;
 
+/// Retries of the Docker daemon connection, waiting 1+2+4+8s before giving up
+pub const DEFAULT_CONNECT_RETRY: RetryPolicy = RetryPolicy {
+    max_attempts: 5,
+    base_delay: Duration::from_secs(1),
+};
+
+/// Retries of the Docker daemon connection, if set by [`set_connect_retry`]
+static CONNECT_RETRY: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// Retry the Docker daemon connection with `policy` instead of [`DEFAULT_CONNECT_RETRY`]
+///
+/// Only the first call takes effect, so set it before any rollup is started.
+pub fn set_connect_retry(policy: RetryPolicy) {
+    let _ = CONNECT_RETRY.set(policy);
+}
+
+/// Run `connect` until it succeeds or `policy` runs out of attempts
+///
+/// The daemon socket may not accept connections yet right after the host boots.
+pub async fn connect_with_retry<T, E, F, Fut>(policy: RetryPolicy, mut connect: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match connect().await {
+            Ok(connection) => return Ok(connection),
+            Err(e) if attempt >= max_attempts => {
+                return Err(anyhow!(
+                    "Failed to connect to the Docker daemon after {} attempts: {}",
+                    attempt,
+                    e
+                ));
+            }
+            Err(e) => {
+                let delay = policy.delay(attempt);
+                warn!(
+                    "Failed to connect to the Docker daemon (attempt {}/{}), retrying in {:?}: {}",
+                    attempt, max_attempts, delay, e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Label identifying the compose project a Docker resource belongs to
+pub const PROJECT_LABEL: &str = "project";
+
+/// Compose label marking a service the rollup can run without, set to `"true"`
+pub const OPTIONAL_LABEL: &str = "espresso.optional";
+
+/// File in the config directory mapping service names to container IDs
+pub const CONTAINERS_FILE_NAME: &str = "containers.json";
+
+/// Observed state of one of the project's containers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceState {
+    /// Container name
+    pub name: String,
+    /// Docker state, e.g. `running` or `exited`
+    pub state: String,
+}
+
+/// A network or volume that already exists on the Docker host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExistingResource {
+    /// Resource name
+    pub name: String,
+    /// Resource labels
+    pub labels: HashMap<String, String>,
+}
+
+/// What to do with a network or volume the project needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Adoption {
+    /// Reuse the resource left behind by a previous run of the same project
+    Adopt,
+    /// No resource with that name exists yet
+    Create,
+    /// A resource with that name exists but isn't labeled for this project
+    Conflict(String),
+}
+
+/// Docker name of the compose `network` of `project`, or of its default network
+///
+/// Names are scoped to the project so rollups declaring the same networks stay isolated.
+pub fn project_network_name(project: &str, network: Option<&str>) -> String {
+    match network {
+        Some(network) => format!("network-{}-{}", project, network),
+        None => format!("network-{}", project),
+    }
+}
+
+/// Names of the services in `config` not labeled [`OPTIONAL_LABEL`], sorted
+pub fn required_services(config: &ComposeConfig) -> Vec<String> {
+    let mut services: Vec<String> = config
+        .services
+        .iter()
+        .filter(|(_, service)| {
+            service
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get(OPTIONAL_LABEL))
+                .is_none_or(|optional| optional != "true")
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    services.sort();
+    services
+}
+
+/// Breakdown of the services that aren't up, e.g. `validation_node: exited`
+///
+/// `statuses` pairs service names with [`DockerComposeManager::get_service_status`].
+/// Services are up when `running`, or `healthy` or still `starting` with a healthcheck.
+pub fn service_failures(statuses: &[(String, String)]) -> Option<String> {
+    let failures: Vec<String> = statuses
+        .iter()
+        .filter(|(_, status)| !matches!(status.as_str(), "running" | "healthy" | "starting"))
+        .map(|(name, status)| format!("{}: {}", name, status))
+        .collect();
+    (!failures.is_empty()).then(|| failures.join(", "))
+}
+
+/// Images the services of a compose file may run, by prefix
+///
+/// An empty allowlist allows every image. Prefixes are matched against the image as
+/// written in the compose file, so `nitro` doesn't allow `docker.io/library/nitro`.
+/// Operators set it through `ESPRESSO_ALLOWED_IMAGES` as comma-separated prefixes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageAllowlist {
+    prefixes: Vec<String>,
+}
+
+impl ImageAllowlist {
+    /// Only allow the images starting with one of `prefixes`, blank ones are skipped
+    pub fn new<I, S>(prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            prefixes: prefixes
+                .into_iter()
+                .map(|prefix| prefix.as_ref().trim().to_string())
+                .filter(|prefix| !prefix.is_empty())
+                .collect(),
+        }
+    }
+
+    /// The allowlist of an `ESPRESSO_ALLOWED_IMAGES` value
+    pub fn from_prefixes(value: &str) -> Self {
+        Self::new(value.split(','))
+    }
+
+    /// Whether `image` may be run
+    pub fn allows(&self, image: &str) -> bool {
+        self.prefixes.is_empty()
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| image.starts_with(prefix.as_str()))
+    }
+}
+
+/// Error of a compose file that can't be deployed, as opposed to Docker failing to deploy it
+///
+/// Retrying doesn't help until the compose file or the image allowlist is changed.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct ComposeRejected(pub String);
+
+impl ComposeRejected {
+    /// `error` marked as a rejected compose file
+    pub fn wrap(error: anyhow::Error) -> anyhow::Error {
+        anyhow::Error::new(Self(error.to_string()))
+    }
+}
+
+/// Lines quoted on each side of the line a compose parse error points at
+const COMPOSE_ERROR_CONTEXT_LINES: usize = 2;
+
+/// Line a parser error points at, e.g. `... at line 5 column 3`
+static ERROR_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bline (\d+)").expect("valid regex"));
+
+/// Parse the compose file at `path` holding `content`, see [`compose_parse_error`]
+pub fn parse_compose(path: &Path, content: &str) -> Result<ComposeConfig> {
+    ComposeParser::new()
+        .parse(&mut content.as_bytes())
+        .map_err(|e| ComposeRejected::wrap(compose_parse_error(path, content, e)))
+}
+
+/// Error of the parser failing with `error` on the compose file at `path` holding `content`
+///
+/// The file is named, and if the parser points at a line, the lines around it are quoted
+/// with the offending one marked.
+pub fn compose_parse_error(
+    path: &Path,
+    content: &str,
+    error: impl std::fmt::Display,
+) -> anyhow::Error {
+    let error = error.to_string();
+    let message = format!("Failed to parse compose file {}: {}", path.display(), error);
+    let lines: Vec<&str> = content.lines().collect();
+    let line = ERROR_LINE
+        .captures(&error)
+        .and_then(|captures| captures[1].parse::<usize>().ok())
+        .filter(|line| (1..=lines.len()).contains(line));
+    let Some(line) = line else {
+        return anyhow!(message);
+    };
+
+    let first = line.saturating_sub(COMPOSE_ERROR_CONTEXT_LINES).max(1);
+    let last = (line + COMPOSE_ERROR_CONTEXT_LINES).min(lines.len());
+    let width = last.to_string().len();
+    let mut context = String::new();
+    for number in first..=last {
+        let marker = if number == line { '>' } else { ' ' };
+        context.push_str(&format!(
+            "\n{} {:>width$} | {}",
+            marker,
+            number,
+            lines[number - 1]
+        ));
+    }
+    anyhow!("{}{}", message, context)
+}
+
+/// Check that `config` can be deployed, naming the first problem found
+///
+/// The node service must be present, every service needs an image `allowed_images`
+/// allows, and the named volumes services mount must be declared. Bind mounts (paths)
+/// aren't checked.
+pub fn validate_compose(config: &ComposeConfig, allowed_images: &ImageAllowlist) -> Result<()> {
+    if !config.services.contains_key(NODE_SERVICE) {
+        return Err(anyhow!(
+            "Compose file has no `{}` service to run the node",
+            NODE_SERVICE
+        ));
+    }
+
+    let mut services: Vec<_> = config.services.iter().collect();
+    services.sort_by_key(|(name, _)| *name);
+    for (name, service) in services {
+        let image = service.image.as_deref().unwrap_or_default().trim();
+        if image.is_empty() {
+            return Err(anyhow!("Service `{}` has no image", name));
+        }
+        if !allowed_images.allows(image) {
+            return Err(anyhow!(
+                "Service `{}` uses image `{}`, which doesn't start with an allowed prefix: {}",
+                name,
+                image,
+                allowed_images.prefixes.join(", ")
+            ));
+        }
+        for mount in service.volumes.iter().flatten() {
+            let source = mount.split(':').next().unwrap_or_default();
+            let is_path = source.starts_with(['.', '/', '~']);
+            if !is_path && !config.volumes.contains_key(source) {
+                return Err(anyhow!(
+                    "Service `{}` mounts the undeclared volume `{}`",
+                    name,
+                    source
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The parsed compose `config` deployed as `project`, with every service labeled with `labels`
+///
+/// Services keep the networks they declare, scoped to the project by
+/// [`project_network_name`], and join the default network if they declare none.
+/// Services are cloned rather than rebuilt, so fields other than the networks and labels
+/// survive whatever dockworker adds. Labels from the compose file are kept next to `labels`.
+pub fn project_compose_config(
+    config: &ComposeConfig,
+    project: &str,
+    labels: &HashMap<String, String>,
+) -> ComposeConfig {
+    let mut compose_config = config.clone();
+    compose_config.version = "3".to_string();
+    for service in compose_config.services.values_mut() {
+        let networks = match service.networks.as_deref() {
+            Some(networks) if !networks.is_empty() => networks
+                .iter()
+                .map(|network| project_network_name(project, Some(network)))
+                .collect(),
+            _ => vec![project_network_name(project, None)],
+        };
+        service.networks = Some(networks);
+        service
+            .labels
+            .get_or_insert_with(HashMap::new)
+            .extend(labels.clone());
+    }
+    compose_config
+}
+
+/// Decide whether the resource `name` can be reused by `project`
+pub fn plan_adoption(existing: &[ExistingResource], name: &str, project: &str) -> Adoption {
+    match existing.iter().find(|resource| resource.name == name) {
+        None => Adoption::Create,
+        Some(resource) => match resource.labels.get(PROJECT_LABEL) {
+            Some(owner) if owner == project => Adoption::Adopt,
+            Some(owner) => Adoption::Conflict(format!(
+                "{} already exists and belongs to project {}",
+                name, owner
+            )),
+            None => Adoption::Conflict(format!(
+                "{} already exists without a {} label",
+                name, PROJECT_LABEL
+            )),
+        },
+    }
+}
+
+/// How Docker Compose is invoked on this host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComposeCommand {
+    /// The `docker compose` plugin of Compose v2
+    Plugin,
+    /// The legacy standalone `docker-compose` binary
+    Standalone,
+}
+
+static COMPOSE_COMMAND: OnceLock<ComposeCommand> = OnceLock::new();
+
+impl ComposeCommand {
+    /// Find the available Compose, preferring the v2 plugin
+    ///
+    /// The first successful probe is cached for the lifetime of the process.
+    pub fn detect() -> Result<Self> {
+        if let Some(command) = COMPOSE_COMMAND.get() {
+            return Ok(*command);
+        }
+
+        let command = [ComposeCommand::Plugin, ComposeCommand::Standalone]
+            .into_iter()
+            .find(|command| {
+                command
+                    .program()
+                    .arg("version")
+                    .output()
+                    .is_ok_and(|output| output.status.success())
+            })
+            .ok_or_else(|| anyhow!("Neither `docker compose` nor `docker-compose` is installed"))?;
+        info!("Using {:?} Docker Compose", command);
+        Ok(*COMPOSE_COMMAND.get_or_init(|| command))
+    }
+
+    /// A bare Compose invocation
+    fn program(self) -> std::process::Command {
+        match self {
+            ComposeCommand::Plugin => {
+                let mut command = std::process::Command::new("docker");
+                command.arg("compose");
+                command
+            }
+            ComposeCommand::Standalone => std::process::Command::new("docker-compose"),
+        }
+    }
+
+    /// A Compose invocation targeting the project described by `options`
+    pub fn project(self, options: &DockerComposeOptions) -> std::process::Command {
+        let mut command = self.program();
+        command
+            .arg("-f")
+            .arg(&options.compose_file_path)
+            .arg("-p")
+            .arg(&options.project_name);
+        command
+    }
+}
+
 /// Options for Docker container execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DockerComposeOptions {
@@ -20,6 +413,10 @@ pub struct DockerComposeOptions {
 
     /// Project name for docker-compose
     pub project_name: String,
+
+    /// Images the services may run
+    #[serde(default)]
+    pub allowed_images: ImageAllowlist,
 }
 
 /// Manager for Docker containers using docker-compose
@@ -31,15 +428,47 @@ pub struct DockerComposeManager {
 
 impl DockerComposeManager {
     /// Create a new Docker compose manager
+    ///
+    /// Picks up the container IDs recorded by a previous start of the project.
     pub async fn new(options: DockerComposeOptions) -> Result<Self> {
-        let docker = DockerBuilder::new().await?;
+        let policy = CONNECT_RETRY
+            .get()
+            .copied()
+            .unwrap_or(DEFAULT_CONNECT_RETRY);
+        let docker = connect_with_retry(policy, DockerBuilder::new).await?;
+        let container_ids = load_container_ids(&options.config_dir.join(CONTAINERS_FILE_NAME));
         Ok(Self {
             docker,
             options,
-            container_ids: HashMap::new(),
+            container_ids,
         })
     }
 
+    /// Path of the file the container IDs are recorded in
+    fn containers_file(&self) -> PathBuf {
+        self.options.config_dir.join(CONTAINERS_FILE_NAME)
+    }
+
+    /// Record the container IDs so a later process can stop the containers by ID
+    fn save_container_ids(&self) -> Result<()> {
+        std::fs::write(
+            self.containers_file(),
+            serde_json::to_vec_pretty(&self.container_ids)?,
+        )?;
+        Ok(())
+    }
+
+    /// Forget the recorded container IDs once the containers are gone
+    fn remove_container_ids(&self) {
+        let path = self.containers_file();
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                error!("Failed to remove {}: {}", path.display(), e);
+            }
+            _ => {}
+        }
+    }
+
     /// Start the containers defined in the docker-compose.yml file
     pub async fn start_containers(&mut self) -> Result<()> {
         info!(
@@ -63,61 +492,36 @@ impl DockerComposeManager {
             )
         })?;
         let compose_content = std::fs::read_to_string(&self.options.compose_file_path)?;
-        let config = ComposeParser::new()
-            .parse(&mut compose_content.as_bytes())
-            .map_err(|e| anyhow!("Failed to parse compose file: {}", e))?;
-
-        // Create a network for services
-        let network_name = format!("network-{}", self.options.project_name);
+        let config = parse_compose(&self.options.compose_file_path, &compose_content)?;
+        validate_compose(&config, &self.options.allowed_images).map_err(|e| {
+            ComposeRejected::wrap(anyhow!(
+                "Invalid compose file {}: {}",
+                self.options.compose_file_path.display(),
+                e
+            ))
+        })?;
 
         // Prepare labels for tracking
         let mut labels = HashMap::new();
-        labels.insert("project".to_string(), self.options.project_name.clone());
-
-        // Create the network with retry mechanism
-        self.docker
-            .create_network_with_retry(
-                &network_name,
-                3,
-                Duration::from_secs(2),
-                Some(labels.clone()),
-            )
-            .await
-            .map_err(|e| anyhow!("Failed to create network: {}", e))?;
-
-        // Prepare the compose configuration
-        let mut services = HashMap::new();
-
-        // Convert the parsed services to the format expected by dockworker
-        for (service_name, parsed_service) in &config.services {
-            // Create service with required configuration
-            let service = Service {
-                image: parsed_service.image.clone(),
-                ports: parsed_service.ports.clone(),
-                environment: parsed_service.environment.clone(),
-                networks: Some(vec![network_name.clone()]),
-                volumes: parsed_service.volumes.clone(),
-                requirements: parsed_service.requirements.clone(),
-                depends_on: parsed_service.depends_on.clone(),
-                healthcheck: parsed_service.healthcheck.clone(),
-                restart: parsed_service.restart.clone(),
-                command: parsed_service.command.clone(),
-                user: parsed_service.user.clone(),
-                labels: Some(labels.clone()),
-                platform: parsed_service.platform.clone(),
-                env_file: parsed_service.env_file.clone(),
-                build: parsed_service.build.clone(),
-            };
-
-            services.insert(service_name.clone(), service);
+        labels.insert(PROJECT_LABEL.to_string(), self.options.project_name.clone());
+
+        // Scope the networks to the project, keeping everything else the services configure
+        let mut compose_config =
+            project_compose_config(&config, &self.options.project_name, &labels);
+
+        // Reuse the networks from a previous run, otherwise create them
+        let networks: BTreeSet<&String> = compose_config
+            .services
+            .values()
+            .flat_map(|service| service.networks.iter().flatten())
+            .collect();
+        for network_name in networks {
+            self.adopt_or_create_network(network_name, &labels).await?;
         }
 
-        // Create the compose configuration
-        let mut compose_config = ComposeConfig {
-            version: "3".to_string(),
-            services,
-            volumes: config.volumes.clone(),
-        };
+        // Same for the named volumes, so they carry the project label
+        self.adopt_or_create_volumes(config.volumes.keys(), &labels)
+            .await?;
 
         // Deploy the compose configuration
         let container_ids = self
@@ -130,11 +534,169 @@ impl DockerComposeManager {
         for (name, id) in container_ids {
             self.container_ids.insert(name, id);
         }
+        if let Err(e) = self.save_container_ids() {
+            error!("Failed to record container IDs: {}", e);
+        }
 
         info!("All containers started successfully");
         Ok(())
     }
 
+    /// Adopt the network `name` left by a previous run or create it with the project labels
+    async fn adopt_or_create_network(
+        &self,
+        name: &str,
+        labels: &HashMap<String, String>,
+    ) -> Result<()> {
+        let networks = self.existing_networks(name).await?;
+        match plan_adoption(&networks, name, &self.options.project_name) {
+            Adoption::Adopt => info!("Adopting existing network {}", name),
+            Adoption::Create => {
+                self.docker
+                    .create_network_with_retry(
+                        name,
+                        3,
+                        Duration::from_secs(2),
+                        Some(labels.clone()),
+                    )
+                    .await
+                    .map_err(|e| anyhow!("Failed to create network {}: {}", name, e))?;
+            }
+            Adoption::Conflict(reason) => {
+                return Err(anyhow!("Cannot use network: {}", reason));
+            }
+        }
+        Ok(())
+    }
+
+    /// Names of the networks labeled with this project
+    async fn project_networks(&self) -> Result<Vec<String>> {
+        let label = format!("{}={}", PROJECT_LABEL, self.options.project_name);
+        let mut filters = HashMap::new();
+        filters.insert("label", vec![label.as_str()]);
+        let networks = self
+            .docker
+            .get_client()
+            .list_networks(Some(ListNetworksOptions { filters }))
+            .await
+            .map_err(|e| anyhow!("Failed to list networks: {}", e))?;
+        Ok(networks
+            .into_iter()
+            .filter_map(|network| network.name)
+            .collect())
+    }
+
+    /// Remove the networks labeled with this project, skipping ones that are already gone
+    async fn remove_project_networks(&self) -> Result<()> {
+        for network_name in self.project_networks().await? {
+            ignore_not_found(self.docker.get_client().remove_network(&network_name).await)
+                .map_err(|e| anyhow!("Failed to remove network {}: {}", network_name, e))?;
+            info!("Removed network {}", network_name);
+        }
+        Ok(())
+    }
+
+    /// List the networks named `name` on the Docker host
+    async fn existing_networks(&self, name: &str) -> Result<Vec<ExistingResource>> {
+        let mut filters = HashMap::new();
+        filters.insert("name", vec![name]);
+        let networks = self
+            .docker
+            .get_client()
+            .list_networks(Some(ListNetworksOptions { filters }))
+            .await
+            .map_err(|e| anyhow!("Failed to list networks: {}", e))?;
+
+        Ok(networks
+            .into_iter()
+            .filter_map(|network| {
+                Some(ExistingResource {
+                    name: network.name?,
+                    labels: network.labels.unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+
+    /// Adopt the named volumes left by a previous run or create them with the project labels
+    async fn adopt_or_create_volumes<'a>(
+        &self,
+        volumes: impl IntoIterator<Item = &'a String>,
+        labels: &HashMap<String, String>,
+    ) -> Result<()> {
+        let mut filters = HashMap::new();
+        filters.insert("label", vec![PROJECT_LABEL]);
+        let existing: Vec<ExistingResource> = self
+            .docker
+            .get_client()
+            .list_volumes(Some(ListVolumesOptions { filters }))
+            .await
+            .map_err(|e| anyhow!("Failed to list volumes: {}", e))?
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|volume| ExistingResource {
+                name: volume.name,
+                labels: volume.labels,
+            })
+            .collect();
+
+        for volume in volumes {
+            match plan_adoption(&existing, volume, &self.options.project_name) {
+                Adoption::Adopt => info!("Adopting existing volume {}", volume),
+                Adoption::Create => {
+                    self.docker
+                        .get_client()
+                        .create_volume(CreateVolumeOptions {
+                            name: volume.as_str(),
+                            labels: labels
+                                .iter()
+                                .map(|(k, v)| (k.as_str(), v.as_str()))
+                                .collect(),
+                            ..Default::default()
+                        })
+                        .await
+                        .map_err(|e| anyhow!("Failed to create volume {}: {}", volume, e))?;
+                }
+                Adoption::Conflict(reason) => {
+                    return Err(anyhow!("Cannot use volume: {}", reason));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the state of every container labeled with this project, including stopped ones
+    pub async fn container_states(&self) -> Result<Vec<ServiceState>> {
+        let label = format!("{}={}", PROJECT_LABEL, self.options.project_name);
+        let mut filters = HashMap::new();
+        filters.insert("label", vec![label.as_str()]);
+        let containers = self
+            .docker
+            .get_client()
+            .list_containers(Some(ListContainersOptions {
+                all: true,
+                filters,
+                ..Default::default()
+            }))
+            .await
+            .map_err(|e| anyhow!("Failed to list containers: {}", e))?;
+
+        Ok(containers
+            .into_iter()
+            .map(|container| ServiceState {
+                name: container
+                    .names
+                    .and_then(|names| names.into_iter().next())
+                    .map(|name| name.trim_start_matches('/').to_string())
+                    .or(container.id)
+                    .unwrap_or_default(),
+                state: container.state.unwrap_or_default(),
+            })
+            .collect())
+    }
+
     /// Stop the containers defined in the docker-compose.yml file
     pub async fn stop_containers(&self) -> Result<()> {
         info!(
@@ -179,14 +741,11 @@ impl DockerComposeManager {
             }
         }
 
-        // Try to remove the network
-        if api_success {
-            let network_name = format!("network-{}", self.options.project_name);
-            if let Err(e) = self.docker.get_client().remove_network(&network_name).await {
-                api_success = false;
-                api_error = format!("Failed to remove network: {}", e);
-                error!("{}", api_error);
-            }
+        // Try to remove the networks
+        if api_success && let Err(e) = self.remove_project_networks().await {
+            api_success = false;
+            api_error = e.to_string();
+            error!("{}", api_error);
         }
 
         // If the API approach failed, fall back to docker compose down command
@@ -196,12 +755,8 @@ impl DockerComposeManager {
                 api_error
             );
 
-            let output = std::process::Command::new("docker")
-                .arg("compose")
-                .arg("-f")
-                .arg(&self.options.compose_file_path)
-                .arg("-p")
-                .arg(&self.options.project_name)
+            let output = ComposeCommand::detect()?
+                .project(&self.options)
                 .arg("down")
                 .output()?;
 
@@ -215,11 +770,69 @@ impl DockerComposeManager {
             }
         }
 
+        self.remove_container_ids();
         info!("All containers stopped successfully");
         Ok(())
     }
 
+    /// Remove every container, the networks and the named volumes of this project
+    ///
+    /// Resources are found by their project label, so this works without a prior start.
+    /// Resources that are already gone are skipped.
+    pub async fn teardown(&self) -> Result<()> {
+        info!("Tearing down project {}", self.options.project_name);
+        let client = self.docker.get_client();
+        let label = format!("{}={}", PROJECT_LABEL, self.options.project_name);
+
+        let mut filters = HashMap::new();
+        filters.insert("label", vec![label.as_str()]);
+        let containers = client
+            .list_containers(Some(ListContainersOptions {
+                all: true,
+                filters,
+                ..Default::default()
+            }))
+            .await
+            .map_err(|e| anyhow!("Failed to list containers: {}", e))?;
+        for container_id in containers.into_iter().filter_map(|container| container.id) {
+            let removed = client
+                .remove_container(
+                    &container_id,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await;
+            ignore_not_found(removed)
+                .map_err(|e| anyhow!("Failed to remove container {}: {}", container_id, e))?;
+            info!("Removed container {}", container_id);
+        }
+
+        self.remove_project_networks().await?;
+
+        let mut filters = HashMap::new();
+        filters.insert("label", vec![label.as_str()]);
+        let volumes = client
+            .list_volumes(Some(ListVolumesOptions { filters }))
+            .await
+            .map_err(|e| anyhow!("Failed to list volumes: {}", e))?
+            .volumes
+            .unwrap_or_default();
+        for volume in volumes {
+            ignore_not_found(client.remove_volume(&volume.name, None).await)
+                .map_err(|e| anyhow!("Failed to remove volume {}: {}", volume.name, e))?;
+            info!("Removed volume {}", volume.name);
+        }
+
+        self.remove_container_ids();
+        info!("Project {} torn down", self.options.project_name);
+        Ok(())
+    }
+
     /// Get the status of a specific service
+    ///
+    /// Reports the health status, e.g. `healthy`, for services with a healthcheck.
     pub async fn get_service_status(&self, service_name: &str) -> Result<String> {
         if let Some(container_id) = self.container_ids.get(service_name) {
             // Use Docker API to inspect container
@@ -230,13 +843,20 @@ impl DockerComposeManager {
                 .await
                 .map_err(|e| anyhow!("Failed to inspect container: {}", e))?;
 
-            if let Some(state) = inspect.state {
-                if let Some(status) = state.status {
-                    return Ok(status.to_string());
-                }
+            let state = inspect
+                .state
+                .ok_or_else(|| anyhow!("Could not determine container status"))?;
+            let health = state
+                .health
+                .and_then(|health| health.status)
+                .filter(|status| {
+                    !matches!(status, HealthStatusEnum::NONE | HealthStatusEnum::EMPTY)
+                });
+            match (health, state.status) {
+                (Some(health), _) => Ok(health.to_string()),
+                (None, Some(status)) => Ok(status.to_string()),
+                (None, None) => Err(anyhow!("Could not determine container status")),
             }
-
-            Err(anyhow!("Could not determine container status"))
         } else {
             Err(anyhow!(
                 "Container ID not found for service {}",
@@ -245,6 +865,24 @@ impl DockerComposeManager {
         }
     }
 
+    /// Status of every service [`required_services`] lists, see [`Self::get_service_status`]
+    ///
+    /// Services whose status can't be read are reported as `unknown` with the reason.
+    pub async fn service_statuses(&self) -> Result<Vec<(String, String)>> {
+        let compose_content = std::fs::read_to_string(&self.options.compose_file_path)?;
+        let config = parse_compose(&self.options.compose_file_path, &compose_content)?;
+
+        let mut statuses = Vec::new();
+        for service in required_services(&config) {
+            let status = match self.get_service_status(&service).await {
+                Ok(status) => status,
+                Err(e) => format!("unknown ({})", e),
+            };
+            statuses.push((service, status));
+        }
+        Ok(statuses)
+    }
+
     /// Get the logs for a specific service
     pub async fn get_service_logs(&self, service_name: &str) -> Result<String> {
         if let Some(container_id) = self.container_ids.get(service_name) {
@@ -257,13 +895,9 @@ impl DockerComposeManager {
 
             Ok(logs)
         } else {
-            // Fall back to docker-compose command if container ID not found
-            let output = std::process::Command::new("docker")
-                .arg("compose")
-                .arg("-f")
-                .arg(&self.options.compose_file_path)
-                .arg("-p")
-                .arg(&self.options.project_name)
+            // Fall back to docker compose if container ID not found
+            let output = ComposeCommand::detect()?
+                .project(&self.options)
                 .arg("logs")
                 .arg(service_name)
                 .output()?;
@@ -301,3 +935,32 @@ impl DockerComposeManager {
         }
     }
 }
+
+/// Read the container IDs recorded at `path`, starting empty if there are none
+fn load_container_ids(path: &Path) -> HashMap<String, String> {
+    if !path.exists() {
+        return HashMap::new();
+    }
+
+    std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| serde_json::from_str(&content).map_err(anyhow::Error::from))
+        .unwrap_or_else(|e| {
+            error!(
+                "Failed to read container IDs from {}: {}",
+                path.display(),
+                e
+            );
+            HashMap::new()
+        })
+}
+
+/// Treat a 404 from the Docker API as success
+fn ignore_not_found(result: Result<(), BollardError>) -> Result<(), BollardError> {
+    match result {
+        Err(BollardError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,87 @@
+use blueprint_sdk as sdk;
+
+use anyhow::{Result, anyhow};
+use sdk::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Directories of a rollup that are included in its final archive
+const ARCHIVED_DIRS: [&str; 2] = ["config", "database"];
+
+/// Record of a decommissioned rollup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecommissionReceipt {
+    /// Rollup ID
+    pub rollup_id: String,
+    /// Service ID
+    pub service_id: u64,
+    /// Chain ID of the rollup
+    pub chain_id: u64,
+    /// Last block produced by the node, if it was reachable
+    pub final_block: Option<u64>,
+    /// Initial chain owner address
+    pub initial_chain_owner: String,
+    /// Validator addresses
+    pub validators: Vec<String>,
+    /// Batch poster address
+    pub batch_poster_address: String,
+    /// Batch poster manager address
+    pub batch_poster_manager: String,
+    /// Archive of the rollup data, unless archiving was skipped
+    pub archive_path: Option<PathBuf>,
+    /// Decommission timestamp
+    pub decommissioned_at: String,
+}
+
+/// Archive the config and chain data of a rollup into `archive_dir`
+///
+/// `rollup_root` is the directory holding the rollup's workspace, config and
+/// database mounts. Returns the path of the created `.tar.gz`.
+pub async fn archive_rollup_data(
+    rollup_root: &Path,
+    archive_dir: &Path,
+    rollup_id: &str,
+) -> Result<PathBuf> {
+    let entries: Vec<&str> = ARCHIVED_DIRS
+        .into_iter()
+        .filter(|dir| rollup_root.join(dir).exists())
+        .collect();
+    if !entries.contains(&"database") {
+        return Err(anyhow!(
+            "No rollup data found at {}",
+            rollup_root.join("database").display()
+        ));
+    }
+
+    fs::create_dir_all(archive_dir)?;
+    let archive_path = archive_dir.join(format!(
+        "{}-{}.tar.gz",
+        rollup_id,
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+
+    info!(
+        "Archiving rollup {} data to {}",
+        rollup_id,
+        archive_path.display()
+    );
+    let output = Command::new("tar")
+        .arg("-czf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(rollup_root)
+        .args(&entries)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to archive rollup data: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(archive_path)
+}
@@ -1,9 +1,36 @@
 use blueprint_sdk as sdk;
 
-use crate::docker::container::{DockerComposeManager, DockerComposeOptions};
+use crate::deployer::rollup::command_error;
+use crate::docker::container::{
+    ComposeCommand, ComposeRejected, DockerComposeManager, DockerComposeOptions, ImageAllowlist,
+    ServiceState, parse_compose, validate_compose,
+};
+use crate::docker::health::{Endpoints, NODE_SERVICE, rpc_host_port};
 use anyhow::{Result, anyhow};
+use dockworker::ComposeConfig;
 use sdk::info;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Default prefix of the compose project names, see [`EspressoDockerManager::project_name`]
+pub const DEFAULT_PROJECT_PREFIX: &str = "espresso";
+
+/// Check that `prefix` can start a compose project name
+///
+/// Compose only accepts lowercase letters, digits, `-` and `_`, starting with a letter or digit.
+pub fn validate_project_prefix(prefix: &str) -> Result<()> {
+    let valid = prefix
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase() || first.is_ascii_digit())
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow!("Invalid compose project prefix {:?}", prefix));
+    }
+    Ok(())
+}
 
 /// Connect Docker with the Espresso configuration generator
 pub struct EspressoDockerManager {
@@ -11,6 +38,8 @@ pub struct EspressoDockerManager {
     workspace_dir: PathBuf,
     config_dir: PathBuf,
     vm_id: String,
+    project_prefix: String,
+    allowed_images: ImageAllowlist,
 }
 
 impl EspressoDockerManager {
@@ -25,28 +54,57 @@ impl EspressoDockerManager {
             workspace_dir: workspace_dir.as_ref().to_path_buf(),
             config_dir: config_dir.as_ref().to_path_buf(),
             vm_id: vm_id.to_string(),
+            project_prefix: DEFAULT_PROJECT_PREFIX.to_string(),
+            allowed_images: ImageAllowlist::default(),
         }
     }
 
-    /// Initialize and start the Docker containers
-    pub async fn start(&mut self) -> Result<()> {
-        info!(
-            "Starting Espresso Docker containers for VM ID: {}",
-            self.vm_id
-        );
+    /// Name the compose project `{prefix}-{vm_id}` instead of using [`DEFAULT_PROJECT_PREFIX`]
+    pub fn with_project_prefix(mut self, prefix: &str) -> Self {
+        self.project_prefix = prefix.to_string();
+        self
+    }
+
+    /// Refuse to start or pull compose files running images `allowed_images` doesn't allow
+    pub fn with_allowed_images(mut self, allowed_images: ImageAllowlist) -> Self {
+        self.allowed_images = allowed_images;
+        self
+    }
+
+    /// Docker compose project name of this VM
+    ///
+    /// The project's networks are named after it, see [`project_network_name`].
+    ///
+    /// [`project_network_name`]: crate::docker::container::project_network_name
+    pub fn project_name(&self) -> String {
+        format!("{}-{}", self.project_prefix, self.vm_id)
+    }
 
+    /// Docker compose options for this VM
+    fn compose_options(&self) -> Result<DockerComposeOptions> {
         let compose_file_path = self
             .workspace_dir
             .parent()
             .ok_or_else(|| anyhow!("Failed to get parent directory"))?
             .join("docker-compose.yml");
 
-        // Create Docker compose options
-        let options = DockerComposeOptions {
+        Ok(DockerComposeOptions {
             compose_file_path,
             config_dir: self.config_dir.clone(),
-            project_name: format!("espresso-{}", self.vm_id),
-        };
+            project_name: self.project_name(),
+            allowed_images: self.allowed_images.clone(),
+        })
+    }
+
+    /// Initialize and start the Docker containers
+    pub async fn start(&mut self) -> Result<()> {
+        info!(
+            "Starting Espresso Docker containers for VM ID: {}",
+            self.vm_id
+        );
+
+        // Create Docker compose options
+        let options = self.compose_options()?;
 
         // Create and initialize the Docker compose manager
         let mut compose_manager = DockerComposeManager::new(options).await?;
@@ -62,21 +120,93 @@ impl EspressoDockerManager {
     }
 
     /// Stop the Docker containers
+    ///
+    /// Works without a prior `start()` by stopping the compose project by name.
     pub async fn stop(&self) -> Result<()> {
         info!(
             "Stopping Espresso Docker containers for VM ID: {}",
             self.vm_id
         );
 
-        if let Some(compose_manager) = &self.compose_manager {
-            compose_manager.stop_containers().await?;
-            info!("Espresso Docker containers stopped successfully");
-            Ok(())
-        } else {
-            Err(anyhow!("Docker compose manager not initialized"))
+        match &self.compose_manager {
+            Some(compose_manager) => compose_manager.stop_containers().await?,
+            None => {
+                DockerComposeManager::new(self.compose_options()?)
+                    .await?
+                    .stop_containers()
+                    .await?
+            }
+        }
+        info!("Espresso Docker containers stopped successfully");
+        Ok(())
+    }
+
+    /// Pull the images of the compose file, without touching the running containers
+    pub async fn pull_images(&self) -> Result<()> {
+        info!("Pulling Espresso Docker images for VM ID: {}", self.vm_id);
+
+        // Pulling alone must not fetch images the node couldn't be started on
+        validate_compose(&self.compose_config()?, &self.allowed_images)
+            .map_err(ComposeRejected::wrap)?;
+        let mut command = ComposeCommand::detect()?.project(&self.compose_options()?);
+        command.arg("pull");
+        let output = tokio::process::Command::from(command).output().await?;
+        if !output.status.success() {
+            return Err(command_error("docker compose pull", &output));
+        }
+        Ok(())
+    }
+
+    /// Wait for the Espresso node to report `healthy`, or `running` without a healthcheck
+    pub async fn wait_until_healthy(
+        &self,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let status = self.get_status().await?;
+            if status == "healthy" || status == "running" {
+                info!("Espresso node of VM ID {} is {}", self.vm_id, status);
+                return Ok(());
+            }
+            if Instant::now() + poll_interval > deadline {
+                return Err(anyhow!(
+                    "Espresso node not healthy after {}s, last status: {}",
+                    timeout.as_secs(),
+                    status
+                ));
+            }
+            tokio::time::sleep(poll_interval).await;
         }
     }
 
+    /// Parse the compose file of this VM
+    fn compose_config(&self) -> Result<ComposeConfig> {
+        let compose_file_path = self.compose_options()?.compose_file_path;
+        let compose_content = std::fs::read_to_string(&compose_file_path).map_err(|e| {
+            anyhow!(
+                "Failed to read compose file {}: {}",
+                compose_file_path.display(),
+                e
+            )
+        })?;
+        parse_compose(&compose_file_path, &compose_content)
+    }
+
+    /// RPC endpoint of the Espresso node, from the port mapping in the compose file
+    pub fn rpc_url(&self) -> Result<String> {
+        let port = rpc_host_port(&self.compose_config()?)
+            .ok_or_else(|| anyhow!("Compose file doesn't expose the node RPC port"))?;
+        Ok(format!("http://localhost:{}", port))
+    }
+
+    /// Endpoints of the Espresso node on `host`, from the port mappings in the compose file
+    pub fn endpoints(&self, host: &str) -> Result<Endpoints> {
+        Endpoints::from_compose(&self.compose_config()?, host)
+            .ok_or_else(|| anyhow!("Compose file doesn't expose the node RPC port"))
+    }
+
     /// Get the status of the Espresso node
     pub async fn get_status(&self) -> Result<String> {
         if let Some(compose_manager) = &self.compose_manager {
@@ -86,21 +216,79 @@ impl EspressoDockerManager {
         }
     }
 
+    /// Status of every service the rollup needs, see [`DockerComposeManager::service_statuses`]
+    pub async fn service_statuses(&self) -> Result<Vec<(String, String)>> {
+        match &self.compose_manager {
+            Some(compose_manager) => compose_manager.service_statuses().await,
+            None => {
+                DockerComposeManager::new(self.compose_options()?)
+                    .await?
+                    .service_statuses()
+                    .await
+            }
+        }
+    }
+
+    /// Inspect the project's containers on the Docker host
+    ///
+    /// Works without a prior `start()`, so it can be used after a process restart.
+    pub async fn container_states(&self) -> Result<Vec<ServiceState>> {
+        match &self.compose_manager {
+            Some(compose_manager) => compose_manager.container_states().await,
+            None => {
+                DockerComposeManager::new(self.compose_options()?)
+                    .await?
+                    .container_states()
+                    .await
+            }
+        }
+    }
+
+    /// Remove the containers, network and volumes of this VM
+    pub async fn teardown(&self) -> Result<()> {
+        info!(
+            "Tearing down Espresso Docker resources for VM ID: {}",
+            self.vm_id
+        );
+
+        match &self.compose_manager {
+            Some(compose_manager) => compose_manager.teardown().await,
+            None => {
+                DockerComposeManager::new(self.compose_options()?)
+                    .await?
+                    .teardown()
+                    .await
+            }
+        }
+    }
+
     /// Get the logs of the Espresso node
+    ///
+    /// Works without a prior `start()` through the recorded container IDs.
     pub async fn get_logs(&self) -> Result<String> {
-        if let Some(compose_manager) = &self.compose_manager {
-            compose_manager.get_service_logs("nitro").await
-        } else {
-            Err(anyhow!("Docker compose manager not initialized"))
+        match &self.compose_manager {
+            Some(compose_manager) => compose_manager.get_service_logs(NODE_SERVICE).await,
+            None => {
+                DockerComposeManager::new(self.compose_options()?)
+                    .await?
+                    .get_service_logs(NODE_SERVICE)
+                    .await
+            }
         }
     }
 
     /// Execute a command in the Espresso node container
+    ///
+    /// Works without a prior `start()` through the recorded container IDs.
     pub async fn exec_command(&self, command: &[&str]) -> Result<String> {
-        if let Some(compose_manager) = &self.compose_manager {
-            compose_manager.exec_command("nitro", command).await
-        } else {
-            Err(anyhow!("Docker compose manager not initialized"))
+        match &self.compose_manager {
+            Some(compose_manager) => compose_manager.exec_command(NODE_SERVICE, command).await,
+            None => {
+                DockerComposeManager::new(self.compose_options()?)
+                    .await?
+                    .exec_command(NODE_SERVICE, command)
+                    .await
+            }
         }
     }
 }
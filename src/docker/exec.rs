@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+/// Commands the `exec_docker_rollup` job may run in the node container, by name
+///
+/// Callers only pick a name, the arguments are fixed by the operator, so no caller input
+/// ever reaches the container. The defaults are:
+///
+/// - `version`: `nitro --version`
+/// - `disk-usage`: `du -sh /home/user/.arbitrum`, the size of the node database
+///
+/// Operators add commands with [`ExecAllowlist::with_command`], or through
+/// `ESPRESSO_EXEC_ALLOWLIST` as `name=program args;name=program args`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecAllowlist {
+    commands: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for ExecAllowlist {
+    fn default() -> Self {
+        Self::empty()
+            .with_command("version", ["nitro", "--version"])
+            .with_command("disk-usage", ["du", "-sh", "/home/user/.arbitrum"])
+    }
+}
+
+impl ExecAllowlist {
+    /// An allowlist without any command
+    pub fn empty() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+        }
+    }
+
+    /// Allow running `command` under `name`, replacing any command of the same name
+    pub fn with_command<I, S>(mut self, name: &str, command: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.commands.insert(
+            name.to_string(),
+            command.into_iter().map(Into::into).collect(),
+        );
+        self
+    }
+
+    /// Add the commands of an `ESPRESSO_EXEC_ALLOWLIST` value
+    ///
+    /// Entries are separated by `;`, each is `name=program args` split on whitespace.
+    /// Entries without a name or a program are skipped.
+    pub fn with_commands_from(mut self, value: &str) -> Self {
+        for entry in value.split(';') {
+            let Some((name, command)) = entry.split_once('=') else {
+                continue;
+            };
+            let name = name.trim();
+            let command = command.split_whitespace().collect::<Vec<_>>();
+            if !name.is_empty() && !command.is_empty() {
+                self = self.with_command(name, command);
+            }
+        }
+        self
+    }
+
+    /// The command allowed under `name`
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.commands.get(name).map(Vec::as_slice)
+    }
+
+    /// Names of the allowed commands, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(String::as_str)
+    }
+}
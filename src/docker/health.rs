@@ -0,0 +1,312 @@
+use alloy::primitives::{Address, Bytes, U64, U256, keccak256};
+use alloy::providers::{Provider, RootProvider};
+use anyhow::{Result, anyhow};
+use dockworker::ComposeConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// Compose service running the rollup node
+pub const NODE_SERVICE: &str = "nitro";
+
+/// Port the node serves JSON-RPC on inside its container
+pub const NODE_RPC_PORT: u16 = 8547;
+
+/// Port the node serves JSON-RPC over WebSocket on inside its container
+pub const NODE_WS_PORT: u16 = 8548;
+
+/// Port the node publishes its sequencer feed on inside its container
+pub const NODE_FEED_PORT: u16 = 9642;
+
+/// Port the validation node serves on inside its container
+pub const VALIDATION_NODE_PORT: u16 = 8549;
+
+/// Upper bound for the health check RPC round-trip
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether the RPC of a rollup node answers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RollupHealth {
+    /// The node answered `eth_blockNumber`
+    Healthy {
+        /// RPC endpoint that was checked
+        rpc_url: String,
+        /// Round-trip time of the call
+        latency_ms: u64,
+        /// Latest block of the rollup
+        latest_block: u64,
+    },
+    /// Nothing listens on the RPC port, e.g. the node is still starting or has crashed
+    ConnectionRefused { rpc_url: String, error: String },
+    /// The node accepted the connection but didn't answer in time
+    Timeout { rpc_url: String, timeout_ms: u64 },
+    /// Any other failure, such as a malformed response
+    Unhealthy { rpc_url: String, error: String },
+}
+
+impl RollupHealth {
+    /// Whether the node answered
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, RollupHealth::Healthy { .. })
+    }
+}
+
+/// Host port mapped to `container_port` by a compose port mapping such as `127.0.0.1:8547:8547/tcp`
+pub fn host_port(mapping: &str, container_port: u16) -> Option<u16> {
+    let mapping = mapping.split('/').next()?;
+    let (host, container) = mapping.rsplit_once(':')?;
+    if container.parse::<u16>().ok()? != container_port {
+        return None;
+    }
+    host.rsplit(':').next()?.parse().ok()
+}
+
+/// Host port mapped to `container_port` of the node in a rollup's compose config
+pub fn node_host_port(compose: &ComposeConfig, container_port: u16) -> Option<u16> {
+    compose
+        .services
+        .get(NODE_SERVICE)?
+        .ports
+        .as_ref()?
+        .iter()
+        .find_map(|mapping| host_port(mapping, container_port))
+}
+
+/// Host port serving the node RPC in a rollup's compose config
+pub fn rpc_host_port(compose: &ComposeConfig) -> Option<u16> {
+    node_host_port(compose, NODE_RPC_PORT)
+}
+
+/// Externally reachable endpoints of a rollup node
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Endpoints {
+    /// HTTP JSON-RPC URL
+    pub rpc: String,
+    /// WebSocket JSON-RPC URL, unset if the compose file doesn't publish the port
+    pub ws: Option<String>,
+    /// Sequencer feed URL, unset if the compose file doesn't publish the port
+    pub feed: Option<String>,
+}
+
+impl Endpoints {
+    /// Endpoints of the node on `host`, from the port mappings of its compose config
+    ///
+    /// `None` if the compose config doesn't publish the RPC port.
+    pub fn from_compose(compose: &ComposeConfig, host: &str) -> Option<Self> {
+        let url = |scheme: &str, port: u16| format!("{}://{}:{}", scheme, host, port);
+        Some(Self {
+            rpc: url("http", rpc_host_port(compose)?),
+            ws: node_host_port(compose, NODE_WS_PORT).map(|port| url("ws", port)),
+            feed: node_host_port(compose, NODE_FEED_PORT).map(|port| url("ws", port)),
+        })
+    }
+}
+
+/// How far a rollup node has caught up with its chain
+///
+/// A running node only serves the latest state of the rollup once synced, which can take
+/// hours for a node starting from genesis.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncStatus {
+    /// Whether the node has caught up
+    pub synced: bool,
+    /// Percentage of the chain known to the node it has processed, 100 once synced
+    pub sync_progress: u8,
+}
+
+impl SyncStatus {
+    /// The sync status an `eth_syncing` response reports
+    ///
+    /// Nitro compares the batches it processed with those it saw posted on the parent
+    /// chain, other nodes report their current and highest block. The progress is 0 when
+    /// the response tells neither.
+    pub fn from_syncing(syncing: &Value) -> Self {
+        if *syncing == Value::Bool(false) {
+            return Self {
+                synced: true,
+                sync_progress: 100,
+            };
+        }
+        let number = |key: &str| match &syncing[key] {
+            Value::Number(number) => number.as_u64(),
+            Value::String(quantity) => quantity.parse::<U64>().ok().map(|q| q.to::<u64>()),
+            _ => None,
+        };
+        let sync_progress = [
+            ("batchProcessed", "batchSeen"),
+            ("currentBlock", "highestBlock"),
+        ]
+        .into_iter()
+        .find_map(|(current, highest)| Some((number(current)?, number(highest)?)))
+        .filter(|(_, highest)| *highest > 0)
+        // Still syncing, so never 100 even when rounding would get there
+        .map(|(current, highest)| {
+            (current.min(highest) as u128 * 100 / highest as u128).min(99) as u8
+        })
+        .unwrap_or(0);
+        Self {
+            synced: false,
+            sync_progress,
+        }
+    }
+}
+
+impl std::fmt::Display for SyncStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.synced {
+            write!(f, "synced")
+        } else {
+            write!(f, "syncing {}%", self.sync_progress)
+        }
+    }
+}
+
+/// Ask the node at `rpc_url` how far it has synced, giving up after `timeout`
+pub async fn node_sync_status(rpc_url: &str, timeout: Duration) -> Result<SyncStatus> {
+    let provider = rpc_provider(rpc_url)?;
+    let syncing: Value =
+        tokio::time::timeout(timeout, provider.raw_request("eth_syncing".into(), ()))
+            .await
+            .map_err(|_| anyhow!("eth_syncing timed out after {:?}", timeout))?
+            .map_err(|e| anyhow!("eth_syncing failed: {}", e))?;
+    Ok(SyncStatus::from_syncing(&syncing))
+}
+
+/// Chain metrics of a rollup node
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChainMetrics {
+    /// RPC endpoint that was queried
+    pub rpc_url: String,
+    /// Latest block of the rollup
+    pub block_number: u64,
+    /// Peers the node is connected to
+    pub peer_count: u64,
+    /// Whether the node is still catching up, its block number lags the chain meanwhile
+    pub syncing: bool,
+    /// Batches posted to the parent chain, unset if the sequencer inbox couldn't be read
+    pub batch_count: Option<u64>,
+}
+
+/// Query the block number, peers and sync state of the node at `rpc_url`
+///
+/// Each call gives up after `timeout`. The batch count is left for [`posted_batches`].
+pub async fn node_metrics(rpc_url: &str, timeout: Duration) -> Result<ChainMetrics> {
+    let provider = rpc_provider(rpc_url)?;
+    let call = |method: &'static str| {
+        let provider = provider.clone();
+        async move {
+            tokio::time::timeout(timeout, provider.raw_request::<_, Value>(method.into(), ()))
+                .await
+                .map_err(|_| anyhow!("{} timed out after {:?}", method, timeout))?
+                .map_err(|e| anyhow!("{} failed: {}", method, e))
+        }
+    };
+    let quantity = |method, value: Value| {
+        serde_json::from_value::<U64>(value)
+            .map(|quantity| quantity.to::<u64>())
+            .map_err(|e| anyhow!("Invalid {} response: {}", method, e))
+    };
+
+    Ok(ChainMetrics {
+        rpc_url: rpc_url.to_string(),
+        block_number: quantity("eth_blockNumber", call("eth_blockNumber").await?)?,
+        peer_count: quantity("net_peerCount", call("net_peerCount").await?)?,
+        syncing: !SyncStatus::from_syncing(&call("eth_syncing").await?).synced,
+        batch_count: None,
+    })
+}
+
+/// Number of batches posted to `sequencer_inbox` on the parent chain at `rpc_url`
+pub async fn posted_batches(
+    rpc_url: &str,
+    sequencer_inbox: &str,
+    timeout: Duration,
+) -> Result<u64> {
+    let provider = rpc_provider(rpc_url)?;
+    let sequencer_inbox: Address = sequencer_inbox.parse().map_err(|e| {
+        anyhow!(
+            "Invalid sequencer inbox address {:?}: {}",
+            sequencer_inbox,
+            e
+        )
+    })?;
+    let call = serde_json::json!({
+        "to": sequencer_inbox,
+        "data": Bytes::copy_from_slice(&keccak256("batchCount()")[..4]),
+    });
+    let output: Bytes = tokio::time::timeout(
+        timeout,
+        provider.raw_request("eth_call".into(), (call, "latest")),
+    )
+    .await
+    .map_err(|_| anyhow!("Reading the batch count timed out after {:?}", timeout))?
+    .map_err(|e| anyhow!("Failed to read the batch count: {}", e))?;
+    if output.len() != 32 {
+        return Err(anyhow!(
+            "Unexpected batch count of {} bytes from {}",
+            output.len(),
+            sequencer_inbox
+        ));
+    }
+    u64::try_from(U256::from_be_slice(&output))
+        .map_err(|_| anyhow!("Batch count of {} out of range", sequencer_inbox))
+}
+
+/// HTTP provider of the JSON-RPC at `rpc_url`
+fn rpc_provider(rpc_url: &str) -> Result<RootProvider> {
+    let url = rpc_url
+        .parse()
+        .map_err(|e| anyhow!("Invalid RPC URL {:?}: {}", rpc_url, e))?;
+    Ok(RootProvider::new_http(url))
+}
+
+/// Call `eth_blockNumber` on `rpc_url`, giving up after `timeout`
+pub async fn check_rpc(rpc_url: &str, timeout: Duration) -> RollupHealth {
+    let rpc_url = rpc_url.to_string();
+    let provider: RootProvider = match rpc_url.parse() {
+        Ok(url) => RootProvider::new_http(url),
+        Err(e) => {
+            return RollupHealth::Unhealthy {
+                rpc_url,
+                error: format!("Invalid RPC URL: {}", e),
+            };
+        }
+    };
+
+    let started = Instant::now();
+    match tokio::time::timeout(timeout, provider.get_block_number()).await {
+        Ok(Ok(latest_block)) => RollupHealth::Healthy {
+            rpc_url,
+            latency_ms: started.elapsed().as_millis() as u64,
+            latest_block,
+        },
+        Ok(Err(e)) if is_connection_refused(&e) => RollupHealth::ConnectionRefused {
+            rpc_url,
+            error: e.to_string(),
+        },
+        Ok(Err(e)) => RollupHealth::Unhealthy {
+            rpc_url,
+            error: e.to_string(),
+        },
+        Err(_) => RollupHealth::Timeout {
+            rpc_url,
+            timeout_ms: timeout.as_millis() as u64,
+        },
+    }
+}
+
+/// Whether an I/O error refusing the connection caused `error`
+fn is_connection_refused(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if error
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused)
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
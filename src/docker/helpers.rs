@@ -1,32 +1,182 @@
 use blueprint_sdk as sdk;
 
-use crate::{RollupConfig, docker::rollup::RollupManager};
+use crate::deployer::keys::NodeKeyAddresses;
+use crate::deployer::rollup::RetryPolicy;
+use crate::docker::container::{DEFAULT_CONNECT_RETRY, ImageAllowlist, set_connect_retry};
+use crate::docker::decommission::DecommissionReceipt;
+use crate::docker::espresso::{DEFAULT_PROJECT_PREFIX, validate_project_prefix};
+use crate::docker::exec::ExecAllowlist;
+use crate::docker::health::{ChainMetrics, Endpoints, RollupHealth};
+use crate::docker::logs::{DEFAULT_LOGS_COMPRESSION_THRESHOLD, LogsEnvelope};
+use crate::docker::rollup::{
+    CreateRollupResult, DEFAULT_DATA_DIR, DEFAULT_MAX_CONCURRENT_DEPLOYS, DEFAULT_PUBLIC_HOST,
+    DEFAULT_RECONCILE_CONCURRENCY, DEFAULT_RECONCILE_INTERVAL, DEFAULT_RPC_PORT_RANGE,
+    DEFAULT_START_POLL_INTERVAL, DEFAULT_START_TIMEOUT, ListFilter, MAX_LIST_PAGE_SIZE, RollupInfo,
+    RollupManager, STATE_FILE_NAME,
+};
+use crate::{ConfigOverrides, FieldDiff, RaasError, RollupConfig, RollupConfigParams};
 use anyhow::{Result, anyhow};
 use lazy_static::lazy_static;
-use sdk::{error, info};
+use sdk::{error, info, warn};
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-// Singleton for managing rollups
+// Default manager, rooted at `ESPRESSO_DATA_DIR` and rehydrated on first use
 lazy_static! {
-    pub static ref ROLLUP_MANAGER: RollupManager = RollupManager::new();
+    static ref ROLLUP_MANAGER: Arc<RollupManager> = Arc::new(load_rollup_manager());
 }
 
-/// Create a new rollup
+/// The process-wide manager configured from the environment
+///
+/// [`ServiceContext::new`](crate::ServiceContext::new) hands it to the jobs, tests and
+/// embedders build their own [`RollupManager`] instead.
+pub fn default_rollup_manager() -> Arc<RollupManager> {
+    ROLLUP_MANAGER.clone()
+}
+
+fn load_rollup_manager() -> RollupManager {
+    let data_dir = std::env::var("ESPRESSO_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_DATA_DIR));
+    let state_file = std::env::var("ESPRESSO_STATE_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| data_dir.join(STATE_FILE_NAME));
+
+    let manager = RollupManager::load(&state_file).unwrap_or_else(|e| {
+        error!("Failed to load rollup state: {}", e);
+
+        // Keep the unreadable file around instead of overwriting it on the next change
+        let backup = state_file.with_extension("json.corrupt");
+        if let Err(e) = std::fs::rename(&state_file, &backup) {
+            error!("Failed to back up rollup state file: {}", e);
+        }
+        RollupManager::new(&state_file)
+    });
+    let max_concurrent_deploys = std::env::var("ESPRESSO_MAX_CONCURRENT_DEPLOYS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_DEPLOYS);
+
+    let start_timeout = std::env::var("ESPRESSO_START_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_START_TIMEOUT);
+    let start_poll_interval = std::env::var("ESPRESSO_START_POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_START_POLL_INTERVAL);
+
+    // The Docker connection is made per operation, configure it before the first one
+    set_connect_retry(RetryPolicy {
+        max_attempts: std::env::var("ESPRESSO_DOCKER_CONNECT_ATTEMPTS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CONNECT_RETRY.max_attempts),
+        base_delay: std::env::var("ESPRESSO_DOCKER_CONNECT_DELAY_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_CONNECT_RETRY.base_delay),
+    });
+
+    let keep_failed_workspaces = std::env::var("ESPRESSO_KEEP_FAILED_WORKSPACES")
+        .is_ok_and(|value| matches!(value.as_str(), "1" | "true"));
+    let exec_allowlist = match std::env::var("ESPRESSO_EXEC_ALLOWLIST") {
+        Ok(value) => ExecAllowlist::default().with_commands_from(&value),
+        Err(_) => ExecAllowlist::default(),
+    };
+    // Unset allows every image, as before the allowlist existed
+    let allowed_images = std::env::var("ESPRESSO_ALLOWED_IMAGES")
+        .map(|value| ImageAllowlist::from_prefixes(&value))
+        .unwrap_or_default();
+    let rpc_port_range = std::env::var("ESPRESSO_RPC_PORT_RANGE")
+        .ok()
+        .and_then(|value| parse_port_range(&value))
+        .unwrap_or(DEFAULT_RPC_PORT_RANGE);
+    let project_prefix = std::env::var("ESPRESSO_PROJECT_PREFIX")
+        .ok()
+        .filter(|prefix| validate_project_prefix(prefix).is_ok())
+        .unwrap_or_else(|| DEFAULT_PROJECT_PREFIX.to_string());
+    let per_rollup_keys = std::env::var("ESPRESSO_PER_ROLLUP_KEYS")
+        .is_ok_and(|value| matches!(value.as_str(), "1" | "true"));
+    let public_host = std::env::var("ESPRESSO_PUBLIC_HOST")
+        .ok()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| DEFAULT_PUBLIC_HOST.to_string());
+
+    manager
+        .in_base_dir(data_dir)
+        .with_max_concurrent_deploys(max_concurrent_deploys)
+        .with_start_health_check(start_timeout, start_poll_interval)
+        .with_rpc_port_range(rpc_port_range)
+        .with_failed_workspaces_kept(keep_failed_workspaces)
+        .with_exec_allowlist(exec_allowlist)
+        .with_allowed_images(allowed_images)
+        .with_project_prefix(&project_prefix)
+        .with_public_host(&public_host)
+        .with_per_rollup_keys(per_rollup_keys)
+}
+
+/// Parse a port range such as `18547-18646`
+fn parse_port_range(value: &str) -> Option<RangeInclusive<u16>> {
+    let (start, end) = value.split_once('-')?;
+    let range = start.trim().parse().ok()?..=end.trim().parse().ok()?;
+    (!range.is_empty()).then_some(range)
+}
+
+/// Chain IDs the operator keeps out of use, from the comma-separated `ESPRESSO_RESERVED_CHAIN_IDS`
+fn reserved_chain_ids() -> Vec<u64> {
+    std::env::var("ESPRESSO_RESERVED_CHAIN_IDS")
+        .map(|value| parse_chain_ids(&value))
+        .unwrap_or_default()
+}
+
+/// Parse a comma-separated list of chain IDs, skipping invalid entries
+fn parse_chain_ids(value: &str) -> Vec<u64> {
+    value
+        .split(',')
+        .filter_map(|id| id.trim().parse().ok())
+        .collect()
+}
+
+/// Create a new rollup, returning its deployed contracts
 pub async fn create_rollup(
+    manager: &RollupManager,
     service_id: u64,
     rollup_id: &str,
     vm_id: &str,
     config: RollupConfig,
-) -> Result<bool> {
+    request_id: Option<String>,
+) -> Result<CreateRollupResult, RaasError> {
     info!(
         "Creating rollup for service_id: {}, vm_id: {}",
         service_id, vm_id
     );
 
-    // Set workspace and config directories based on VM ID
-    let workspace_dir = PathBuf::from(format!("/tmp/espresso/{}/workspace", vm_id));
-    let config_dir = PathBuf::from(format!("/tmp/espresso/{}/config", vm_id));
+    config
+        .validate_reserving(&reserved_chain_ids())
+        .map_err(RaasError::InvalidConfig)?;
+
+    // Mainnet deployments spend real funds, so they must be enabled explicitly
+    if config.targets_mainnet() {
+        warn!(
+            "MAINNET deployment of rollup {} to {} requested, this spends real ETH",
+            rollup_id, config.network
+        );
+    }
+    let allow_mainnet = std::env::var("ESPRESSO_ALLOW_MAINNET")
+        .is_ok_and(|value| matches!(value.as_str(), "1" | "true"));
+    config
+        .check_mainnet_allowed(allow_mainnet)
+        .map_err(RaasError::InvalidConfig)?;
+
+    // Lay the rollup out under its ID, see `RollupPaths`
+    let (workspace_dir, config_dir) = manager.rollup_dirs(rollup_id);
 
     // Create directories if they don't exist
     std::fs::create_dir_all(&workspace_dir).map_err(|e| {
@@ -45,7 +195,7 @@ pub async fn create_rollup(
     })?;
 
     // Create rollup in the manager
-    match ROLLUP_MANAGER
+    match manager
         .create_rollup(
             service_id,
             rollup_id,
@@ -53,178 +203,548 @@ pub async fn create_rollup(
             config,
             workspace_dir,
             config_dir,
+            request_id,
         )
         .await
     {
-        Ok(rollup_id) => {
-            info!("Created rollup with rollup_id: {}", rollup_id);
-            Ok(true)
+        Ok(created) => {
+            info!("Created rollup with rollup_id: {}", created.rollup_id);
+            Ok(created)
         }
         Err(e) => {
             error!("Failed to create rollup: {}", e);
-            Err(anyhow!("Failed to create rollup: {}", e))
+            Err(e)
         }
     }
 }
 
 /// Start a rollup
-pub async fn start_rollup(rollup_id: &str) -> Result<bool> {
+pub async fn start_rollup(manager: &RollupManager, rollup_id: &str) -> Result<bool, RaasError> {
     info!("Starting rollup for rollup_id: {}", rollup_id);
 
     // Get rollup by service ID
-    let rollup = ROLLUP_MANAGER
+    let rollup = manager
         .get_rollup(rollup_id)
         .await
-        .ok_or_else(|| anyhow!("Rollup not found for rollup_id: {}", rollup_id))?;
+        .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
 
     // Start the rollup
-    match ROLLUP_MANAGER.start_rollup(&rollup.rollup_id).await {
+    match manager.start_rollup(&rollup.rollup_id).await {
         Ok(_) => {
             info!("Started rollup with rollup_id: {}", rollup.rollup_id);
             Ok(true)
         }
         Err(e) => {
             error!("Failed to start rollup: {}", e);
-            Err(anyhow!("Failed to start rollup: {}", e))
+            Err(e)
         }
     }
 }
 
 /// Start a rollup by service ID
-pub async fn start_rollup_by_service_id(service_id: u64) -> Result<bool> {
+pub async fn start_rollup_by_service_id(
+    manager: &RollupManager,
+    service_id: u64,
+) -> Result<bool, RaasError> {
     info!("Starting rollup for service_id: {}", service_id);
 
     // Get rollup by service ID
-    let rollup = ROLLUP_MANAGER
+    let rollup = manager
         .get_rollup_by_service_id(service_id)
         .await
-        .ok_or_else(|| anyhow!("Rollup not found for service_id: {}", service_id))?;
+        .ok_or_else(|| RaasError::RollupNotFound(format!("service_id {}", service_id)))?;
 
     // Start the rollup
-    match ROLLUP_MANAGER.start_rollup(&rollup.rollup_id).await {
+    match manager.start_rollup(&rollup.rollup_id).await {
         Ok(_) => {
             info!("Started rollup with rollup_id: {}", rollup.rollup_id);
             Ok(true)
         }
         Err(e) => {
             error!("Failed to start rollup: {}", e);
-            Err(anyhow!("Failed to start rollup: {}", e))
+            Err(e)
         }
     }
 }
 
 /// Stop a rollup by rollup ID
-pub async fn stop_rollup(rollup_id: &str) -> Result<bool> {
+pub async fn stop_rollup(manager: &RollupManager, rollup_id: &str) -> Result<bool, RaasError> {
     info!("Stopping rollup for rollup_id: {}", rollup_id);
 
     // Get rollup by rollup ID
-    let rollup = ROLLUP_MANAGER
+    let rollup = manager
         .get_rollup(rollup_id)
         .await
-        .ok_or_else(|| anyhow!("Rollup not found for rollup_id: {}", rollup_id))?;
+        .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
 
     // Stop the rollup
-    match ROLLUP_MANAGER.stop_rollup(&rollup.rollup_id).await {
+    match manager.stop_rollup(&rollup.rollup_id).await {
         Ok(_) => {
             info!("Stopped rollup with rollup_id: {}", rollup.rollup_id);
             Ok(true)
         }
         Err(e) => {
             error!("Failed to stop rollup: {}", e);
-            Err(anyhow!("Failed to stop rollup: {}", e))
+            Err(e)
         }
     }
 }
 
 /// Stop a rollup by service ID
-pub async fn stop_rollup_by_service_id(service_id: u64) -> Result<bool> {
+pub async fn stop_rollup_by_service_id(
+    manager: &RollupManager,
+    service_id: u64,
+) -> Result<bool, RaasError> {
     info!("Stopping rollup for service_id: {}", service_id);
 
     // Get rollup by service ID
-    let rollup = ROLLUP_MANAGER
+    let rollup = manager
         .get_rollup_by_service_id(service_id)
         .await
-        .ok_or_else(|| anyhow!("Rollup not found for service_id: {}", service_id))?;
+        .ok_or_else(|| RaasError::RollupNotFound(format!("service_id {}", service_id)))?;
 
     // Stop the rollup
-    match ROLLUP_MANAGER.stop_rollup(&rollup.rollup_id).await {
+    match manager.stop_rollup(&rollup.rollup_id).await {
         Ok(_) => {
             info!("Stopped rollup with rollup_id: {}", rollup.rollup_id);
             Ok(true)
         }
         Err(e) => {
             error!("Failed to stop rollup: {}", e);
-            Err(anyhow!("Failed to stop rollup: {}", e))
+            Err(e)
+        }
+    }
+}
+
+/// Replace the node keys of a rollup, returning the addresses of the new ones
+pub async fn rotate_rollup_keys(
+    manager: &RollupManager,
+    rollup_id: &str,
+) -> Result<NodeKeyAddresses, RaasError> {
+    info!("Rotating the keys of rollup_id: {}", rollup_id);
+
+    match manager.rotate_keys(rollup_id).await {
+        Ok(addresses) => {
+            info!("Rotated the keys of rollup with rollup_id: {}", rollup_id);
+            Ok(addresses)
+        }
+        Err(e) => {
+            error!("Failed to rotate the keys of rollup: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Restart a rollup by rollup ID
+pub async fn restart_rollup(manager: &RollupManager, rollup_id: &str) -> Result<bool, RaasError> {
+    info!("Restarting rollup for rollup_id: {}", rollup_id);
+
+    // Get rollup by rollup ID
+    let rollup = manager
+        .get_rollup(rollup_id)
+        .await
+        .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+
+    // Restart the rollup
+    match manager.restart_rollup(&rollup.rollup_id).await {
+        Ok(_) => {
+            info!("Restarted rollup with rollup_id: {}", rollup.rollup_id);
+            Ok(true)
+        }
+        Err(e) => {
+            error!("Failed to restart rollup: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Check the node RPC of a rollup by rollup ID
+pub async fn check_rollup_health(
+    manager: &RollupManager,
+    rollup_id: &str,
+) -> Result<RollupHealth, RaasError> {
+    info!("Checking health of rollup_id: {}", rollup_id);
+    manager.health_check(rollup_id).await
+}
+
+/// Get the chain metrics of a rollup by rollup ID
+pub async fn get_rollup_metrics(
+    manager: &RollupManager,
+    rollup_id: &str,
+) -> Result<ChainMetrics, RaasError> {
+    info!("Getting chain metrics of rollup_id: {}", rollup_id);
+    manager.chain_metrics(rollup_id).await
+}
+
+/// Run an allowlisted maintenance command in the node container of a rollup by rollup ID
+pub async fn exec_rollup_command(
+    manager: &RollupManager,
+    rollup_id: &str,
+    command: &str,
+) -> Result<String, RaasError> {
+    info!("Running {} in rollup_id: {}", command, rollup_id);
+    manager.exec_command(rollup_id, command).await
+}
+
+/// Upgrade the node image of a rollup to `tag`
+pub async fn upgrade_rollup(
+    manager: &RollupManager,
+    rollup_id: &str,
+    tag: &str,
+) -> Result<bool, RaasError> {
+    info!(
+        "Upgrading rollup_id: {} to node image tag {}",
+        rollup_id, tag
+    );
+
+    match manager.upgrade_rollup(rollup_id, tag).await {
+        Ok(_) => {
+            info!("Upgraded rollup with rollup_id: {}", rollup_id);
+            Ok(true)
+        }
+        Err(e) => {
+            error!("Failed to upgrade rollup: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Get the logs of a rollup's node, compressed above `ESPRESSO_LOGS_COMPRESSION_THRESHOLD` bytes
+pub async fn get_rollup_logs(
+    manager: &RollupManager,
+    rollup_id: &str,
+) -> Result<LogsEnvelope, RaasError> {
+    info!("Getting logs of rollup_id: {}", rollup_id);
+    let logs = manager.logs(rollup_id).await?;
+
+    let threshold = std::env::var("ESPRESSO_LOGS_COMPRESSION_THRESHOLD")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_LOGS_COMPRESSION_THRESHOLD);
+    Ok(LogsEnvelope::encode(&logs, threshold)?)
+}
+
+/// Get the RPC, WebSocket and feed endpoints of a rollup, on the host `ESPRESSO_PUBLIC_HOST` names
+pub async fn get_rollup_endpoints(
+    manager: &RollupManager,
+    rollup_id: &str,
+) -> Result<Endpoints, RaasError> {
+    info!("Getting endpoints of rollup_id: {}", rollup_id);
+    manager.endpoints(rollup_id).await
+}
+
+/// Update the hot-reloadable config of a rollup by rollup ID
+pub async fn update_rollup_config(
+    manager: &RollupManager,
+    rollup_id: &str,
+    params: RollupConfigParams,
+) -> Result<bool, RaasError> {
+    info!("Updating config for rollup_id: {}", rollup_id);
+
+    match manager.update_config(rollup_id, params).await {
+        Ok(_) => {
+            info!("Updated config of rollup with rollup_id: {}", rollup_id);
+            Ok(true)
+        }
+        Err(e) => {
+            error!("Failed to update rollup config: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Regenerate the node config of a rollup by rollup ID without redeploying its contracts
+pub async fn regenerate_rollup_config(
+    manager: &RollupManager,
+    rollup_id: &str,
+    overrides: ConfigOverrides,
+) -> Result<bool, RaasError> {
+    info!("Regenerating config for rollup_id: {}", rollup_id);
+
+    match manager.regenerate_config(rollup_id, overrides).await {
+        Ok(_) => {
+            info!("Regenerated config of rollup with rollup_id: {}", rollup_id);
+            Ok(true)
+        }
+        Err(e) => {
+            error!("Failed to regenerate rollup config: {}", e);
+            Err(e)
         }
     }
 }
 
 /// Delete a rollup by rollup ID
-pub async fn delete_rollup(rollup_id: &str) -> Result<bool> {
+pub async fn delete_rollup(manager: &RollupManager, rollup_id: &str) -> Result<bool, RaasError> {
     info!("Deleting rollup for rollup_id: {}", rollup_id);
 
     // Get rollup by rollup ID
-    let rollup = ROLLUP_MANAGER
+    let rollup = manager
         .get_rollup(rollup_id)
         .await
-        .ok_or_else(|| anyhow!("Rollup not found for rollup_id: {}", rollup_id))?;
+        .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
 
     // Delete the rollup
-    match ROLLUP_MANAGER.delete_rollup(&rollup.rollup_id).await {
+    match manager.delete_rollup(&rollup.rollup_id).await {
         Ok(_) => {
             info!("Deleted rollup with rollup_id: {}", rollup.rollup_id);
             Ok(true)
         }
         Err(e) => {
             error!("Failed to delete rollup: {}", e);
-            Err(anyhow!("Failed to delete rollup: {}", e))
+            Err(e)
         }
     }
 }
 
 /// Delete a rollup by service ID
-pub async fn delete_rollup_by_service_id(service_id: u64) -> Result<bool> {
+pub async fn delete_rollup_by_service_id(
+    manager: &RollupManager,
+    service_id: u64,
+) -> Result<bool, RaasError> {
     info!("Deleting rollup for service_id: {}", service_id);
 
     // Get rollup by service ID
-    let rollup = ROLLUP_MANAGER
+    let rollup = manager
         .get_rollup_by_service_id(service_id)
         .await
-        .ok_or_else(|| anyhow!("Rollup not found for service_id: {}", service_id))?;
+        .ok_or_else(|| RaasError::RollupNotFound(format!("service_id {}", service_id)))?;
 
     // Delete the rollup
-    match ROLLUP_MANAGER.delete_rollup(&rollup.rollup_id).await {
+    match manager.delete_rollup(&rollup.rollup_id).await {
         Ok(_) => {
             info!("Deleted rollup with rollup_id: {}", rollup.rollup_id);
             Ok(true)
         }
         Err(e) => {
             error!("Failed to delete rollup: {}", e);
-            Err(anyhow!("Failed to delete rollup: {}", e))
+            Err(e)
         }
     }
 }
 
+/// Decommission a rollup by rollup ID
+///
+/// The rollup data is archived to `ESPRESSO_ARCHIVE_DIR` unless `skip_archive` is set.
+pub async fn decommission_rollup(
+    manager: &RollupManager,
+    rollup_id: &str,
+    skip_archive: bool,
+) -> Result<DecommissionReceipt, RaasError> {
+    info!("Decommissioning rollup for rollup_id: {}", rollup_id);
+
+    let archive_dir = if skip_archive {
+        None
+    } else {
+        let archive_dir = std::env::var("ESPRESSO_ARCHIVE_DIR").map_err(|_| {
+            anyhow!("ESPRESSO_ARCHIVE_DIR environment variable not set and skip_archive is false")
+        })?;
+        Some(PathBuf::from(archive_dir))
+    };
+
+    match manager
+        .decommission_rollup(rollup_id, archive_dir.as_deref())
+        .await
+    {
+        Ok(receipt) => {
+            info!("Decommissioned rollup with rollup_id: {}", rollup_id);
+            Ok(receipt)
+        }
+        Err(e) => {
+            error!("Failed to decommission rollup: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Get the status of a rollup by rollup ID, e.g. `Running (syncing 45%)`
+pub async fn get_rollup_status_by_id(
+    manager: &RollupManager,
+    rollup_id: &str,
+) -> Result<String, RaasError> {
+    info!("Getting status for rollup with rollup_id: {}", rollup_id);
+
+    let rollup = manager
+        .get_rollup(rollup_id)
+        .await
+        .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+    Ok(rollup.status_summary())
+}
+
+/// Get the full record of a rollup of `service_id` by rollup ID
+///
+/// Rollups of other services are reported as not found
+pub async fn get_rollup_info(
+    manager: &RollupManager,
+    service_id: u64,
+    rollup_id: &str,
+) -> Result<RollupInfo, RaasError> {
+    info!("Getting info for rollup with rollup_id: {}", rollup_id);
+
+    manager
+        .get_rollup(rollup_id)
+        .await
+        .filter(|info| info.service_id == service_id)
+        .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))
+}
+
+/// Get the config fields of a rollup that differ from the defaults
+pub async fn get_rollup_config_diff(
+    manager: &RollupManager,
+    rollup_id: &str,
+) -> Result<Vec<FieldDiff>, RaasError> {
+    info!(
+        "Getting config diff for rollup with rollup_id: {}",
+        rollup_id
+    );
+
+    let rollup = manager
+        .get_rollup(rollup_id)
+        .await
+        .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+
+    Ok(rollup.config.diff_from_default())
+}
+
 /// Get the status of a rollup
-pub async fn get_rollup_status(vm_id: &str) -> Result<String> {
+pub async fn get_rollup_status(manager: &RollupManager, vm_id: &str) -> Result<String, RaasError> {
     info!("Getting status for rollup with vm_id: {}", vm_id);
 
     // Get rollup by VM ID
-    let rollup = ROLLUP_MANAGER
+    let rollup = manager
         .get_rollup_by_vm_id(vm_id)
         .await
-        .ok_or_else(|| anyhow!("Rollup not found for vm_id: {}", vm_id))?;
+        .ok_or_else(|| RaasError::RollupNotFound(format!("vm_id {}", vm_id)))?;
 
     // Get the status
-    Ok(rollup.status.to_string())
+    Ok(rollup.status_summary())
 }
 
-/// List all rollups
-pub async fn list_rollups() -> Vec<HashMap<String, String>> {
+/// Number of rollups inspected at once, from `ESPRESSO_RECONCILE_CONCURRENCY`
+fn reconcile_concurrency() -> usize {
+    std::env::var("ESPRESSO_RECONCILE_CONCURRENCY")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_RECONCILE_CONCURRENCY)
+}
+
+/// Time between reconciliation passes, from `ESPRESSO_RECONCILE_INTERVAL_SECS`
+fn reconcile_interval() -> Duration {
+    std::env::var("ESPRESSO_RECONCILE_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RECONCILE_INTERVAL)
+}
+
+/// Reconcile persisted rollup statuses with the containers running on the host
+pub async fn reconcile_rollups(manager: &RollupManager) -> Result<(), RaasError> {
+    info!("Reconciling rollup statuses with Docker");
+    manager.reconcile(reconcile_concurrency()).await
+}
+
+/// Spawn a task that keeps reconciling rollup statuses in the background
+///
+/// Each pass also records how far the nodes of the running rollups have synced.
+/// Passes never overlap: the next one starts one interval after the previous one
+/// started, or right away when a pass took longer than the interval.
+pub fn spawn_reconciler(manager: Arc<RollupManager>) -> tokio::task::JoinHandle<()> {
+    let interval = reconcile_interval();
+    let concurrency = reconcile_concurrency();
+    info!(
+        "Reconciling rollups every {}s with concurrency {}",
+        interval.as_secs(),
+        concurrency
+    );
+
+    tokio::spawn(async move {
+        let mut wait = interval;
+        loop {
+            tokio::time::sleep(wait).await;
+
+            let started = Instant::now();
+            if let Err(e) = manager.reconcile(concurrency).await {
+                error!("Failed to reconcile rollups: {}", e);
+            }
+            manager.check_sync_all(concurrency).await;
+            let elapsed = started.elapsed();
+            if elapsed > interval {
+                info!(
+                    "Reconciliation took {}s, longer than the {}s interval",
+                    elapsed.as_secs(),
+                    interval.as_secs()
+                );
+            }
+            wait = interval.saturating_sub(elapsed);
+        }
+    })
+}
+
+/// Stop the running rollups before the process exits
+///
+/// Skipped when `ESPRESSO_KEEP_ROLLUPS_RUNNING` is set, leaving the containers
+/// running independently of the blueprint.
+pub async fn shutdown_rollups(manager: &RollupManager) {
+    let keep_running = std::env::var("ESPRESSO_KEEP_ROLLUPS_RUNNING")
+        .is_ok_and(|value| matches!(value.as_str(), "1" | "true"));
+    if keep_running {
+        info!("Leaving rollups running on shutdown");
+        return;
+    }
+
+    info!("Stopping running rollups before shutdown");
+    let stopped = manager.stop_all().await;
+    info!("Stopped {} rollups", stopped.len());
+}
+
+/// Start the created or stopped rollups of a service
+pub async fn start_service_rollups(
+    manager: &RollupManager,
+    service_id: u64,
+) -> Vec<(String, Result<(), RaasError>)> {
+    info!("Starting rollups of service_id: {}", service_id);
+    let results = manager.start_all_for_service(service_id).await;
+    for (rollup_id, result) in &results {
+        if let Err(e) = result {
+            error!("Failed to start rollup {}: {}", rollup_id, e);
+        }
+    }
+    results
+}
+
+/// Stop the running rollups of a service
+pub async fn stop_service_rollups(
+    manager: &RollupManager,
+    service_id: u64,
+) -> Vec<(String, Result<(), RaasError>)> {
+    info!("Stopping rollups of service_id: {}", service_id);
+    let results = manager.stop_all_for_service(service_id).await;
+    for (rollup_id, result) in &results {
+        if let Err(e) = result {
+            error!("Failed to stop rollup {}: {}", rollup_id, e);
+        }
+    }
+    results
+}
+
+/// Delete the rollups of a service that have been failed for longer than `older_than`
+pub async fn prune_service_rollups(
+    manager: &RollupManager,
+    service_id: u64,
+    older_than: Duration,
+) -> Vec<String> {
+    info!(
+        "Pruning rollups of service_id: {} failed for more than {:?}",
+        service_id, older_than
+    );
+    manager.prune_for_service(service_id, older_than).await
+}
+
+/// List all rollups, newest first
+pub async fn list_rollups(manager: &RollupManager) -> Vec<HashMap<String, String>> {
     info!("Listing all rollups");
 
     // Get all rollups
-    let rollups = ROLLUP_MANAGER.list_rollups().await;
+    let rollups = manager.list_rollups_filtered(ListFilter::default()).await;
 
     // Convert to a simpler format
     rollups
@@ -235,8 +755,35 @@ pub async fn list_rollups() -> Vec<HashMap<String, String>> {
             map.insert("rollup_id".to_string(), rollup.rollup_id);
             map.insert("vm_id".to_string(), rollup.vm_id);
             map.insert("status".to_string(), rollup.status.to_string());
-            map.insert("created_at".to_string(), rollup.created_at);
+            map.insert("created_at".to_string(), rollup.created_at.to_rfc3339());
+            map.insert("updated_at".to_string(), rollup.updated_at.to_rfc3339());
             map
         })
         .collect()
 }
+
+/// A page of the rollups of a service, with the number of rollups the service has
+///
+/// Pages list the oldest rollups first, so new rollups don't shift them. `limit` is capped
+/// at [`MAX_LIST_PAGE_SIZE`].
+pub async fn list_service_rollups_page(
+    manager: &RollupManager,
+    service_id: u64,
+    offset: usize,
+    limit: usize,
+) -> (Vec<RollupInfo>, usize) {
+    info!(
+        "Listing rollups of service_id: {} from {} (limit {})",
+        service_id, offset, limit
+    );
+
+    let filter = ListFilter {
+        service_id: Some(service_id),
+        offset: Some(offset),
+        limit: Some(limit.min(MAX_LIST_PAGE_SIZE)),
+        oldest_first: true,
+        ..Default::default()
+    };
+    let total = manager.count_rollups(&filter).await;
+    (manager.list_rollups_filtered(filter).await, total)
+}
@@ -1,18 +1,21 @@
 use blueprint_sdk as sdk;
 
-use crate::{RollupConfig, RollupConfigParams};
+use crate::{ConfigOverrides, NetworkType, RollupConfig, RollupConfigParams, ServiceContext};
 use anyhow::Result;
-use sdk::tangle::extract::{ServiceId, TangleArg, TangleResult};
+use sdk::extract::Context;
+use sdk::tangle::extract::{Optional, ServiceId, TangleArg, TangleArgs2, TangleResult};
 use uuid::Uuid;
 
 /// Create a new Docker-based rollup
 ///
-/// Returns the ID of the created rollup
+/// Returns a JSON [`CreateRollupResult`](crate::docker::rollup::CreateRollupResult) with the ID and contracts of the created rollup
 pub async fn create_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
     ServiceId(service_id): ServiceId,
     TangleArg(config_params): TangleArg<RollupConfigParams>,
 ) -> Result<TangleResult<String>> {
-    // Convert to RollupConfig
+    // Convert to RollupConfig, configs that can't be deployed are rejected on creation
+    let request_id = config_params.request_id.clone();
     let config = RollupConfig::from(config_params);
 
     sdk::info!(
@@ -25,15 +28,24 @@ pub async fn create_docker_rollup(
     let rollup_id = Uuid::new_v4().to_string();
     let vm_id = format!("docker-rollup-{}-{}", service_id, rollup_id);
 
-    // Create and start the Docker-based rollup
-    let created = crate::create_rollup(service_id, &rollup_id, &vm_id, config).await?;
+    // Create and start the Docker-based rollup, a retried request returns the first rollup
+    let created = crate::create_rollup(
+        &ctx.rollups,
+        service_id,
+        &rollup_id,
+        &vm_id,
+        config,
+        request_id,
+    )
+    .await?;
 
     sdk::info!("Docker rollup created: {:?}", created);
-    Ok(TangleResult(rollup_id))
+    Ok(TangleResult(serde_json::to_string(&created)?))
 }
 
 /// Start an existing Docker-based rollup
 pub async fn start_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
     ServiceId(service_id): ServiceId,
     TangleArg(rollup_id): TangleArg<String>,
 ) -> Result<TangleResult<bool>> {
@@ -44,7 +56,7 @@ pub async fn start_docker_rollup(
     );
 
     // Start the Docker-based rollup
-    let started = crate::start_rollup(&rollup_id).await?;
+    let started = crate::start_rollup(&ctx.rollups, &rollup_id).await?;
 
     sdk::info!("Docker rollup started: {:?}", started);
     Ok(TangleResult(started))
@@ -52,6 +64,7 @@ pub async fn start_docker_rollup(
 
 /// Stop an existing Docker-based rollup
 pub async fn stop_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
     ServiceId(service_id): ServiceId,
     TangleArg(rollup_id): TangleArg<String>,
 ) -> Result<TangleResult<bool>> {
@@ -62,14 +75,208 @@ pub async fn stop_docker_rollup(
     );
 
     // Stop the Docker-based rollup
-    let stopped = crate::stop_rollup(&rollup_id).await?;
+    let stopped = crate::stop_rollup(&ctx.rollups, &rollup_id).await?;
 
     sdk::info!("Docker rollup stopped: {:?}", stopped);
     Ok(TangleResult(stopped))
 }
 
+/// Restart an existing Docker-based rollup
+pub async fn restart_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<bool>> {
+    sdk::info!(
+        "Restarting Docker-based rollup for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may restart it
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    // Restart the Docker-based rollup
+    let restarted = crate::restart_rollup(&ctx.rollups, &rollup_id).await?;
+
+    sdk::info!("Docker rollup restarted: {:?}", restarted);
+    Ok(TangleResult(restarted))
+}
+
+/// Update the config of an existing Docker-based rollup
+///
+/// Takes the rollup ID and the full new config. Only the hot-reloadable fields listed in
+/// [`RollupConfig::immutable_changes`] may differ, a running rollup is restarted to apply them
+pub async fn update_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArgs2(rollup_id, config_params): TangleArgs2<String, RollupConfigParams>,
+) -> Result<TangleResult<bool>> {
+    sdk::info!(
+        "Updating Docker-based rollup for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may change its config
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    // Regenerate the node config and restart the rollup
+    let updated =
+        crate::docker::helpers::update_rollup_config(&ctx.rollups, &rollup_id, config_params)
+            .await?;
+
+    sdk::info!("Docker rollup updated: {:?}", updated);
+    Ok(TangleResult(updated))
+}
+
+/// Regenerate the node config of an existing Docker-based rollup
+///
+/// Takes the rollup ID and the [`ConfigOverrides`] to apply. The contracts aren't redeployed,
+/// the config is rendered against the recorded addresses and a running rollup is restarted
+pub async fn regenerate_docker_rollup_config(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArgs2(rollup_id, overrides): TangleArgs2<String, ConfigOverrides>,
+) -> Result<TangleResult<bool>> {
+    sdk::info!(
+        "Regenerating Docker-based rollup config for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may override its image or environment
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let regenerated =
+        crate::docker::helpers::regenerate_rollup_config(&ctx.rollups, &rollup_id, overrides)
+            .await?;
+
+    sdk::info!("Docker rollup config regenerated: {:?}", regenerated);
+    Ok(TangleResult(regenerated))
+}
+
+/// Upgrade the node image of an existing Docker-based rollup
+///
+/// Takes the rollup ID and the new tag of the nitro node image. A running rollup is
+/// recreated on the new image, and put back on the previous one if it doesn't come up
+pub async fn upgrade_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArgs2(rollup_id, tag): TangleArgs2<String, String>,
+) -> Result<TangleResult<bool>> {
+    sdk::info!(
+        "Upgrading Docker-based rollup for service_id: {} with rollup_id: {} to {}",
+        service_id,
+        rollup_id,
+        tag
+    );
+
+    // Only the service owning the rollup may change its node image
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let upgraded = crate::docker::helpers::upgrade_rollup(&ctx.rollups, &rollup_id, &tag).await?;
+
+    sdk::info!("Docker rollup upgraded: {:?}", upgraded);
+    Ok(TangleResult(upgraded))
+}
+
+/// Replace the validator and batch poster keys of a Docker-based rollup
+///
+/// Only rollups with keys of their own can rotate them. Returns the JSON
+/// [`NodeKeyAddresses`](crate::deployer::keys::NodeKeyAddresses) of the new keys, which the
+/// chain owner has to grant the validator and batch poster roles
+pub async fn rotate_docker_rollup_keys(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Rotating the keys of Docker-based rollup for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may rotate its keys
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let addresses = crate::docker::helpers::rotate_rollup_keys(&ctx.rollups, &rollup_id).await?;
+    Ok(TangleResult(serde_json::to_string(&addresses)?))
+}
+
+/// Start every created or stopped Docker-based rollup of the calling service
+///
+/// Returns a JSON array of `{rollup_id, ok, error}`, one per rollup
+pub async fn start_docker_rollups_for_service(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Starting Docker-based rollups for service_id: {}",
+        service_id
+    );
+
+    let results = crate::docker::helpers::start_service_rollups(&ctx.rollups, service_id).await;
+    Ok(TangleResult(batch_summary(results)?))
+}
+
+/// Stop every running Docker-based rollup of the calling service
+///
+/// Returns a JSON array of `{rollup_id, ok, error}`, one per rollup
+pub async fn stop_docker_rollups_for_service(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Stopping Docker-based rollups for service_id: {}",
+        service_id
+    );
+
+    let results = crate::docker::helpers::stop_service_rollups(&ctx.rollups, service_id).await;
+    Ok(TangleResult(batch_summary(results)?))
+}
+
+/// Delete the Docker-based rollups of the calling service that have been failed for
+/// longer than `older_than_secs`
+///
+/// Returns the JSON array of the pruned rollup IDs. Rollups that aren't failed are never touched.
+pub async fn prune_docker_rollups(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(older_than_secs): TangleArg<u64>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Pruning Docker-based rollups for service_id: {}",
+        service_id
+    );
+
+    let pruned = crate::docker::helpers::prune_service_rollups(
+        &ctx.rollups,
+        service_id,
+        std::time::Duration::from_secs(older_than_secs),
+    )
+    .await;
+    Ok(TangleResult(serde_json::to_string(&pruned)?))
+}
+
+/// Encode the per-rollup outcomes of a batch operation
+fn batch_summary(results: Vec<(String, Result<(), crate::RaasError>)>) -> Result<String> {
+    let summary: Vec<serde_json::Value> = results
+        .into_iter()
+        .map(|(rollup_id, result)| {
+            serde_json::json!({
+                "rollup_id": rollup_id,
+                "ok": result.is_ok(),
+                "error": result.err().map(|e| e.to_string()),
+            })
+        })
+        .collect();
+    Ok(serde_json::to_string(&summary)?)
+}
+
 /// Delete a Docker-based rollup
 pub async fn delete_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
     ServiceId(service_id): ServiceId,
     TangleArg(rollup_id): TangleArg<String>,
 ) -> Result<TangleResult<bool>> {
@@ -80,6 +287,322 @@ pub async fn delete_docker_rollup(
     );
 
     // Delete the Docker-based rollup
-    let deleted = crate::delete_rollup(&rollup_id).await?;
+    let deleted = crate::delete_rollup(&ctx.rollups, &rollup_id).await?;
     Ok(TangleResult(deleted))
 }
+
+/// Permanently decommission a Docker-based rollup
+///
+/// Takes the rollup ID and an optional `skip_archive` flag.
+/// Returns the JSON-encoded [`DecommissionReceipt`](crate::docker::decommission::DecommissionReceipt)
+pub async fn decommission_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArgs2(rollup_id, Optional(skip_archive)): TangleArgs2<String, Optional<bool>>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Decommissioning Docker-based rollup for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may decommission it
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let receipt = crate::docker::helpers::decommission_rollup(
+        &ctx.rollups,
+        &rollup_id,
+        skip_archive.unwrap_or(false),
+    )
+    .await?;
+
+    sdk::info!("Docker rollup decommissioned: {:?}", receipt);
+    Ok(TangleResult(serde_json::to_string(&receipt)?))
+}
+
+/// Get the status of a Docker-based rollup
+///
+/// Running rollups also report how far their node has synced once checked, e.g.
+/// `Running (syncing 45%)` or `Running (synced)`
+pub async fn get_docker_rollup_status(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Getting Docker-based rollup status for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Rollups of other services are reported as not found
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let status = crate::docker::helpers::get_rollup_status_by_id(&ctx.rollups, &rollup_id).await?;
+    Ok(TangleResult(status))
+}
+
+/// Get the full record of a Docker-based rollup
+///
+/// Returns the JSON-encoded [`RollupInfo`](crate::docker::rollup::RollupInfo): config,
+/// directories, deployed addresses and timestamps. Keys are never part of it
+pub async fn get_docker_rollup_info(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Getting Docker-based rollup info for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    let info =
+        crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+    Ok(TangleResult(serde_json::to_string(&info)?))
+}
+
+/// Get the logs of the node of a Docker-based rollup
+///
+/// Returns the JSON-encoded [`LogsEnvelope`](crate::docker::logs::LogsEnvelope), large
+/// logs are gzip-compressed and base64-encoded to keep the result small
+pub async fn get_docker_rollup_logs(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Getting Docker-based rollup logs for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may read its logs
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let logs = crate::docker::helpers::get_rollup_logs(&ctx.rollups, &rollup_id).await?;
+    Ok(TangleResult(serde_json::to_string(&logs)?))
+}
+
+/// Get the externally reachable endpoints of a Docker-based rollup
+///
+/// Returns the JSON-encoded [`Endpoints`](crate::docker::health::Endpoints)
+pub async fn get_docker_rollup_endpoints(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Getting Docker-based rollup endpoints for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup learns where its node is reachable
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let endpoints = crate::docker::helpers::get_rollup_endpoints(&ctx.rollups, &rollup_id).await?;
+    Ok(TangleResult(serde_json::to_string(&endpoints)?))
+}
+
+/// Get the block number, peer count, sync state and posted batches of a Docker-based rollup
+///
+/// Returns the JSON-encoded [`ChainMetrics`](crate::docker::health::ChainMetrics)
+pub async fn get_docker_rollup_metrics(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Getting Docker-based rollup metrics for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may read its chain metrics
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let metrics = crate::docker::helpers::get_rollup_metrics(&ctx.rollups, &rollup_id).await?;
+    Ok(TangleResult(serde_json::to_string(&metrics)?))
+}
+
+/// Check that the RPC of a Docker-based rollup answers
+///
+/// Returns the JSON-encoded [`RollupHealth`](crate::docker::health::RollupHealth)
+pub async fn check_docker_rollup_health(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Checking Docker-based rollup health for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may probe its node
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let health = crate::docker::helpers::check_rollup_health(&ctx.rollups, &rollup_id).await?;
+
+    sdk::info!("Docker rollup health: {:?}", health);
+    Ok(TangleResult(serde_json::to_string(&health)?))
+}
+
+/// Run a maintenance command in the node container of a Docker-based rollup
+///
+/// Takes the rollup ID and the name of a command on the operator's allowlist, see
+/// [`ExecAllowlist`](crate::docker::exec::ExecAllowlist). Returns the command's output
+pub async fn exec_docker_rollup(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArgs2(rollup_id, command): TangleArgs2<String, String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Running {} in Docker-based rollup for service_id: {} with rollup_id: {}",
+        command,
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may run commands in it
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let output =
+        crate::docker::helpers::exec_rollup_command(&ctx.rollups, &rollup_id, &command).await?;
+
+    sdk::info!("Docker rollup command finished");
+    Ok(TangleResult(output))
+}
+
+/// List the Docker-based rollups owned by the calling service, newest first
+///
+/// Returns a JSON array of `{rollup_id, vm_id, status, created_at, updated_at}`
+pub async fn list_docker_rollups(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Listing Docker-based rollups for service_id: {}",
+        service_id
+    );
+
+    // Only expose rollups created by this service
+    let service_id = service_id.to_string();
+    let rollups: Vec<serde_json::Value> = crate::list_rollups(&ctx.rollups)
+        .await
+        .into_iter()
+        .filter(|rollup| rollup.get("service_id") == Some(&service_id))
+        .map(|rollup| {
+            serde_json::json!({
+                "rollup_id": rollup.get("rollup_id"),
+                "vm_id": rollup.get("vm_id"),
+                "status": rollup.get("status"),
+                "created_at": rollup.get("created_at"),
+                "updated_at": rollup.get("updated_at"),
+            })
+        })
+        .collect();
+
+    Ok(TangleResult(serde_json::to_string(&rollups)?))
+}
+
+/// List one page of the Docker-based rollups owned by the calling service
+///
+/// Takes the offset and the page size, capped at
+/// [`MAX_LIST_PAGE_SIZE`](crate::docker::rollup::MAX_LIST_PAGE_SIZE). Returns JSON
+/// `{items, total}`, with items shaped like those of [`list_docker_rollups`]
+pub async fn list_docker_rollups_paged(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArgs2(offset, limit): TangleArgs2<u64, u64>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Listing Docker-based rollups for service_id: {} from {} (limit {})",
+        service_id,
+        offset,
+        limit
+    );
+
+    let (rollups, total) = crate::docker::helpers::list_service_rollups_page(
+        &ctx.rollups,
+        service_id,
+        usize::try_from(offset).unwrap_or(usize::MAX),
+        usize::try_from(limit).unwrap_or(usize::MAX),
+    )
+    .await;
+    let items: Vec<serde_json::Value> = rollups
+        .into_iter()
+        .map(|rollup| {
+            serde_json::json!({
+                "rollup_id": rollup.rollup_id,
+                "vm_id": rollup.vm_id,
+                "status": rollup.status.to_string(),
+                "created_at": rollup.created_at.to_rfc3339(),
+                "updated_at": rollup.updated_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    Ok(TangleResult(serde_json::to_string(
+        &serde_json::json!({ "items": items, "total": total }),
+    )?))
+}
+
+/// Get the config fields of a Docker-based rollup that differ from the defaults
+///
+/// Returns a JSON array of [`FieldDiff`](crate::FieldDiff)
+pub async fn get_docker_rollup_config_diff(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+    TangleArg(rollup_id): TangleArg<String>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Getting Docker-based rollup config diff for service_id: {} with rollup_id: {}",
+        service_id,
+        rollup_id
+    );
+
+    // Only the service owning the rollup may read its config
+    crate::docker::helpers::get_rollup_info(&ctx.rollups, service_id, &rollup_id).await?;
+
+    let diff = crate::docker::helpers::get_rollup_config_diff(&ctx.rollups, &rollup_id).await?;
+    Ok(TangleResult(serde_json::to_string(&diff)?))
+}
+
+/// Check that a parent chain is usable before creating a rollup on it
+///
+/// Takes the network and an optional RPC URL overriding the network default.
+/// Returns the JSON-encoded [`NetworkDiagnostics`](crate::diagnostics::NetworkDiagnostics) report
+pub async fn diagnose_network(
+    ServiceId(service_id): ServiceId,
+    TangleArgs2(network, Optional(rpc_url)): TangleArgs2<NetworkType, Optional<String>>,
+) -> Result<TangleResult<String>> {
+    sdk::info!(
+        "Diagnosing network {} for service_id: {}",
+        network,
+        service_id
+    );
+
+    let deployer = crate::diagnostics::deployer_address_from_env();
+    let report = crate::diagnostics::diagnose_network(network, rpc_url, deployer).await;
+
+    sdk::info!("Network diagnostics: {:?}", report);
+    Ok(TangleResult(serde_json::to_string(&report)?))
+}
+
+/// Check that the host has the tools and Docker daemon creating and running rollups needs
+///
+/// Returns the JSON-encoded [`PreflightReport`](crate::diagnostics::PreflightReport), listing
+/// everything missing at once
+pub async fn diagnose_host(
+    Context(ctx): Context<ServiceContext>,
+    ServiceId(service_id): ServiceId,
+) -> Result<TangleResult<String>> {
+    sdk::info!("Diagnosing the host for service_id: {}", service_id);
+
+    let report = ctx.rollups.preflight().await;
+
+    sdk::info!("Host diagnostics: {:?}", report);
+    Ok(TangleResult(serde_json::to_string(&report)?))
+}
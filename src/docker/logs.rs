@@ -0,0 +1,70 @@
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// Default size in bytes above which logs are compressed before being returned
+pub const DEFAULT_LOGS_COMPRESSION_THRESHOLD: usize = 64 * 1024;
+
+/// How the `data` of a [`LogsEnvelope`] is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogsEncoding {
+    /// The logs as they are
+    #[serde(rename = "plain")]
+    Plain,
+    /// The gzip-compressed logs, base64-encoded
+    #[serde(rename = "gzip+base64")]
+    GzipBase64,
+}
+
+/// Container logs as returned by the `get_docker_rollup_logs` job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogsEnvelope {
+    /// Encoding of `data`
+    pub encoding: LogsEncoding,
+    /// The logs
+    pub data: String,
+}
+
+impl LogsEnvelope {
+    /// Wrap `logs`, compressing them when they are larger than `threshold` bytes
+    pub fn encode(logs: &str, threshold: usize) -> Result<Self> {
+        if logs.len() <= threshold {
+            return Ok(Self {
+                encoding: LogsEncoding::Plain,
+                data: logs.to_string(),
+            });
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(logs.as_bytes())?;
+        let compressed = encoder
+            .finish()
+            .map_err(|e| anyhow!("Failed to compress logs: {}", e))?;
+        Ok(Self {
+            encoding: LogsEncoding::GzipBase64,
+            data: STANDARD.encode(compressed),
+        })
+    }
+
+    /// The logs, decompressed if needed
+    pub fn decode(&self) -> Result<String> {
+        match self.encoding {
+            LogsEncoding::Plain => Ok(self.data.clone()),
+            LogsEncoding::GzipBase64 => {
+                let compressed = STANDARD
+                    .decode(&self.data)
+                    .map_err(|e| anyhow!("Invalid base64 logs: {}", e))?;
+                let mut logs = String::new();
+                GzDecoder::new(compressed.as_slice())
+                    .read_to_string(&mut logs)
+                    .map_err(|e| anyhow!("Failed to decompress logs: {}", e))?;
+                Ok(logs)
+            }
+        }
+    }
+}
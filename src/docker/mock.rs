@@ -0,0 +1,153 @@
+use crate::docker::container::{ComposeRejected, ServiceState};
+use crate::docker::health::{Endpoints, NODE_RPC_PORT, NODE_SERVICE};
+use crate::docker::rollup::RollupInfo;
+use crate::docker::runtime::ContainerRuntime;
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// [`ContainerRuntime`] keeping track of running rollups in memory, for exercising rollups without Docker
+///
+/// Clones share the set of running projects, so one `MockRuntime` can hand out
+/// a runtime per rollup with [`MockRuntime::for_rollup`] and observe them all.
+#[derive(Debug, Clone, Default)]
+pub struct MockRuntime {
+    running: Arc<Mutex<HashSet<String>>>,
+    project: String,
+    failing: Option<String>,
+    rejecting: Option<String>,
+    unhealthy: bool,
+    rpc_port: u16,
+    ws_port: Option<u16>,
+    feed_port: Option<u16>,
+}
+
+impl MockRuntime {
+    /// A runtime failing every start with `error`
+    pub fn failing(error: &str) -> Self {
+        Self {
+            failing: Some(error.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// A runtime rejecting the compose file of every start with `error`
+    pub fn rejecting(error: &str) -> Self {
+        Self {
+            rejecting: Some(error.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// A runtime whose containers start but never report healthy
+    pub fn unhealthy() -> Self {
+        Self {
+            unhealthy: true,
+            ..Self::default()
+        }
+    }
+
+    /// Runtime of the containers of `info`
+    pub fn for_rollup(&self, info: &RollupInfo) -> Self {
+        Self {
+            project: info.vm_id.clone(),
+            rpc_port: info.rpc_port.unwrap_or(NODE_RPC_PORT),
+            ws_port: info.ws_port,
+            feed_port: info.feed_port,
+            ..self.clone()
+        }
+    }
+
+    /// Whether the containers of the rollup on `vm_id` are running
+    pub fn is_running(&self, vm_id: &str) -> bool {
+        self.running().contains(vm_id)
+    }
+
+    fn running(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.running.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl ContainerRuntime for MockRuntime {
+    async fn start(&mut self) -> Result<()> {
+        if let Some(error) = &self.failing {
+            return Err(anyhow!(error.clone()));
+        }
+        if let Some(error) = &self.rejecting {
+            return Err(ComposeRejected::wrap(anyhow!(error.clone())));
+        }
+        self.running().insert(self.project.clone());
+        Ok(())
+    }
+
+    async fn wait_until_healthy(&self, _timeout: Duration, _poll_interval: Duration) -> Result<()> {
+        if !self.is_running(&self.project) {
+            return Err(anyhow!("Node of VM ID {} is not running", self.project));
+        }
+        if self.unhealthy {
+            return Err(anyhow!("Node of VM ID {} is not healthy", self.project));
+        }
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        self.running().remove(&self.project);
+        Ok(())
+    }
+
+    async fn status(&self) -> Result<String> {
+        let status = if self.is_running(&self.project) {
+            "running"
+        } else {
+            "NotRunning"
+        };
+        Ok(status.to_string())
+    }
+
+    async fn service_statuses(&self) -> Result<Vec<(String, String)>> {
+        Ok(vec![(NODE_SERVICE.to_string(), self.status().await?)])
+    }
+
+    async fn container_states(&self) -> Result<Vec<ServiceState>> {
+        // Stopped projects have no containers left, like after `docker compose down`
+        if !self.is_running(&self.project) {
+            return Ok(Vec::new());
+        }
+        Ok(vec![ServiceState {
+            name: format!("{}-{}", self.project, NODE_SERVICE),
+            state: "running".to_string(),
+        }])
+    }
+
+    async fn teardown(&self) -> Result<()> {
+        self.running().remove(&self.project);
+        Ok(())
+    }
+
+    async fn pull_images(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Echoes `command` back as its output
+    async fn exec(&self, command: &[&str]) -> Result<String> {
+        if !self.is_running(&self.project) {
+            return Err(anyhow!("Node of VM ID {} is not running", self.project));
+        }
+        Ok(command.join(" "))
+    }
+
+    async fn logs(&self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    fn endpoints(&self, host: &str) -> Result<Endpoints> {
+        let url = |scheme: &str, port: u16| format!("{}://{}:{}", scheme, host, port);
+        Ok(Endpoints {
+            rpc: url("http", self.rpc_port),
+            ws: self.ws_port.map(|port| url("ws", port)),
+            feed: self.feed_port.map(|port| url("ws", port)),
+        })
+    }
+}
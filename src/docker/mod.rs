@@ -1,22 +1,52 @@
 pub mod container;
+pub mod decommission;
 pub mod espresso;
+pub mod exec;
+pub mod health;
 pub mod helpers;
 pub mod jobs;
+pub mod logs;
+pub mod mock;
 pub mod rollup;
+pub mod runtime;
 
 // Re-export public types from container
-pub use container::DockerComposeManager;
+pub use container::{DockerComposeManager, ImageAllowlist};
 
 // Re-export helper functions
 pub use helpers::{
-    create_rollup, delete_rollup, get_rollup_status, list_rollups, start_rollup, stop_rollup,
+    create_rollup, delete_rollup, get_rollup_status, list_rollups, reconcile_rollups,
+    restart_rollup, shutdown_rollups, spawn_reconciler, start_rollup, stop_rollup,
 };
 
+// Re-export decommission types
+pub use decommission::DecommissionReceipt;
+
+// Re-export exec types
+pub use exec::ExecAllowlist;
+
+// Re-export health types
+pub use health::{Endpoints, RollupHealth, SyncStatus};
+
+// Re-export logs types
+pub use logs::{LogsEncoding, LogsEnvelope};
+
 // Re-export rollup types
 pub use espresso::EspressoDockerManager;
-pub use rollup::{RollupInfo, RollupManager, RollupStatus};
+pub use mock::MockRuntime;
+pub use rollup::{
+    CreateRollupResult, CreationPhase, ListFilter, RollupInfo, RollupManager, RollupStatus,
+};
+pub use runtime::ContainerRuntime;
 
 // Reexport from jobs
 pub use jobs::{
-    create_docker_rollup, delete_docker_rollup, start_docker_rollup, stop_docker_rollup,
+    check_docker_rollup_health, create_docker_rollup, decommission_docker_rollup,
+    delete_docker_rollup, diagnose_host, diagnose_network, exec_docker_rollup,
+    get_docker_rollup_config_diff, get_docker_rollup_endpoints, get_docker_rollup_info,
+    get_docker_rollup_logs, get_docker_rollup_metrics, get_docker_rollup_status,
+    list_docker_rollups, list_docker_rollups_paged, prune_docker_rollups,
+    regenerate_docker_rollup_config, restart_docker_rollup, rotate_docker_rollup_keys,
+    start_docker_rollup, start_docker_rollups_for_service, stop_docker_rollup,
+    stop_docker_rollups_for_service, update_docker_rollup, upgrade_docker_rollup,
 };
@@ -1,22 +1,104 @@
 use blueprint_sdk as sdk;
 
-use crate::RollupConfig;
-use crate::deployer::config::ConfigGenerator;
-use crate::deployer::rollup::{DeploymentConfig, RollupDeployer};
-use crate::docker::espresso::EspressoDockerManager;
+use crate::RaasError;
+use crate::deployer::config::{ConfigGenerator, checksum_address, validate_image_tag};
+use crate::deployer::keys::{NODE_KEYS_DIR_NAME, NodeKeyAddresses, NodeKeys, RollupKey};
+use crate::deployer::rollup::{
+    DeployCancelled, DeployProgress, DeploymentConfig, RollupDeployer, cancelled,
+};
+use crate::deployer::{Deploy, DeploymentResult};
+use crate::diagnostics::{PreflightReport, REQUIRED_TOOLS, preflight};
+use crate::docker::container::{ComposeRejected, ImageAllowlist, ServiceState, service_failures};
+use crate::docker::decommission::{DecommissionReceipt, archive_rollup_data};
+use crate::docker::espresso::{DEFAULT_PROJECT_PREFIX, EspressoDockerManager};
+use crate::docker::exec::ExecAllowlist;
+use crate::docker::health::{
+    ChainMetrics, Endpoints, HEALTH_CHECK_TIMEOUT, NODE_RPC_PORT, RollupHealth, SyncStatus,
+    check_rpc, node_metrics, node_sync_status, posted_batches,
+};
+use crate::docker::runtime::ContainerRuntime;
+use crate::metrics::metrics;
+use crate::{ConfigOverrides, RollupConfig, RollupConfigParams};
 use anyhow::{Result, anyhow};
-use sdk::{error, info};
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, stream};
+use sdk::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore, mpsc, watch};
+
+/// Default directory holding the rollup state and the per-rollup directories
+pub const DEFAULT_DATA_DIR: &str = "/var/lib/espresso-raas";
+
+/// Name of the persisted rollup state inside the data directory
+pub const STATE_FILE_NAME: &str = "state.json";
+
+/// Default number of rollups inspected at once during reconciliation
+pub const DEFAULT_RECONCILE_CONCURRENCY: usize = 8;
+
+/// Default time between the start of two reconciliation passes
+pub const DEFAULT_RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default number of contract deployments allowed to run at once
+pub const DEFAULT_MAX_CONCURRENT_DEPLOYS: usize = 1;
+
+/// Default time a started rollup has to report healthy
+pub const DEFAULT_START_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Default time between two health checks of a starting rollup
+pub const DEFAULT_START_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default host ports the endpoints of new rollups are published on, four per rollup
+pub const DEFAULT_RPC_PORT_RANGE: RangeInclusive<u16> = 18547..=18946;
+
+/// Default host the rollup endpoints are reported on
+pub const DEFAULT_PUBLIC_HOST: &str = "localhost";
+
+/// Most rollups returned by one page of a listing
+pub const MAX_LIST_PAGE_SIZE: usize = 100;
+
+/// Directory of a rollup the node's chain data is kept in
+pub const DATA_DIR_NAME: &str = "database";
+
+/// Progress events of a deployment buffered while the manager is busy, later ones are dropped
+const DEPLOY_PROGRESS_BUFFER: usize = 256;
+
+/// Step of a rollup creation
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CreationPhase {
+    /// Cloning the nitro-contracts repository
+    #[default]
+    CloningRepo,
+    /// Installing dependencies and building the contracts
+    BuildingContracts,
+    /// Deploying the rollup creator
+    DeployingContracts,
+    /// Creating the rollup proxy through the rollup creator
+    DeployingProxy,
+    /// Waiting for the parent chain to confirm the deployment block
+    ConfirmingDeployment,
+    /// Writing the node configs and docker-compose.yml
+    GeneratingConfig,
+}
+
+impl std::fmt::Display for CreationPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
 
 /// Status of a rollup
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum RollupStatus {
     /// Rollup is being created
-    Creating,
+    Creating(CreationPhase),
     /// Rollup is created but not running
     Created,
     /// Rollup is being started
@@ -29,6 +111,8 @@ pub enum RollupStatus {
     Stopped,
     /// Rollup is being deleted
     Deleting,
+    /// Node containers are being recreated on a new image
+    Upgrading,
     /// Rollup creation failed
     Failed(String),
 }
@@ -36,18 +120,81 @@ pub enum RollupStatus {
 impl std::fmt::Display for RollupStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            RollupStatus::Creating => write!(f, "Creating"),
+            RollupStatus::Creating(phase) => write!(f, "Creating: {}", phase),
             RollupStatus::Created => write!(f, "Created"),
             RollupStatus::Starting => write!(f, "Starting"),
             RollupStatus::Running => write!(f, "Running"),
             RollupStatus::Stopping => write!(f, "Stopping"),
             RollupStatus::Stopped => write!(f, "Stopped"),
             RollupStatus::Deleting => write!(f, "Deleting"),
+            RollupStatus::Upgrading => write!(f, "Upgrading"),
             RollupStatus::Failed(reason) => write!(f, "Failed: {}", reason),
         }
     }
 }
 
+impl RollupStatus {
+    /// Name of the status without its phase or failure reason
+    pub fn name(&self) -> &'static str {
+        match self {
+            RollupStatus::Creating(_) => "Creating",
+            RollupStatus::Created => "Created",
+            RollupStatus::Starting => "Starting",
+            RollupStatus::Running => "Running",
+            RollupStatus::Stopping => "Stopping",
+            RollupStatus::Stopped => "Stopped",
+            RollupStatus::Deleting => "Deleting",
+            RollupStatus::Upgrading => "Upgrading",
+            RollupStatus::Failed(_) => "Failed",
+        }
+    }
+}
+
+/// Work out the status a rollup should have given the state of its containers
+///
+/// Returns `None` when `current` already matches what Docker reports.
+pub fn reconciled_status(
+    current: &RollupStatus,
+    containers: &[ServiceState],
+) -> Option<RollupStatus> {
+    let running = containers
+        .iter()
+        .filter(|container| container.state == "running")
+        .count();
+
+    let observed = if containers.is_empty() {
+        match current {
+            // Containers were expected but are gone
+            RollupStatus::Starting
+            | RollupStatus::Running
+            | RollupStatus::Stopping
+            | RollupStatus::Deleting
+            | RollupStatus::Upgrading => RollupStatus::Stopped,
+            RollupStatus::Creating(_) => {
+                RollupStatus::Failed("Creation interrupted by a restart".to_string())
+            }
+            _ => return None,
+        }
+    } else if running == containers.len() {
+        RollupStatus::Running
+    } else if running == 0 {
+        match current {
+            // Exited containers are what a failure leaves behind, keep its reason
+            RollupStatus::Failed(_) => return None,
+            _ => RollupStatus::Stopped,
+        }
+    } else {
+        let breakdown = containers
+            .iter()
+            .map(|container| format!("{} {}", container.name, container.state))
+            .collect::<Vec<_>>()
+            .join(", ");
+        RollupStatus::Failed(format!("Partially running: {}", breakdown))
+    };
+
+    (observed != *current).then_some(observed)
+}
+
 /// Rollup information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RollupInfo {
@@ -60,30 +207,719 @@ pub struct RollupInfo {
     /// Rollup configuration
     pub config: RollupConfig,
     /// Rollup status
+    #[serde(deserialize_with = "deserialize_status")]
     pub status: RollupStatus,
     /// Creation timestamp
-    pub created_at: String,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+    /// Time of the last status change, missing from rollups persisted by older versions
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
     /// Workspace directory
     pub workspace_dir: PathBuf,
     /// Config directory
     pub config_dir: PathBuf,
+    /// Deployed contract addresses, once the contracts are deployed
+    #[serde(default)]
+    pub deployment: Option<DeploymentResult>,
+    /// Client-chosen ID of the creation request
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// Host port of the node RPC, unset for rollups created before ports were allocated
+    #[serde(default)]
+    pub rpc_port: Option<u16>,
+    /// Host port of the node WebSocket RPC, unset for rollups created before it was allocated
+    #[serde(default)]
+    pub ws_port: Option<u16>,
+    /// Host port of the node sequencer feed, unset for rollups created before it was allocated
+    #[serde(default)]
+    pub feed_port: Option<u16>,
+    /// Host port of the validation node, unset for rollups created before it was allocated
+    #[serde(default)]
+    pub validation_port: Option<u16>,
+    /// Tag of the node image the rollup was upgraded to, unset until its first upgrade
+    #[serde(default)]
+    pub node_image_tag: Option<String>,
+    /// Prefix of the compose project name, rollups persisted by older versions used the default
+    #[serde(default = "default_project_prefix")]
+    pub project_prefix: String,
+    /// Directory the node keeps its chain data in, unset for rollups created before it was
+    /// recorded, which keep it in `database` next to their compose file
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// Files holding the rollup's own node keys, unset for rollups on the operator's keys
+    #[serde(default)]
+    pub node_keys: Option<NodeKeys>,
+    /// How far the node had synced when last checked, unset until checked while running
+    #[serde(default)]
+    pub sync_status: Option<SyncStatus>,
+}
+
+fn default_project_prefix() -> String {
+    DEFAULT_PROJECT_PREFIX.to_string()
+}
+
+impl RollupInfo {
+    /// Change the status, recording the time of the change
+    ///
+    /// The sync status is forgotten once the node stops running, it's only known again after
+    /// the next check.
+    pub fn set_status(&mut self, status: RollupStatus) {
+        if status != RollupStatus::Running {
+            self.sync_status = None;
+        }
+        self.status = status;
+        self.updated_at = Utc::now();
+    }
+
+    /// The status, along with the sync status of a running node once checked
+    ///
+    /// E.g. `Running (syncing 45%)` or `Running (synced)`.
+    pub fn status_summary(&self) -> String {
+        match (&self.status, self.sync_status) {
+            (RollupStatus::Running, Some(sync_status)) => {
+                format!("{} ({})", self.status, sync_status)
+            }
+            (status, _) => status.to_string(),
+        }
+    }
+
+    /// RPC endpoint the node publishes on the host
+    pub fn rpc_url(&self) -> String {
+        format!(
+            "http://localhost:{}",
+            self.rpc_port.unwrap_or(NODE_RPC_PORT)
+        )
+    }
+
+    /// Host ports the rollup publishes, the RPC port of rollups from before it was allocated
+    /// included
+    pub fn host_ports(&self) -> Vec<u16> {
+        let allocated = [self.ws_port, self.feed_port, self.validation_port];
+        std::iter::once(self.rpc_port.unwrap_or(NODE_RPC_PORT))
+            .chain(allocated.into_iter().flatten())
+            .collect()
+    }
+
+    /// Directory the node keeps its chain data in
+    pub fn data_dir(&self) -> PathBuf {
+        self.data_dir.clone().unwrap_or_else(|| self.paths().data)
+    }
+
+    /// Where the files of the rollup actually are
+    ///
+    /// The workspace and config directories, and the data and key directories once recorded,
+    /// are the ones stored with the rollup. Rollups created before they were recorded keep
+    /// theirs next to the workspace.
+    pub fn paths(&self) -> RollupPaths {
+        let root = self
+            .workspace_dir
+            .parent()
+            .unwrap_or(&self.workspace_dir)
+            .to_path_buf();
+        let mut paths = RollupPaths::under(root);
+        paths.workspace = self.workspace_dir.clone();
+        paths.config = self.config_dir.clone();
+        if let Some(data_dir) = &self.data_dir {
+            paths.data = data_dir.clone();
+        }
+        if let Some(keys) = self
+            .node_keys
+            .as_ref()
+            .and_then(|keys| keys.validator.parent())
+        {
+            paths.keys = keys.to_path_buf();
+        }
+        paths
+    }
+
+    /// Docker manager of the rollup's compose project
+    pub fn docker_manager(&self) -> EspressoDockerManager {
+        EspressoDockerManager::new(&self.workspace_dir, &self.config_dir, &self.vm_id)
+            .with_project_prefix(&self.project_prefix)
+    }
+}
+
+/// Where the files of a rollup live
+///
+/// New rollups are laid out under `{base_dir}/{rollup_id}`, so they can be found from their ID:
+///
+/// ```text
+/// {base_dir}/{rollup_id}/
+///     docker-compose.yml
+///     workspace/    contracts checkout and build
+///     config/       node configs, mounted at /config
+///     database/     chain data, mounted into the node
+///     wasm/         node WASM cache
+///     keys/         the rollup's own node keys, if it has them
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollupPaths {
+    /// Directory holding the others and the compose file
+    pub root: PathBuf,
+    /// Checkout of the contracts the rollup was deployed from
+    pub workspace: PathBuf,
+    /// Node configs
+    pub config: PathBuf,
+    /// Chain data of the node
+    pub data: PathBuf,
+    /// The rollup's own node keys
+    pub keys: PathBuf,
+}
+
+impl RollupPaths {
+    /// The layout under `root`
+    pub fn under(root: PathBuf) -> Self {
+        Self {
+            workspace: root.join("workspace"),
+            config: root.join("config"),
+            data: root.join(DATA_DIR_NAME),
+            keys: root.join(NODE_KEYS_DIR_NAME),
+            root,
+        }
+    }
+}
+
+/// Which rollups [`RollupManager::list_rollups_filtered`] returns
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListFilter {
+    /// Only rollups created by this service
+    pub service_id: Option<u64>,
+    /// Only rollups with this status, ignoring creation phases and failure reasons
+    pub status: Option<RollupStatus>,
+    /// Return at most this many rollups
+    pub limit: Option<usize>,
+    /// Skip this many matching rollups
+    pub offset: Option<usize>,
+    /// List the oldest rollups first, so pages stay put as rollups are created
+    #[serde(default)]
+    pub oldest_first: bool,
+}
+
+impl ListFilter {
+    fn matches(&self, info: &RollupInfo) -> bool {
+        self.service_id
+            .is_none_or(|service_id| info.service_id == service_id)
+            && self
+                .status
+                .as_ref()
+                .is_none_or(|status| info.status.name() == status.name())
+    }
+}
+
+/// Outcome of a rollup creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateRollupResult {
+    /// Rollup ID
+    pub rollup_id: String,
+    /// Chain ID of the rollup
+    pub chain_id: u64,
+    /// Rollup proxy contract address
+    pub rollup_proxy_address: String,
+    /// Upgrade executor contract address
+    pub upgrade_executor_address: String,
+    /// Parent chain block the rollup was deployed at
+    pub deployment_block: u64,
+}
+
+impl CreateRollupResult {
+    /// Summarize the deployment of `rollup_id`
+    pub fn new(rollup_id: &str, deployment: &DeploymentResult) -> Self {
+        Self {
+            rollup_id: rollup_id.to_string(),
+            chain_id: deployment.chain_id,
+            rollup_proxy_address: deployment.rollup_proxy_address.clone(),
+            upgrade_executor_address: deployment.upgrade_executor_address.clone(),
+            deployment_block: deployment.deployment_block,
+        }
+    }
+}
+
+/// Reason a rollup whose creation was cancelled is `Failed` with
+pub const CANCELLED_REASON: &str = "cancelled";
+
+/// Marks a rollup as busy with an operation until dropped
+struct OperationGuard {
+    operations: Arc<Mutex<HashSet<String>>>,
+    cancellations: Arc<Mutex<HashMap<String, watch::Sender<bool>>>>,
+    rollup_id: String,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        self.operations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.rollup_id);
+        // Only now, so whoever waits on the cancellation finds the rollup idle
+        self.cancellations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.rollup_id);
+    }
 }
 
+/// Builds the [`ContainerRuntime`] of a rollup, see [`RollupManager::with_runtime`]
+pub type RuntimeFactory = Box<dyn Fn(&RollupInfo) -> Box<dyn ContainerRuntime> + Send + Sync>;
+
+/// Called with the rollup ID, the old and the new status, see [`RollupManager::on_status_change`]
+pub type StatusCallback = dyn Fn(&str, &RollupStatus, &RollupStatus) + Send + Sync;
+
 /// Rollup manager for managing rollups
 pub struct RollupManager {
     /// Map of rollup ID to rollup information
     rollups: Arc<RwLock<HashMap<String, RollupInfo>>>,
+    /// File the rollup map is persisted to after every change
+    state_file: PathBuf,
+    /// Directory the per-rollup workspace and config directories are created in
+    base_dir: PathBuf,
+    /// IDs of the rollups with an operation in progress
+    operations: Arc<Mutex<HashSet<String>>>,
+    /// Cancellation signals of the creations in progress
+    cancellations: Arc<Mutex<HashMap<String, watch::Sender<bool>>>>,
+    /// Permits for the contract deployments, which build the contracts on the host
+    deploys: Arc<Semaphore>,
+    /// Time a started rollup has to report healthy before it's marked failed
+    start_timeout: Duration,
+    /// Time between two health checks of a starting rollup
+    start_poll_interval: Duration,
+    /// Host ports the endpoints of new rollups are published on
+    rpc_port_range: RangeInclusive<u16>,
+    /// Whether failed creations keep their workspace for debugging
+    keep_failed_workspaces: bool,
+    /// Commands the nodes may be asked to run
+    exec_allowlist: ExecAllowlist,
+    /// Images the compose files of the rollups may run
+    allowed_images: ImageAllowlist,
+    /// Prefix of the compose project names of new rollups
+    project_prefix: String,
+    /// Host name or address the rollup endpoints are reachable on from outside
+    public_host: String,
+    /// Whether new rollups get validator and batch poster keys of their own
+    per_rollup_keys: bool,
+    /// Deployer used instead of a [`RollupDeployer`] built from each rollup's config
+    deployer: Option<Box<dyn Deploy>>,
+    /// Runtime of each rollup's containers used instead of its [`EspressoDockerManager`]
+    runtime: Option<RuntimeFactory>,
+    /// Status channels of the watched rollups
+    status_watchers: Arc<Mutex<HashMap<String, watch::Sender<RollupStatus>>>>,
+    /// Callbacks registered with [`RollupManager::on_status_change`]
+    status_callbacks: Arc<Mutex<Vec<Arc<StatusCallback>>>>,
 }
 
 impl RollupManager {
-    /// Create a new rollup manager
-    pub fn new() -> Self {
+    /// Create a new, empty rollup manager persisting to `state_file`
+    ///
+    /// Rollup directories are created next to the state file.
+    pub fn new<P: AsRef<Path>>(state_file: P) -> Self {
+        let state_file = state_file.as_ref();
         Self {
             rollups: Arc::new(RwLock::const_new(HashMap::new())),
+            state_file: state_file.to_path_buf(),
+            base_dir: default_base_dir(state_file),
+            operations: Arc::default(),
+            cancellations: Arc::default(),
+            deploys: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_DEPLOYS)),
+            start_timeout: DEFAULT_START_TIMEOUT,
+            start_poll_interval: DEFAULT_START_POLL_INTERVAL,
+            rpc_port_range: DEFAULT_RPC_PORT_RANGE,
+            keep_failed_workspaces: false,
+            exec_allowlist: ExecAllowlist::default(),
+            allowed_images: ImageAllowlist::default(),
+            project_prefix: DEFAULT_PROJECT_PREFIX.to_string(),
+            public_host: DEFAULT_PUBLIC_HOST.to_string(),
+            per_rollup_keys: false,
+            deployer: None,
+            runtime: None,
+            status_watchers: Arc::default(),
+            status_callbacks: Arc::default(),
+        }
+    }
+
+    /// Create a new, empty rollup manager keeping its state and rollups under `base_dir`
+    pub fn with_base_dir(base_dir: PathBuf) -> Self {
+        Self::new(base_dir.join(STATE_FILE_NAME))
+    }
+
+    /// Create rollup directories under `base_dir` instead of next to the state file
+    pub fn in_base_dir<P: AsRef<Path>>(mut self, base_dir: P) -> Self {
+        self.base_dir = base_dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Allow `max` contract deployments to run at once, further ones queue
+    pub fn with_max_concurrent_deploys(mut self, max: usize) -> Self {
+        self.deploys = Arc::new(Semaphore::new(max.max(1)));
+        self
+    }
+
+    /// Give started rollups `timeout` to report healthy, checking every `poll_interval`
+    pub fn with_start_health_check(mut self, timeout: Duration, poll_interval: Duration) -> Self {
+        self.start_timeout = timeout;
+        self.start_poll_interval = poll_interval;
+        self
+    }
+
+    /// Publish the node RPC, WS RPC and feed and the validation node of new rollups on free
+    /// ports of `range`
+    pub fn with_rpc_port_range(mut self, range: RangeInclusive<u16>) -> Self {
+        self.rpc_port_range = range;
+        self
+    }
+
+    /// Keep the workspace of failed creations, holding the contracts checkout, for debugging
+    pub fn with_failed_workspaces_kept(mut self, keep: bool) -> Self {
+        self.keep_failed_workspaces = keep;
+        self
+    }
+
+    /// Only let [`RollupManager::exec_command`] run the commands of `allowlist`
+    pub fn with_exec_allowlist(mut self, allowlist: ExecAllowlist) -> Self {
+        self.exec_allowlist = allowlist;
+        self
+    }
+
+    /// Only start rollups whose compose files run images `allowed_images` allows
+    ///
+    /// Every image is allowed by default.
+    pub fn with_allowed_images(mut self, allowed_images: ImageAllowlist) -> Self {
+        self.allowed_images = allowed_images;
+        self
+    }
+
+    /// Name the compose projects of new rollups `{prefix}-{vm_id}`
+    ///
+    /// Existing rollups keep the prefix they were created with, so they're still cleaned up.
+    pub fn with_project_prefix(mut self, prefix: &str) -> Self {
+        self.project_prefix = prefix.to_string();
+        self
+    }
+
+    /// Report the endpoints of the rollups on `host` rather than [`DEFAULT_PUBLIC_HOST`]
+    pub fn with_public_host(mut self, host: &str) -> Self {
+        self.public_host = host.to_string();
+        self
+    }
+
+    /// Give each new rollup a validator and batch poster key of its own
+    ///
+    /// Keys already provisioned in the [`RollupPaths::keys`] of a rollup are used, others are
+    /// generated. The rollup's validators and batch poster become the addresses of its keys,
+    /// in place of the configured ones. Without this, every rollup runs on the operator's keys.
+    pub fn with_per_rollup_keys(mut self, per_rollup_keys: bool) -> Self {
+        self.per_rollup_keys = per_rollup_keys;
+        self
+    }
+
+    /// Deploy every rollup with `deployer`, e.g. a [`MockDeployer`], instead of the toolchain
+    ///
+    /// No deployment keys are needed then, and the deployments aren't confirmed.
+    ///
+    /// [`MockDeployer`]: crate::deployer::MockDeployer
+    pub fn with_deployer(mut self, deployer: Box<dyn Deploy>) -> Self {
+        self.deployer = Some(deployer);
+        self
+    }
+
+    /// Run the containers of every rollup on the runtime `runtime` builds, e.g. a [`MockRuntime`]
+    ///
+    /// Starting, stopping, deleting and reconciling rollups then go through it instead of Docker.
+    ///
+    /// [`MockRuntime`]: crate::docker::mock::MockRuntime
+    pub fn with_runtime<F>(mut self, runtime: F) -> Self
+    where
+        F: Fn(&RollupInfo) -> Box<dyn ContainerRuntime> + Send + Sync + 'static,
+    {
+        self.runtime = Some(Box::new(runtime));
+        self
+    }
+
+    /// Runtime of the containers of `info`
+    fn runtime(&self, info: &RollupInfo) -> Box<dyn ContainerRuntime> {
+        match &self.runtime {
+            Some(runtime) => runtime(info),
+            None => Box::new(
+                info.docker_manager()
+                    .with_allowed_images(self.allowed_images.clone()),
+            ),
+        }
+    }
+
+    /// Load a rollup manager from `state_file`, starting empty if the file doesn't exist
+    pub fn load<P: AsRef<Path>>(state_file: P) -> Result<Self, RaasError> {
+        let state_file = state_file.as_ref();
+        if !state_file.exists() {
+            info!(
+                "No rollup state found at {}, starting empty",
+                state_file.display()
+            );
+            return Ok(Self::new(state_file));
+        }
+
+        let content = fs::read_to_string(state_file)
+            .map_err(|e| anyhow!("Failed to read {}: {}", state_file.display(), e))?;
+        let mut rollups: HashMap<String, RollupInfo> = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", state_file.display(), e))?;
+        // Older versions didn't record status changes, the creation is the last one known
+        for info in rollups.values_mut() {
+            info.updated_at = info.updated_at.max(info.created_at);
+        }
+        info!(
+            "Loaded {} rollups from {}",
+            rollups.len(),
+            state_file.display()
+        );
+        metrics().record_rollups(rollups.values().map(|info| &info.status));
+
+        Ok(Self {
+            rollups: Arc::new(RwLock::const_new(rollups)),
+            ..Self::new(state_file)
+        })
+    }
+
+    /// Path of the state file
+    pub fn state_file(&self) -> &Path {
+        &self.state_file
+    }
+
+    /// Directory the rollup directories are created in
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Where the files of the rollup `rollup_id` are created, see [`RollupPaths`]
+    ///
+    /// Rollups created before the layout was keyed on the rollup ID keep theirs, see
+    /// [`RollupInfo::paths`].
+    pub fn paths(&self, rollup_id: &str) -> RollupPaths {
+        RollupPaths::under(self.base_dir.join(rollup_id))
+    }
+
+    /// Workspace and config directories of the rollup `rollup_id`
+    pub fn rollup_dirs(&self, rollup_id: &str) -> (PathBuf, PathBuf) {
+        let paths = self.paths(rollup_id);
+        (paths.workspace, paths.config)
+    }
+
+    /// Atomically write the rollup map to the state file
+    ///
+    /// Callers must hold the registry write lock so concurrent flushes can't interleave.
+    fn persist(&self, rollups: &HashMap<String, RollupInfo>) -> Result<()> {
+        // Watchers follow the registry, even if writing it out fails
+        self.notify_status_watchers(rollups);
+
+        if let Some(parent) = self.state_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temp file next to the state file, then rename over it
+        let tmp_path = self.state_file.with_extension("json.tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&serde_json::to_vec_pretty(rollups)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.state_file).map_err(|e| {
+            anyhow!(
+                "Failed to persist rollup state to {}: {}",
+                self.state_file.display(),
+                e
+            )
+        })?;
+
+        // Every registry change is persisted, so this keeps the counts current
+        metrics().record_rollups(rollups.values().map(|info| &info.status));
+        Ok(())
+    }
+
+    /// Send the current status of every watched rollup, closing the channels of removed ones
+    fn notify_status_watchers(&self, rollups: &HashMap<String, RollupInfo>) {
+        let mut watchers = self
+            .status_watchers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        watchers.retain(|rollup_id, sender| {
+            let Some(info) = rollups.get(rollup_id) else {
+                return false;
+            };
+            sender.send_if_modified(|status| {
+                let modified = *status != info.status;
+                if modified {
+                    *status = info.status.clone();
+                }
+                modified
+            });
+            sender.receiver_count() > 0
+        });
+    }
+
+    /// Pick the host ports of the new rollup `info`
+    ///
+    /// The RPC is published on `requested` or the first free port of the range, the WS RPC,
+    /// sequencer feed and validation node on the next free ones. Called with the registry
+    /// write lock held, so concurrent creations never share a port.
+    fn allocate_host_ports(
+        &self,
+        registry: &HashMap<String, RollupInfo>,
+        info: &mut RollupInfo,
+        requested: Option<u16>,
+    ) -> Result<(), RaasError> {
+        let owners: HashMap<u16, &str> = registry
+            .values()
+            .flat_map(|existing| {
+                existing
+                    .host_ports()
+                    .into_iter()
+                    .map(|port| (port, existing.rollup_id.as_str()))
+            })
+            .collect();
+        if let Some(port) = requested
+            && let Some(owner) = owners.get(&port)
+        {
+            return Err(RaasError::RpcPortInUse {
+                port,
+                rollup_id: owner.to_string(),
+            });
+        }
+
+        let mut free = self
+            .rpc_port_range
+            .clone()
+            .filter(|port| !owners.contains_key(port) && Some(*port) != requested);
+        let mut next = |name: &str| {
+            free.next().ok_or_else(|| {
+                RaasError::Internal(anyhow!(
+                    "No free {} port left in {}-{}",
+                    name,
+                    self.rpc_port_range.start(),
+                    self.rpc_port_range.end()
+                ))
+            })
+        };
+        info.rpc_port = Some(match requested {
+            Some(port) => port,
+            None => next("RPC")?,
+        });
+        info.ws_port = Some(next("WS")?);
+        info.feed_port = Some(next("feed")?);
+        info.validation_port = Some(next("validation node")?);
+        Ok(())
+    }
+
+    /// Mark a rollup as busy, failing if another operation is already running on it
+    fn begin_operation(&self, rollup_id: &str) -> Result<OperationGuard, RaasError> {
+        let mut operations = self
+            .operations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !operations.insert(rollup_id.to_string()) {
+            return Err(RaasError::RollupBusy(rollup_id.to_string()));
+        }
+
+        Ok(OperationGuard {
+            operations: self.operations.clone(),
+            cancellations: self.cancellations.clone(),
+            rollup_id: rollup_id.to_string(),
+        })
+    }
+
+    /// Let the creation of a rollup be cancelled, until its operation ends
+    fn cancellable(&self, rollup_id: &str) -> watch::Receiver<bool> {
+        let (cancel, cancelled) = watch::channel(false);
+        self.cancellations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(rollup_id.to_string(), cancel);
+        cancelled
+    }
+
+    /// Cancel the creation of a rollup, if in progress, and wait for it to wind down
+    ///
+    /// Returns whether a creation was cancelled.
+    async fn cancel_and_wait(&self, rollup_id: &str) -> bool {
+        let mut done = {
+            let cancellations = self
+                .cancellations
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let Some(cancel) = cancellations.get(rollup_id) else {
+                return false;
+            };
+            let done = cancel.subscribe();
+            cancel.send_replace(true);
+            done
+        };
+        info!("Cancelling the creation of rollup {}", rollup_id);
+        // The signal is dropped once the creation is over
+        while done.changed().await.is_ok() {}
+        true
+    }
+
+    /// Cancel the creation of a rollup
+    ///
+    /// The deployment stops before its next phase, killing the command it's running, the
+    /// files of the rollup are rolled back and it's left `Failed` with [`CANCELLED_REASON`].
+    /// Returns once the creation has wound down.
+    pub async fn cancel_creation(&self, rollup_id: &str) -> Result<(), RaasError> {
+        if self.cancel_and_wait(rollup_id).await {
+            return Ok(());
+        }
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        Err(RaasError::InvalidConfig(anyhow!(
+            "Rollup {} is not being created, it is {}",
+            rollup_id,
+            info.status
+        )))
+    }
+
+    /// Roll back a cancelled creation
+    async fn abort_creation(
+        &self,
+        rollup_id: &str,
+        workspace_dir: &Path,
+        config_dir: &Path,
+        data_dir: &Path,
+    ) -> RaasError {
+        warn!("Creation of rollup {} was cancelled", rollup_id);
+        self.roll_back_files(workspace_dir, config_dir, data_dir);
+        self.mark_failed(rollup_id, CANCELLED_REASON.to_string())
+            .await;
+        RaasError::Cancelled(rollup_id.to_string())
+    }
+
+    /// Leave a rollup whose creation failed `Failed`, so it doesn't hold on to its chain ID
+    async fn fail_creation(&self, rollup_id: &str, e: RaasError) -> RaasError {
+        error!("Failed to create rollup {}: {}", rollup_id, e);
+        self.mark_failed(rollup_id, format!("Creation failed: {}", e))
+            .await;
+        e
+    }
+
+    /// Whether an operation is running on a rollup
+    pub fn is_busy(&self, rollup_id: &str) -> bool {
+        self.operations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(rollup_id)
+    }
+
+    /// Record a failure on a rollup, logging rather than returning persistence errors
+    async fn mark_failed(&self, rollup_id: &str, reason: String) {
+        if let Err(e) = self
+            .update_rollup_status(rollup_id, RollupStatus::Failed(reason))
+            .await
+        {
+            error!("Failed to record failure for rollup {}: {}", rollup_id, e);
         }
     }
 
     /// Create a new rollup
+    ///
+    /// Creating again with the `request_id` of an existing rollup returns that rollup
+    /// instead of deploying another one.
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(skip_all, fields(rollup_id = %rollup_id, service_id = service_id))]
     pub async fn create_rollup(
         &self,
         service_id: u64,
@@ -92,48 +928,161 @@ impl RollupManager {
         config: RollupConfig,
         workspace_dir: PathBuf,
         config_dir: PathBuf,
-    ) -> Result<String> {
+        request_id: Option<String>,
+    ) -> Result<CreateRollupResult, RaasError> {
         // Update status to Creating
-        let info = RollupInfo {
+        let data_dir = self.paths(rollup_id).data;
+        let now = Utc::now();
+        let mut info = RollupInfo {
             service_id,
             rollup_id: rollup_id.to_string(),
             vm_id: vm_id.to_string(),
             config: config.clone(),
-            status: RollupStatus::Creating,
-            created_at: chrono::Utc::now().to_rfc3339(),
+            status: RollupStatus::Creating(CreationPhase::default()),
+            created_at: now,
+            updated_at: now,
             workspace_dir: workspace_dir.clone(),
             config_dir: config_dir.clone(),
+            deployment: None,
+            request_id: request_id.clone(),
+            rpc_port: None,
+            ws_port: None,
+            feed_port: None,
+            validation_port: None,
+            node_image_tag: None,
+            project_prefix: self.project_prefix.clone(),
+            data_dir: Some(data_dir.clone()),
+            node_keys: None,
+            sync_status: None,
         };
 
-        // Store the rollup information
-        self.rollups
-            .write()
-            .await
-            .insert(rollup_id.to_string(), info);
+        let _operation = self.begin_operation(rollup_id)?;
 
-        info!("Deploying contracts for rollup {}", rollup_id);
+        // The toolchain runs on this host, report all it lacks rather than the first missing tool
+        let preflight = match &self.deployer {
+            Some(_) => None,
+            None => Some(self.preflight().await),
+        };
 
-        // Get deployment keys from environment variables
-        let private_key = std::env::var("DEPLOYER_PRIVATE_KEY")
-            .map_err(|_| anyhow!("DEPLOYER_PRIVATE_KEY environment variable not set"))?;
-        let arbiscan_api_key = std::env::var("ARBISCAN_API_KEY")
-            .map_err(|_| anyhow!("ARBISCAN_API_KEY environment variable not set"))?;
-        let arbitrum_rpc_url = std::env::var("ARBITRUM_RPC_URL")
-            .map_err(|_| anyhow!("ARBITRUM_RPC_URL environment variable not set"))?;
-
-        // Create deployment config
-        let deployment_config = DeploymentConfig::new(
-            &config,
-            &private_key,
-            &arbiscan_api_key,
-            workspace_dir.clone(),
-        );
+        let cancel = self.cancellable(rollup_id);
+        // The deployer reports the transactions it sends and its output while it runs
+        let (progress, mut progress_events) = mpsc::channel(DEPLOY_PROGRESS_BUFFER);
+
+        // Store the rollup information, only once nothing that could fail early is left
+        let (info, deployer) = {
+            let mut registry = self.rollups.write().await;
+            // A retried request must not deploy again, request IDs are only unique per service
+            if let Some(existing) = registry.values().find(|existing| {
+                request_id.is_some()
+                    && existing.request_id == request_id
+                    && existing.service_id == service_id
+            }) {
+                return match &existing.deployment {
+                    Some(deployment) => {
+                        info!(
+                            "Rollup {} already created for request {:?}",
+                            existing.rollup_id, request_id
+                        );
+                        Ok(CreateRollupResult::new(&existing.rollup_id, deployment))
+                    }
+                    None if self.is_busy(&existing.rollup_id) => {
+                        Err(RaasError::RollupBusy(existing.rollup_id.clone()))
+                    }
+                    None => Err(RaasError::DeploymentFailed(anyhow!(
+                        "Rollup {} for this request was never deployed: {}",
+                        existing.rollup_id,
+                        existing.status
+                    ))),
+                };
+            }
+            // Two rollups on one chain ID would be indistinguishable to wallets and bridges,
+            // a failed rollup never made it onto the chain and doesn't hold on to its ID
+            if let Some(existing) = registry.values().find(|existing| {
+                existing.config.chain_id == config.chain_id
+                    && !matches!(existing.status, RollupStatus::Failed(_))
+            }) {
+                return Err(RaasError::ChainIdInUse {
+                    chain_id: config.chain_id,
+                    rollup_id: existing.rollup_id.clone(),
+                });
+            }
+            // A rollup must never start from the chain of another
+            if let Some(existing) = registry
+                .values()
+                .find(|existing| existing.data_dir() == data_dir)
+            {
+                return Err(RaasError::DataDirInUse {
+                    path: data_dir.display().to_string(),
+                    rollup_id: existing.rollup_id.clone(),
+                });
+            }
+            // Each rollup publishes its endpoints on host ports of its own
+            self.allocate_host_ports(&registry, &mut info, config.rpc_host_port)?;
+            if let Some(report) = preflight.filter(|report| !report.passed()) {
+                return Err(RaasError::PreflightFailed(report.failures().join("; ")));
+            }
+            // Without a configured deployer, deploy with the toolchain, using deployment keys
+            // from the keystore or environment variables
+            let credentials = match &self.deployer {
+                Some(_) => None,
+                None => {
+                    let private_key = RollupKey::Deployer.load()?;
+                    let arbiscan_api_key = std::env::var("ARBISCAN_API_KEY")
+                        .map_err(|_| anyhow!("ARBISCAN_API_KEY environment variable not set"))?;
+                    Some((private_key, arbiscan_api_key))
+                }
+            };
+            if self.per_rollup_keys {
+                self.provision_node_keys(&mut info)?;
+            }
+            // The contracts are deployed for the rollup's own keys, if it has them
+            let deployer = credentials.map(|(private_key, arbiscan_api_key)| {
+                let deployment_config = DeploymentConfig::new(
+                    &info.config,
+                    &private_key,
+                    &arbiscan_api_key,
+                    workspace_dir.clone(),
+                );
+                RollupDeployer::new(deployment_config)
+                    .with_progress(progress)
+                    .with_cancellation(cancel.clone())
+            });
+            registry.insert(rollup_id.to_string(), info.clone());
+            if let Err(e) = self.persist(&registry) {
+                // An unrecorded rollup must not linger in memory
+                registry.remove(rollup_id);
+                return Err(e.into());
+            }
+            (info, deployer)
+        };
+        let config = info.config.clone();
+
+        info!("Deploying contracts for rollup {}", rollup_id);
 
-        // Create deployer and deploy contracts
-        let deployer = RollupDeployer::new(deployment_config);
+        // Deployments build the contracts on the host, so queue behind the running ones
+        if self.deploys.available_permits() == 0 {
+            info!("Rollup {} is waiting for a deployment slot", rollup_id);
+        }
+        let deploy_permit = tokio::select! {
+            permit = self.deploys.acquire() => match permit {
+                Ok(permit) => permit,
+                Err(e) => {
+                    let e = anyhow!("Failed to acquire a deployment slot: {}", e);
+                    return Err(self.fail_creation(rollup_id, e.into()).await);
+                }
+            },
+            () = cancelled(cancel.clone()) => {
+                return Err(self
+                    .abort_creation(rollup_id, &workspace_dir, &config_dir, &data_dir)
+                    .await);
+            }
+        };
 
         // Try to deploy contracts
-        let deployment_result = match deployer.deploy().await {
+        let deployment_result = match self
+            .deploy(rollup_id, deployer.as_ref(), &mut progress_events, cancel)
+            .await
+        {
             Ok(result) => {
                 info!(
                     "Contracts deployed successfully for rollup {}. Rollup proxy: {}",
@@ -141,180 +1090,1134 @@ impl RollupManager {
                 );
                 result
             }
+            Err(e) if e.is::<DeployCancelled>() => {
+                return Err(self
+                    .abort_creation(rollup_id, &workspace_dir, &config_dir, &data_dir)
+                    .await);
+            }
             Err(e) => {
                 error!("Failed to deploy contracts: {}", e);
 
                 // Update status to Failed
-                let mut registry = self.rollups.write().await;
-                if let Some(info) = registry.get_mut(rollup_id) {
-                    info.status =
-                        RollupStatus::Failed(format!("Contract deployment failed: {}", e));
-                }
+                self.mark_failed(rollup_id, format!("Contract deployment failed: {}", e))
+                    .await;
 
-                return Err(anyhow!("Failed to deploy contracts: {}", e));
+                return Err(RaasError::DeploymentFailed(e));
             }
         };
+        drop(deploy_permit);
 
-        // Get validator and batch poster keys from environment
-        let validator_key = std::env::var("VALIDATOR_PRIVATE_KEY")
-            .map_err(|_| anyhow!("VALIDATOR_PRIVATE_KEY environment variable not set"))?;
-        let batch_poster_key = std::env::var("BATCH_POSTER_PRIVATE_KEY")
-            .map_err(|_| anyhow!("BATCH_POSTER_PRIVATE_KEY environment variable not set"))?;
-
-        // Use ConfigGenerator to generate all configuration files
-        let config_generator = ConfigGenerator::new(
-            &config_dir,
-            &workspace_dir,
-            config.chain_id,
-            deployment_result.rollup_proxy_address,
-            deployment_result.upgrade_executor_address,
-            deployment_result.deployment_block,
-            validator_key,
-            batch_poster_key,
-            arbitrum_rpc_url,
-        );
+        // Confirmations don't need a deployment slot, let the next deployment build meanwhile
+        if let Some(deployer) = &deployer {
+            if let Err(e) = self
+                .update_rollup_status(
+                    rollup_id,
+                    RollupStatus::Creating(CreationPhase::ConfirmingDeployment),
+                )
+                .await
+            {
+                return Err(self.fail_creation(rollup_id, e).await);
+            }
+            if let Err(e) = deployer.confirm_deployment(&deployment_result).await {
+                if e.is::<DeployCancelled>() {
+                    return Err(self
+                        .abort_creation(rollup_id, &workspace_dir, &config_dir, &data_dir)
+                        .await);
+                }
+                error!("Failed to confirm the deployment: {}", e);
+                self.mark_failed(rollup_id, format!("Deployment confirmation failed: {}", e))
+                    .await;
+                return Err(RaasError::DeploymentFailed(e));
+            }
+        }
+
+        if let Err(e) = self
+            .update_rollup_status(
+                rollup_id,
+                RollupStatus::Creating(CreationPhase::GeneratingConfig),
+            )
+            .await
+        {
+            return Err(self.fail_creation(rollup_id, e).await);
+        }
 
         // Generate all configurations including docker-compose.yml
-        match config_generator.generate_configs() {
+        match generate_configs(&info, &config, &deployment_result, None) {
             Ok(_) => {
                 info!(
                     "Generated configuration files successfully for rollup {}",
                     rollup_id
                 );
+                // The contracts are deployed either way, a missing copy isn't worth failing over
+                if let Some(Err(e)) = deployer
+                    .as_ref()
+                    .map(|deployer| deployer.copy_deployment_artifact(&config_dir))
+                {
+                    warn!(
+                        "Failed to keep the deployment artifact of rollup {}: {}",
+                        rollup_id, e
+                    );
+                }
             }
             Err(e) => {
                 error!("Failed to generate configuration files: {}", e);
 
+                // Don't let repeated failures pile up half-written configs on disk
+                self.roll_back_files(&workspace_dir, &config_dir, &data_dir);
+
                 // Update status to Failed
-                let mut registry = self.rollups.write().await;
-                if let Some(info) = registry.get_mut(rollup_id) {
-                    info.status = RollupStatus::Failed(format!("Config generation failed: {}", e));
-                }
+                self.mark_failed(rollup_id, format!("Config generation failed: {}", e))
+                    .await;
 
-                return Err(anyhow!("Failed to generate config files: {}", e));
+                return Err(RaasError::ConfigGenerationFailed(e));
             }
         }
 
-        // Update status to Created
-        let mut registry = self.rollups.write().await;
-        if let Some(info) = registry.get_mut(rollup_id) {
-            info.status = RollupStatus::Created;
-        }
+        // Record the deployed addresses along with the Created status
+        let recorded = {
+            let mut registry = self.rollups.write().await;
+            match registry.get_mut(rollup_id) {
+                Some(info) => {
+                    let old = info.status.clone();
+                    info.deployment = Some(deployment_result.clone());
+                    info.set_status(RollupStatus::Created);
+                    self.persist(&registry)
+                        .map(|()| old)
+                        .map_err(RaasError::from)
+                }
+                None => Err(RaasError::RollupNotFound(rollup_id.to_string())),
+            }
+        };
+        let old = match recorded {
+            Ok(old) => old,
+            Err(e) => return Err(self.fail_creation(rollup_id, e).await),
+        };
+        self.notify_status_change(rollup_id, &old, &RollupStatus::Created);
 
         info!("Rollup {} created successfully.", rollup_id);
 
-        Ok(rollup_id.to_string())
+        Ok(CreateRollupResult::new(rollup_id, &deployment_result))
+    }
+
+    /// Run the deployment, recording each phase in the rollup status
+    ///
+    /// `deployer` is only unset when the manager has a deployer of its own. The
+    /// transactions and output `progress` receives meanwhile are logged. Setting `cancel`
+    /// fails the deployment with [`DeployCancelled`].
+    async fn deploy(
+        &self,
+        rollup_id: &str,
+        deployer: Option<&RollupDeployer>,
+        progress: &mut mpsc::Receiver<DeployProgress>,
+        cancel: watch::Receiver<bool>,
+    ) -> Result<DeploymentResult> {
+        let enter = |phase| self.update_rollup_status(rollup_id, RollupStatus::Creating(phase));
+
+        let started = Instant::now();
+        let result = async {
+            let Some(deployer) = deployer else {
+                enter(CreationPhase::DeployingContracts).await?;
+                return match &self.deployer {
+                    Some(deployer) => tokio::select! {
+                        result = deployer.deploy() => result,
+                        () = cancelled(cancel) => Err(DeployCancelled.into()),
+                    },
+                    None => Err(anyhow!("No deployer configured")),
+                };
+            };
+            let phases = async {
+                enter(CreationPhase::CloningRepo).await?;
+                deployer.prepare().await?;
+                enter(CreationPhase::BuildingContracts).await?;
+                deployer.build().await?;
+                enter(CreationPhase::DeployingContracts).await?;
+                let rollup_creator_address = deployer.deploy_creator().await?;
+                enter(CreationPhase::DeployingProxy).await?;
+                deployer.deploy_rollup(rollup_creator_address).await
+            };
+            follow_progress(rollup_id, phases, progress).await
+        }
+        .await;
+        metrics().record_deploy(started.elapsed(), result.is_ok());
+        result
+    }
+
+    /// Check the host prerequisites of creating and running rollups
+    ///
+    /// The toolchain is only checked without a configured deployer, and the Docker daemon
+    /// without a configured runtime.
+    pub async fn preflight(&self) -> PreflightReport {
+        let tools: &[&str] = match &self.deployer {
+            Some(_) => &[],
+            None => &REQUIRED_TOOLS,
+        };
+        preflight(tools, self.runtime.is_none()).await
     }
 
     /// Start a rollup
-    pub async fn start_rollup(&self, rollup_id: &str) -> Result<()> {
+    #[tracing::instrument(skip_all, fields(rollup_id = %rollup_id, service_id = tracing::field::Empty))]
+    pub async fn start_rollup(&self, rollup_id: &str) -> Result<(), RaasError> {
+        let _operation = self.begin_operation(rollup_id)?;
+        self.record_service_id(rollup_id).await;
+        self.start_containers(rollup_id).await
+    }
+
+    /// Stop a rollup
+    #[tracing::instrument(skip_all, fields(rollup_id = %rollup_id, service_id = tracing::field::Empty))]
+    pub async fn stop_rollup(&self, rollup_id: &str) -> Result<(), RaasError> {
+        let _operation = self.begin_operation(rollup_id)?;
+        self.record_service_id(rollup_id).await;
+        self.stop_containers(rollup_id).await
+    }
+
+    /// Restart a rollup
+    ///
+    /// Stops and then starts the rollup under a single operation lock. If the
+    /// stop fails the rollup is left `Failed` and no start is attempted.
+    pub async fn restart_rollup(&self, rollup_id: &str) -> Result<(), RaasError> {
+        let _operation = self.begin_operation(rollup_id)?;
+        self.stop_containers(rollup_id).await?;
+        self.start_containers(rollup_id).await
+    }
+
+    /// Add the service owning `rollup_id` to the current span
+    async fn record_service_id(&self, rollup_id: &str) {
+        if let Some(info) = self.rollups.read().await.get(rollup_id) {
+            tracing::Span::current().record("service_id", info.service_id);
+        }
+    }
+
+    /// Start the containers of a rollup, the caller holds the operation lock
+    async fn start_containers(&self, rollup_id: &str) -> Result<(), RaasError> {
         // Get rollup information
         let registry = self.rollups.read().await;
         let info = registry
             .get(rollup_id)
-            .ok_or_else(|| anyhow!("Rollup not found"))?
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?
             .clone();
         drop(registry);
 
         // Update status to Starting
-        {
-            let mut registry = self.rollups.write().await;
-            if let Some(info) = registry.get_mut(rollup_id) {
-                info.status = RollupStatus::Starting;
-            }
-        }
+        self.update_rollup_status(rollup_id, RollupStatus::Starting)
+            .await?;
 
         // Create and start the Docker manager based on rollup type
-        let mut manager = EspressoDockerManager::new(
-            info.workspace_dir.clone(),
-            info.config_dir.clone(),
-            &info.vm_id,
-        );
+        let mut manager = self.runtime(&info);
 
-        // Start the manager
-        match manager.start().await {
+        // Start the manager and only report it running once the node is healthy
+        let started = match manager.start().await {
             Ok(_) => {
-                // Update the status
-                let mut registry = self.rollups.write().await;
-                if let Some(info) = registry.get_mut(rollup_id) {
-                    info.status = RollupStatus::Running;
+                let healthy = manager
+                    .wait_until_healthy(self.start_timeout, self.start_poll_interval)
+                    .await;
+                // Compose can bring up some services and not others, name the ones that are down
+                match manager.service_statuses().await {
+                    Ok(statuses) => match service_failures(&statuses) {
+                        Some(failures) => Err(anyhow!("Services not running: {}", failures)),
+                        None => healthy,
+                    },
+                    Err(e) => {
+                        warn!(
+                            "Failed to check the services of rollup {}: {}",
+                            rollup_id, e
+                        );
+                        healthy
+                    }
                 }
-                Ok(())
+                .map_err(RaasError::ContainersUnhealthy)
+            }
+            Err(e) => Err(container_error(e)),
+        };
+        match started {
+            Ok(_) => {
+                // Update the status
+                self.update_rollup_status(rollup_id, RollupStatus::Running)
+                    .await
             }
             Err(e) => {
                 // Update the status
-                let mut registry = self.rollups.write().await;
-                if let Some(info) = registry.get_mut(rollup_id) {
-                    info.status = RollupStatus::Failed(e.to_string());
-                }
+                self.mark_failed(rollup_id, e.to_string()).await;
                 Err(e)
             }
         }
     }
 
-    /// Stop a rollup
-    pub async fn stop_rollup(&self, rollup_id: &str) -> Result<()> {
+    /// Stop the containers of a rollup, the caller holds the operation lock
+    async fn stop_containers(&self, rollup_id: &str) -> Result<(), RaasError> {
         // Get rollup information
         let registry = self.rollups.read().await;
         let info = registry
             .get(rollup_id)
-            .ok_or_else(|| anyhow!("Rollup not found"))?
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?
             .clone();
         drop(registry);
 
         // Update status to Stopping
-        {
-            let mut registry = self.rollups.write().await;
-            if let Some(info) = registry.get_mut(rollup_id) {
-                info.status = RollupStatus::Deleting;
-            }
-        }
+        self.update_rollup_status(rollup_id, RollupStatus::Stopping)
+            .await?;
 
         // Create and stop the Docker manager based on rollup type
-        let manager = EspressoDockerManager::new(
-            info.workspace_dir.clone(),
-            info.config_dir.clone(),
-            &info.vm_id,
-        );
+        let manager = self.runtime(&info);
 
         // Stop the manager
         match manager.stop().await {
             Ok(_) => {
                 // Update the status
-                let mut registry = self.rollups.write().await;
-                if let Some(info) = registry.get_mut(rollup_id) {
-                    info.status = RollupStatus::Stopped;
-                }
-                Ok(())
+                self.update_rollup_status(rollup_id, RollupStatus::Stopped)
+                    .await
             }
             Err(e) => {
                 // Update the status
-                let mut registry = self.rollups.write().await;
-                if let Some(info) = registry.get_mut(rollup_id) {
-                    info.status = RollupStatus::Failed(e.to_string());
-                }
+                let e = RaasError::DockerUnavailable(e);
+                self.mark_failed(rollup_id, e.to_string()).await;
                 Err(e)
             }
         }
     }
 
+    /// Change the config of a deployed rollup
+    ///
+    /// Only the fields listed in [`RollupConfig::immutable_changes`] as hot-reloadable
+    /// may differ. The node config is regenerated, and a running rollup is restarted
+    /// to pick it up.
+    pub async fn update_config(
+        &self,
+        rollup_id: &str,
+        params: RollupConfigParams,
+    ) -> Result<(), RaasError> {
+        let _operation = self.begin_operation(rollup_id)?;
+
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let config = RollupConfig::from(params);
+        config.validate().map_err(RaasError::InvalidConfig)?;
+
+        let immutable = info.config.immutable_changes(&config);
+        if !immutable.is_empty() {
+            return Err(RaasError::InvalidConfig(anyhow!(
+                "{} can't be changed without an on-chain upgrade",
+                immutable.join(", ")
+            )));
+        }
+
+        self.reconfigure(&info, config).await?;
+        info!("Updated config of rollup {}", rollup_id);
+        Ok(())
+    }
+
+    /// Regenerate the node config of a deployed rollup without redeploying its contracts
+    ///
+    /// The current config with `overrides` applied is rendered against the recorded contract
+    /// addresses, and a running rollup is restarted to pick it up. Rollups without a
+    /// recorded deployment can't be regenerated.
+    pub async fn regenerate_config(
+        &self,
+        rollup_id: &str,
+        overrides: ConfigOverrides,
+    ) -> Result<(), RaasError> {
+        let _operation = self.begin_operation(rollup_id)?;
+
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let config = overrides.apply(info.config.clone());
+        config.validate().map_err(RaasError::InvalidConfig)?;
+
+        self.reconfigure(&info, config).await?;
+        info!("Regenerated config of rollup {}", rollup_id);
+        Ok(())
+    }
+
+    /// Regenerate the node config of `info` for `config` and record it, restarting a running
+    /// rollup, the caller holds the operation lock
+    async fn reconfigure(&self, info: &RollupInfo, config: RollupConfig) -> Result<(), RaasError> {
+        let rollup_id = &info.rollup_id;
+        let deployment = info.deployment.as_ref().ok_or_else(|| {
+            RaasError::ConfigGenerationFailed(anyhow!(
+                "Rollup {} has no recorded deployment",
+                rollup_id
+            ))
+        })?;
+        generate_configs(info, &config, deployment, info.node_image_tag.as_deref())
+            .map_err(RaasError::ConfigGenerationFailed)?;
+
+        {
+            let mut registry = self.rollups.write().await;
+            let info = registry
+                .get_mut(rollup_id)
+                .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+            info.config = config;
+            self.persist(&registry)?;
+        }
+
+        if info.status == RollupStatus::Running {
+            self.stop_containers(rollup_id).await?;
+            self.start_containers(rollup_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Move the nodes of a rollup to `tag` of the nitro node image
+    ///
+    /// The image is pulled first, so a missing tag leaves the rollup untouched. A running
+    /// rollup goes `Upgrading` while its containers are recreated, then `Running` once the
+    /// node is healthy. If it doesn't come up, the previous image is restored and the rollup
+    /// is left `Failed`. Other rollups run the new image on their next start.
+    pub async fn upgrade_rollup(&self, rollup_id: &str, tag: &str) -> Result<(), RaasError> {
+        validate_image_tag(tag).map_err(RaasError::InvalidConfig)?;
+        let _operation = self.begin_operation(rollup_id)?;
+
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        if !matches!(
+            info.status,
+            RollupStatus::Created | RollupStatus::Running | RollupStatus::Stopped
+        ) {
+            return Err(RaasError::InvalidConfig(anyhow!(
+                "Can't upgrade rollup {} while it is {}",
+                rollup_id,
+                info.status
+            )));
+        }
+        let deployment = info
+            .deployment
+            .as_ref()
+            .ok_or_else(|| anyhow!("Rollup {} has no recorded deployment", rollup_id))?;
+        let previous_tag = info.node_image_tag.as_deref();
+        let write_compose = |tag| {
+            generate_configs(&info, &info.config, deployment, tag)
+                .map_err(RaasError::ConfigGenerationFailed)
+        };
+
+        info!("Upgrading rollup {} to node image tag {}", rollup_id, tag);
+        self.update_rollup_status(rollup_id, RollupStatus::Upgrading)
+            .await?;
+        let mut manager = self.runtime(&info);
+
+        let prepared = match write_compose(Some(tag)) {
+            Ok(()) => manager.pull_images().await.map_err(container_error),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = prepared {
+            // Nothing was recreated yet, the rollup keeps running on its previous image
+            if let Err(e) = write_compose(previous_tag) {
+                error!(
+                    "Failed to restore the compose file of rollup {}: {}",
+                    rollup_id, e
+                );
+            }
+            self.update_rollup_status(rollup_id, info.status.clone())
+                .await?;
+            return Err(e);
+        }
+
+        if info.status == RollupStatus::Running
+            && let Err(e) = self.recreate_containers(manager.as_mut()).await
+        {
+            error!("Failed to upgrade rollup {}: {}", rollup_id, e);
+            let reverted = match write_compose(previous_tag) {
+                Ok(()) => self
+                    .recreate_containers(manager.as_mut())
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            let reason = match reverted {
+                Ok(()) => format!("Upgrade to {} failed, reverted: {}", tag, e),
+                Err(revert) => format!(
+                    "Upgrade to {} failed: {}, reverting failed: {}",
+                    tag, e, revert
+                ),
+            };
+            self.mark_failed(rollup_id, reason).await;
+            return Err(e);
+        }
+
+        let old = {
+            let mut registry = self.rollups.write().await;
+            let current = registry
+                .get_mut(rollup_id)
+                .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+            let old = current.status.clone();
+            current.node_image_tag = Some(tag.to_string());
+            current.set_status(info.status.clone());
+            self.persist(&registry)?;
+            old
+        };
+        self.notify_status_change(rollup_id, &old, &info.status);
+        info!("Upgraded rollup {} to node image tag {}", rollup_id, tag);
+        Ok(())
+    }
+
+    /// Replace the node keys of a rollup with freshly generated ones
+    ///
+    /// The node config is regenerated, and a running rollup is restarted on the new keys.
+    /// Only rollups with keys of their own can rotate them. The chain owner still has to
+    /// make the returned addresses the rollup's batch poster and a validator on chain.
+    pub async fn rotate_keys(&self, rollup_id: &str) -> Result<NodeKeyAddresses, RaasError> {
+        let _operation = self.begin_operation(rollup_id)?;
+
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let keys = info.node_keys.as_ref().ok_or_else(|| {
+            RaasError::InvalidConfig(anyhow!(
+                "Rollup {} runs on the operator's keys, rotate them in the keystore",
+                rollup_id
+            ))
+        })?;
+        let deployment = info
+            .deployment
+            .as_ref()
+            .ok_or_else(|| anyhow!("Rollup {} has no recorded deployment", rollup_id))?;
+
+        keys.rotate()?;
+        let addresses = match keys.addresses().and_then(|addresses| {
+            generate_configs(
+                &info,
+                &info.config,
+                deployment,
+                info.node_image_tag.as_deref(),
+            )
+            .map(|_| addresses)
+        }) {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                // Put the previous keys back, and the node config holding them
+                if let Err(e) = keys.roll_back().and_then(|_| {
+                    generate_configs(
+                        &info,
+                        &info.config,
+                        deployment,
+                        info.node_image_tag.as_deref(),
+                    )
+                }) {
+                    error!("Failed to restore the keys of rollup {}: {}", rollup_id, e);
+                }
+                return Err(RaasError::ConfigGenerationFailed(e));
+            }
+        };
+        warn!(
+            "Rotated the keys of rollup {}, the chain owner has to make {} its batch poster and {} a validator",
+            rollup_id, addresses.batch_poster, addresses.validator
+        );
+
+        if info.status == RollupStatus::Running {
+            self.stop_containers(rollup_id).await?;
+            self.start_containers(rollup_id).await?;
+        }
+        Ok(addresses)
+    }
+
+    /// Give the new rollup `info` keys of its own, running its validator and batch poster on them
+    fn provision_node_keys(&self, info: &mut RollupInfo) -> Result<()> {
+        let keys = NodeKeys::load_or_generate(self.paths(&info.rollup_id).keys)?;
+        let addresses = keys.addresses()?;
+        let bytes = |address: &str| -> Result<[u8; 20]> {
+            let mut bytes = [0; 20];
+            hex::decode_to_slice(address.trim_start_matches("0x"), &mut bytes)?;
+            Ok(bytes)
+        };
+        info.config.validators = vec![bytes(&addresses.validator)?];
+        info.config.batch_poster_address = bytes(&addresses.batch_poster)?;
+        info!(
+            "Rollup {} validates as {} and posts batches as {}",
+            info.rollup_id, addresses.validator, addresses.batch_poster
+        );
+        info.node_keys = Some(keys);
+        Ok(())
+    }
+
+    /// Replace the containers of a rollup with ones from its current compose file
+    async fn recreate_containers(
+        &self,
+        manager: &mut dyn ContainerRuntime,
+    ) -> Result<(), RaasError> {
+        manager.stop().await.map_err(container_error)?;
+        manager.start().await.map_err(container_error)?;
+        manager
+            .wait_until_healthy(self.start_timeout, self.start_poll_interval)
+            .await
+            .map_err(RaasError::ContainersUnhealthy)
+    }
+
+    /// Check that the node RPC of a rollup answers, not just that its container runs
+    ///
+    /// The sync status of a running node that answers is recorded along the way, see
+    /// [`RollupManager::check_sync`].
+    pub async fn health_check(&self, rollup_id: &str) -> Result<RollupHealth, RaasError> {
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let health = check_rpc(&node_rpc_url(&info)?, HEALTH_CHECK_TIMEOUT).await;
+        if health.is_healthy()
+            && info.status == RollupStatus::Running
+            && let Err(e) = self.check_sync(rollup_id).await
+        {
+            warn!("Failed to check the sync of rollup {}: {}", rollup_id, e);
+        }
+        Ok(health)
+    }
+
+    /// Ask the node of a rollup how far it has synced, recording it if the rollup is running
+    pub async fn check_sync(&self, rollup_id: &str) -> Result<SyncStatus, RaasError> {
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let sync_status = node_sync_status(&node_rpc_url(&info)?, HEALTH_CHECK_TIMEOUT).await?;
+
+        let mut registry = self.rollups.write().await;
+        let Some(info) = registry
+            .get_mut(rollup_id)
+            .filter(|info| info.status == RollupStatus::Running)
+        else {
+            return Ok(sync_status);
+        };
+        if info.sync_status != Some(sync_status) {
+            if sync_status.synced {
+                info!("Rollup {} is synced", rollup_id);
+            }
+            info.sync_status = Some(sync_status);
+            self.persist(&registry)?;
+        }
+        Ok(sync_status)
+    }
+
+    /// [`RollupManager::check_sync`] on every running rollup, checking `concurrency` at once
+    ///
+    /// Failures are only logged, the reconciliation tells whether the node is down.
+    pub async fn check_sync_all(&self, concurrency: usize) {
+        let running = self
+            .list_rollups()
+            .await
+            .into_iter()
+            .filter(|rollup| rollup.status == RollupStatus::Running)
+            .filter(|rollup| !self.is_busy(&rollup.rollup_id));
+        let mut checks = stream::iter(running)
+            .map(|rollup| async move { (self.check_sync(&rollup.rollup_id).await, rollup) })
+            .buffer_unordered(concurrency.max(1));
+        while let Some((result, rollup)) = checks.next().await {
+            if let Err(e) = result {
+                debug!(
+                    "Failed to check the sync of rollup {}: {}",
+                    rollup.rollup_id, e
+                );
+            }
+        }
+    }
+
+    /// Query the block number, peers and sync state of a rollup node, and its posted batches
+    ///
+    /// The batches are read from the sequencer inbox on the parent chain, and left unset if
+    /// that fails rather than failing the whole query.
+    pub async fn chain_metrics(&self, rollup_id: &str) -> Result<ChainMetrics, RaasError> {
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let mut metrics = node_metrics(&node_rpc_url(&info)?, HEALTH_CHECK_TIMEOUT).await?;
+        if let Some(deployment) = &info.deployment {
+            match posted_batches(
+                &info.config.network.node_rpc_url(),
+                &deployment.sequencer_inbox_address,
+                HEALTH_CHECK_TIMEOUT,
+            )
+            .await
+            {
+                Ok(batch_count) => metrics.batch_count = Some(batch_count),
+                Err(e) => warn!(
+                    "Failed to read the posted batches of rollup {}: {}",
+                    rollup_id, e
+                ),
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// Run the allowlisted command `name` in the node container of a rollup, returning its output
+    pub async fn exec_command(&self, rollup_id: &str, name: &str) -> Result<String, RaasError> {
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let command =
+            self.exec_allowlist
+                .get(name)
+                .ok_or_else(|| RaasError::CommandNotAllowed {
+                    command: name.to_string(),
+                    allowed: self.exec_allowlist.names().collect::<Vec<_>>().join(", "),
+                })?;
+
+        info!("Running {} in rollup {}: {:?}", name, rollup_id, command);
+        let command = command.iter().map(String::as_str).collect::<Vec<_>>();
+        self.runtime(&info)
+            .exec(&command)
+            .await
+            .map_err(RaasError::DockerUnavailable)
+    }
+
+    /// Get the externally reachable endpoints of a rollup's node
+    pub async fn endpoints(&self, rollup_id: &str) -> Result<Endpoints, RaasError> {
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        Ok(self.runtime(&info).endpoints(&self.public_host)?)
+    }
+
+    /// Get the logs of the node container of a rollup
+    pub async fn logs(&self, rollup_id: &str) -> Result<String, RaasError> {
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+
+        self.runtime(&info)
+            .logs()
+            .await
+            .map_err(RaasError::DockerUnavailable)
+    }
+
+    /// Stop every running rollup, e.g. before the process exits
+    ///
+    /// Returns the IDs of the rollups that were stopped. Failures are logged and
+    /// leave the rollup `Failed`; rollups busy with another operation are skipped.
+    pub async fn stop_all(&self) -> Vec<String> {
+        let running = self
+            .list_rollups_filtered(ListFilter {
+                status: Some(RollupStatus::Running),
+                ..Default::default()
+            })
+            .await;
+
+        let stops = running.into_iter().map(|rollup| async move {
+            match self.stop_rollup(&rollup.rollup_id).await {
+                Ok(()) => Some(rollup.rollup_id),
+                Err(e) => {
+                    error!("Failed to stop rollup {}: {}", rollup.rollup_id, e);
+                    None
+                }
+            }
+        });
+        futures::future::join_all(stops)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Start the created or stopped rollups of a service
+    ///
+    /// Returns the outcome for each rollup, a failing rollup doesn't keep the others from starting.
+    pub async fn start_all_for_service(
+        &self,
+        service_id: u64,
+    ) -> Vec<(String, Result<(), RaasError>)> {
+        let stopped = self
+            .list_rollups_filtered(ListFilter {
+                service_id: Some(service_id),
+                ..Default::default()
+            })
+            .await
+            .into_iter()
+            .filter(|rollup| {
+                matches!(rollup.status, RollupStatus::Created | RollupStatus::Stopped)
+            });
+
+        let starts = stopped.map(|rollup| async move {
+            let result = self.start_rollup(&rollup.rollup_id).await;
+            (rollup.rollup_id, result)
+        });
+        futures::future::join_all(starts).await
+    }
+
+    /// Stop the running rollups of a service
+    ///
+    /// Returns the outcome for each rollup, a failing rollup doesn't keep the others from stopping.
+    pub async fn stop_all_for_service(
+        &self,
+        service_id: u64,
+    ) -> Vec<(String, Result<(), RaasError>)> {
+        let running = self
+            .list_rollups_filtered(ListFilter {
+                service_id: Some(service_id),
+                status: Some(RollupStatus::Running),
+                ..Default::default()
+            })
+            .await;
+
+        let stops = running.into_iter().map(|rollup| async move {
+            let result = self.stop_rollup(&rollup.rollup_id).await;
+            (rollup.rollup_id, result)
+        });
+        futures::future::join_all(stops).await
+    }
+
     /// Delete a rollup
-    pub async fn delete_rollup(&self, rollup_id: &str) -> Result<()> {
+    ///
+    /// Stops the rollup, then removes its containers, network and volumes and its
+    /// files. Stop and cleanup failures are logged rather than aborting the delete. A
+    /// creation in progress is cancelled first, see [`RollupManager::cancel_creation`].
+    #[tracing::instrument(skip_all, fields(rollup_id = %rollup_id, service_id = tracing::field::Empty))]
+    pub async fn delete_rollup(&self, rollup_id: &str) -> Result<(), RaasError> {
+        self.cancel_and_wait(rollup_id).await;
+        let _operation = self.begin_operation(rollup_id)?;
+        self.record_service_id(rollup_id).await;
+
         // First stop the rollup if it's running
-        let registry = self.rollups.read().await;
-        let info = registry
-            .get(rollup_id)
-            .ok_or_else(|| anyhow!("Rollup not found"))?;
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
 
-        if info.status == RollupStatus::Running {
-            drop(registry);
-            self.stop_rollup(rollup_id).await?;
-        } else {
-            drop(registry);
+        // Containers that don't stop are still removed with the rest of the rollup
+        if info.status == RollupStatus::Running
+            && let Err(e) = self.stop_containers(rollup_id).await
+        {
+            warn!(
+                "Failed to stop rollup {} before deleting it: {}",
+                rollup_id, e
+            );
+        }
+
+        self.purge(&info).await
+    }
+
+    /// Remove the Docker resources, files and registry entry of a stopped rollup
+    ///
+    /// The caller holds the operation lock. Cleanup failures are logged.
+    async fn purge(&self, info: &RollupInfo) -> Result<(), RaasError> {
+        let manager = self.runtime(info);
+        if let Err(e) = manager.teardown().await {
+            warn!(
+                "Failed to remove Docker resources of rollup {}: {}",
+                info.rollup_id, e
+            );
+        }
+        if let Err(e) = self.remove_rollup_files(info) {
+            warn!("Failed to remove files of rollup {}: {}", info.rollup_id, e);
         }
 
         // Remove the rollup from the registry
-        self.rollups.write().await.remove(rollup_id);
+        let mut registry = self.rollups.write().await;
+        registry.remove(&info.rollup_id);
+        Ok(self.persist(&registry)?)
+    }
+
+    /// Delete the rollups that have been `Failed` for longer than `older_than`
+    ///
+    /// Returns the IDs of the pruned rollups. Rollups in any other status, or busy
+    /// with another operation, are left alone.
+    pub async fn prune(&self, older_than: Duration) -> Vec<String> {
+        self.prune_where(older_than, |_| true).await
+    }
+
+    /// [`RollupManager::prune`], only considering the rollups of a service
+    pub async fn prune_for_service(&self, service_id: u64, older_than: Duration) -> Vec<String> {
+        self.prune_where(older_than, |info| info.service_id == service_id)
+            .await
+    }
+
+    async fn prune_where<F>(&self, older_than: Duration, filter: F) -> Vec<String>
+    where
+        F: Fn(&RollupInfo) -> bool,
+    {
+        // Nothing failed before the cutoff if it lies before the earliest representable time
+        let Some(cutoff) = chrono::Duration::from_std(older_than)
+            .ok()
+            .and_then(|older_than| Utc::now().checked_sub_signed(older_than))
+        else {
+            return Vec::new();
+        };
+        let prunable = |info: &RollupInfo| {
+            matches!(info.status, RollupStatus::Failed(_))
+                && info.updated_at <= cutoff
+                && filter(info)
+        };
+
+        let mut pruned = Vec::new();
+        for rollup in self.list_rollups().await {
+            if !prunable(&rollup) {
+                continue;
+            }
+            let Ok(_operation) = self.begin_operation(&rollup.rollup_id) else {
+                info!("Not pruning rollup {}, it is busy", rollup.rollup_id);
+                continue;
+            };
+            // The rollup may have been retried since it was listed
+            let Some(info) = self
+                .get_rollup(&rollup.rollup_id)
+                .await
+                .filter(|info| prunable(info))
+            else {
+                continue;
+            };
+
+            match self.purge(&info).await {
+                Ok(()) => {
+                    info!("Pruned failed rollup {}", info.rollup_id);
+                    pruned.push(info.rollup_id);
+                }
+                Err(e) => error!("Failed to prune rollup {}: {}", info.rollup_id, e),
+            }
+        }
+        pruned
+    }
+
+    /// Whether `path` lies strictly inside the data directory
+    pub fn owns_path(&self, path: &Path) -> bool {
+        path != self.base_dir
+            && path.starts_with(&self.base_dir)
+            && !path
+                .components()
+                .any(|component| component == Component::ParentDir)
+    }
+
+    /// Remove the files a creation failing after the deployment left behind, best-effort
+    fn roll_back_files(&self, workspace_dir: &Path, config_dir: &Path, data_dir: &Path) {
+        let mut paths = vec![config_dir.to_path_buf(), data_dir.to_path_buf()];
+        if let Some(root) = workspace_dir.parent() {
+            paths.extend(["docker-compose.yml", "wasm"].map(|name| root.join(name)));
+        }
+        if !self.keep_failed_workspaces {
+            paths.push(workspace_dir.to_path_buf());
+        }
+
+        for path in paths.iter().filter(|path| path.exists()) {
+            if !self.owns_path(path) {
+                warn!(
+                    "Not rolling back {}, it is outside {}",
+                    path.display(),
+                    self.base_dir.display()
+                );
+                continue;
+            }
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            match removed {
+                Ok(()) => info!("Rolled back {}", path.display()),
+                Err(e) => warn!("Failed to roll back {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Remove the files and directories of a rollup that lie inside the data directory
+    ///
+    /// Removes as much as it can, then reports every path that couldn't be removed.
+    fn remove_rollup_files(&self, info: &RollupInfo) -> Result<()> {
+        let mut paths = vec![
+            info.workspace_dir.clone(),
+            info.config_dir.clone(),
+            info.data_dir(),
+        ];
+        if let Some(keys) = info
+            .node_keys
+            .as_ref()
+            .and_then(|keys| keys.validator.parent())
+        {
+            paths.push(keys.to_path_buf());
+        }
+        let root = info.workspace_dir.parent().map(Path::to_path_buf);
+        if let Some(root) = &root {
+            paths.extend(["docker-compose.yml", "wasm"].map(|name| root.join(name)));
+        }
+
+        let mut failures = Vec::new();
+        for path in paths.iter().filter(|path| path.exists()) {
+            if !self.owns_path(path) {
+                warn!(
+                    "Not removing {}, it is outside {}",
+                    path.display(),
+                    self.base_dir.display()
+                );
+                continue;
+            }
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            match removed {
+                Ok(()) => info!("Removed {}", path.display()),
+                Err(e) => failures.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        // Drop the rollup's own directory once nothing is left in it
+        if let Some(root) = root.filter(|root| self.owns_path(root)) {
+            let _ = fs::remove_dir(root);
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Failed to remove {}", failures.join("; ")))
+        }
+    }
+
+    /// Permanently retire a rollup
+    ///
+    /// Records the final block height, removes the containers, network and volumes,
+    /// archives the config and chain data into `archive_dir` (skipped when `None`),
+    /// then removes the rollup's directories and forgets it.
+    pub async fn decommission_rollup(
+        &self,
+        rollup_id: &str,
+        archive_dir: Option<&Path>,
+    ) -> Result<DecommissionReceipt, RaasError> {
+        let _operation = self.begin_operation(rollup_id)?;
+
+        let info = self
+            .get_rollup(rollup_id)
+            .await
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+        let rollup_root = info.paths().root;
+
+        self.update_rollup_status(rollup_id, RollupStatus::Deleting)
+            .await?;
+
+        // The node only answers while it's still running
+        let final_block = match crate::diagnostics::block_number(&info.rpc_url()).await {
+            Ok(block) => Some(block),
+            Err(e) => {
+                info!("Could not read final block of rollup {}: {}", rollup_id, e);
+                None
+            }
+        };
+
+        let manager = self.runtime(&info);
+        if let Err(e) = manager.teardown().await {
+            self.mark_failed(rollup_id, format!("Decommission failed: {}", e))
+                .await;
+            return Err(RaasError::DockerUnavailable(e));
+        }
+
+        // Archive once the containers are gone so the database is consistent
+        let archive_path = match archive_dir {
+            Some(archive_dir) => {
+                match archive_rollup_data(&rollup_root, archive_dir, rollup_id).await {
+                    Ok(path) => Some(path),
+                    Err(e) => {
+                        // Keep the data and the registry entry so the archive can be retried
+                        self.mark_failed(rollup_id, format!("Decommission failed: {}", e))
+                            .await;
+                        return Err(e.into());
+                    }
+                }
+            }
+            None => {
+                info!("Skipping archive of rollup {}", rollup_id);
+                None
+            }
+        };
+
+        if let Err(e) = self.remove_rollup_files(&info) {
+            // Keep the registry entry so the removal can be retried
+            self.mark_failed(rollup_id, format!("Decommission failed: {}", e))
+                .await;
+            return Err(e.into());
+        }
+
+        {
+            let mut registry = self.rollups.write().await;
+            registry.remove(rollup_id);
+            self.persist(&registry)?;
+        }
+
+        info!("Rollup {} decommissioned", rollup_id);
+        Ok(DecommissionReceipt {
+            rollup_id: info.rollup_id,
+            service_id: info.service_id,
+            chain_id: info.config.chain_id,
+            final_block,
+            initial_chain_owner: format!("0x{}", hex::encode(info.config.initial_chain_owner)),
+            validators: info
+                .config
+                .validators
+                .iter()
+                .map(|validator| format!("0x{}", hex::encode(validator)))
+                .collect(),
+            batch_poster_address: format!("0x{}", hex::encode(info.config.batch_poster_address)),
+            batch_poster_manager: format!("0x{}", hex::encode(info.config.batch_poster_manager)),
+            archive_path,
+            decommissioned_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// Correct each rollup's status from the actual state of its containers
+    ///
+    /// Inspects up to `concurrency` rollups at once and skips rollups that
+    /// have an operation in progress.
+    pub async fn reconcile(&self, concurrency: usize) -> Result<(), RaasError> {
+        self.reconcile_with(concurrency, |rollup| {
+            let runtime = self.runtime(&rollup);
+            async move { runtime.container_states().await }
+        })
+        .await
+    }
+
+    /// Reconcile using `inspect` to get the containers of each rollup
+    pub async fn reconcile_with<F, Fut>(
+        &self,
+        concurrency: usize,
+        inspect: F,
+    ) -> Result<(), RaasError>
+    where
+        F: Fn(RollupInfo) -> Fut,
+        Fut: Future<Output = Result<Vec<ServiceState>>>,
+    {
+        let rollups = self
+            .list_rollups()
+            .await
+            .into_iter()
+            .filter(|rollup| !self.is_busy(&rollup.rollup_id));
+
+        let mut inspections = stream::iter(rollups)
+            .map(|rollup| {
+                let containers = inspect(rollup.clone());
+                async move { (rollup, containers.await) }
+            })
+            .buffer_unordered(concurrency.max(1));
+
+        while let Some((rollup, containers)) = inspections.next().await {
+            let containers = match containers {
+                Ok(containers) => containers,
+                Err(e) => {
+                    error!(
+                        "Failed to inspect containers of rollup {}: {}",
+                        rollup.rollup_id, e
+                    );
+                    continue;
+                }
+            };
+
+            // An operation may have started, or finished, while the containers were inspected
+            if self.is_busy(&rollup.rollup_id) {
+                continue;
+            }
+            let Ok(current) = self.get_rollup_status(&rollup.rollup_id).await else {
+                continue;
+            };
+
+            if let Some(status) = reconciled_status(&current, &containers) {
+                info!(
+                    "Reconciled rollup {} from {} to {}",
+                    rollup.rollup_id, current, status
+                );
+                self.update_rollup_status(&rollup.rollup_id, status).await?;
+            }
+        }
 
         Ok(())
     }
@@ -324,6 +2227,15 @@ impl RollupManager {
         self.rollups.read().await.get(rollup_id).cloned()
     }
 
+    /// Get the deployed contracts of a rollup, if its deployment finished
+    pub async fn get_deployment(&self, rollup_id: &str) -> Option<DeploymentResult> {
+        self.rollups
+            .read()
+            .await
+            .get(rollup_id)
+            .and_then(|info| info.deployment.clone())
+    }
+
     /// Get a rollup by VM ID
     pub async fn get_rollup_by_vm_id(&self, vm_id: &str) -> Option<RollupInfo> {
         self.rollups
@@ -344,39 +2256,266 @@ impl RollupManager {
             .cloned()
     }
 
-    /// List all rollups
+    /// List all rollups, newest first
     pub async fn list_rollups(&self) -> Vec<RollupInfo> {
-        self.rollups.read().await.values().cloned().collect()
+        self.list_rollups_filtered(ListFilter::default()).await
+    }
+
+    /// List the rollups matching `filter`, newest first unless it asks for the oldest
+    pub async fn list_rollups_filtered(&self, filter: ListFilter) -> Vec<RollupInfo> {
+        let registry = self.rollups.read().await;
+        let mut rollups: Vec<&RollupInfo> = registry
+            .values()
+            .filter(|info| filter.matches(info))
+            .collect();
+        rollups.sort_by(|a, b| (a.created_at, &a.rollup_id).cmp(&(b.created_at, &b.rollup_id)));
+        if !filter.oldest_first {
+            rollups.reverse();
+        }
+
+        rollups
+            .into_iter()
+            .skip(filter.offset.unwrap_or(0))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of rollups matching `filter`, ignoring its limit and offset
+    pub async fn count_rollups(&self, filter: &ListFilter) -> usize {
+        let registry = self.rollups.read().await;
+        registry
+            .values()
+            .filter(|info| filter.matches(info))
+            .count()
     }
 
     /// Get the status of a rollup
-    pub async fn get_rollup_status(&self, rollup_id: &str) -> Result<RollupStatus> {
+    pub async fn get_rollup_status(&self, rollup_id: &str) -> Result<RollupStatus, RaasError> {
         // Get rollup information
         let registry = self.rollups.read().await;
         let info = registry
             .get(rollup_id)
-            .ok_or_else(|| anyhow!("Rollup not found"))?;
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
 
         Ok(info.status.clone())
     }
 
-    /// Update the status of a rollup
-    pub async fn update_rollup_status(&self, rollup_id: &str, status: RollupStatus) -> Result<()> {
+    /// Watch the status of a rollup, to await its transitions instead of polling
+    ///
+    /// The channel closes once the rollup is deleted.
+    pub async fn watch_status(
+        &self,
+        rollup_id: &str,
+    ) -> Result<watch::Receiver<RollupStatus>, RaasError> {
+        // Holding the registry lock, no status change can slip in before the channel exists
+        let registry = self.rollups.read().await;
+        let info = registry
+            .get(rollup_id)
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
+
+        let mut watchers = self
+            .status_watchers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(watchers
+            .entry(rollup_id.to_string())
+            .or_insert_with(|| watch::channel(info.status.clone()).0)
+            .subscribe())
+    }
+
+    /// Call `callback` on every status change of any rollup, e.g. to push webhooks
+    ///
+    /// Callbacks run after the registry lock is released, so they may call back into the
+    /// manager, but they run on the task changing the status and should return quickly.
+    pub fn on_status_change(&self, callback: Box<StatusCallback>) {
+        self.status_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::from(callback));
+    }
+
+    /// Call the status change callbacks, unless the status stayed the same
+    fn notify_status_change(&self, rollup_id: &str, old: &RollupStatus, new: &RollupStatus) {
+        if old == new {
+            return;
+        }
+        // Callbacks may register further callbacks, so don't hold the lock while calling them
+        let callbacks = self
+            .status_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for callback in callbacks {
+            callback(rollup_id, old, new);
+        }
+    }
+
+    /// Update the status of a rollup, notifying its watchers and callbacks
+    pub async fn update_rollup_status(
+        &self,
+        rollup_id: &str,
+        status: RollupStatus,
+    ) -> Result<(), RaasError> {
         // Get rollup information
         let mut registry = self.rollups.write().await;
         let info = registry
             .get_mut(rollup_id)
-            .ok_or_else(|| anyhow!("Rollup not found"))?;
+            .ok_or_else(|| RaasError::RollupNotFound(rollup_id.to_string()))?;
 
         // Update the status
-        info.status = status;
+        let old = info.status.clone();
+        info.set_status(status.clone());
+        self.persist(&registry)?;
+        drop(registry);
 
+        self.notify_status_change(rollup_id, &old, &status);
         Ok(())
     }
 }
 
 impl Default for RollupManager {
     fn default() -> Self {
-        Self::new()
+        Self::with_base_dir(PathBuf::from(DEFAULT_DATA_DIR))
+    }
+}
+
+/// Generate the node configuration of the rollup `info` running `config`
+///
+/// The node keys are the rollup's own if it has them. Otherwise they are loaded from the
+/// keystore, or `VALIDATOR_PRIVATE_KEY` and `BATCH_POSTER_PRIVATE_KEY` without one.
+fn generate_configs(
+    info: &RollupInfo,
+    config: &RollupConfig,
+    deployment: &DeploymentResult,
+    node_image_tag: Option<&str>,
+) -> Result<()> {
+    let (validator_key, batch_poster_key) = match &info.node_keys {
+        Some(keys) => (
+            keys.load(RollupKey::Validator)?,
+            keys.load(RollupKey::BatchPoster)?,
+        ),
+        None => (RollupKey::Validator.load()?, RollupKey::BatchPoster.load()?),
+    };
+    let parent_chain_rpc_url = config.network.node_rpc_url();
+
+    let mut generator = ConfigGenerator::new(
+        &info.config_dir,
+        &info.workspace_dir,
+        config.network,
+        checksum_address(&config.initial_chain_owner),
+        deployment.clone(),
+        validator_key,
+        batch_poster_key,
+        parent_chain_rpc_url,
+    )
+    .with_node_flags(config.node_flags.clone())
+    .with_rpc_host_port(info.rpc_port.unwrap_or(NODE_RPC_PORT))
+    .with_extra_env(config.extra_env.clone());
+    if let Some(image) = &config.node_image {
+        generator = generator.with_node_image(image.clone());
+    }
+    if let Some(url) = &config.espresso_sequencer_url {
+        generator = generator.with_espresso_sequencer_url(url.clone());
+    }
+    if let Some(namespace) = config.espresso_namespace {
+        generator = generator.with_espresso_namespace(namespace);
+    }
+    if let Some(tag) = node_image_tag {
+        generator = generator.with_node_image_tag(tag.to_string());
+    }
+    if let Some(data_dir) = &info.data_dir {
+        generator = generator.with_data_dir(data_dir);
+    }
+    // Rollups created before these ports were allocated keep the ones they always published
+    if let Some(port) = info.ws_port {
+        generator = generator.with_ws_host_port(port);
+    }
+    if let Some(port) = info.feed_port {
+        generator = generator.with_feed_host_port(port);
+    }
+    if let Some(port) = info.validation_port {
+        generator = generator.with_validation_host_port(port);
     }
+    generator.generate_configs()
+}
+
+/// The error of a container operation failing with `e`
+///
+/// Rejected compose files and images fail again until they are changed, anything else is
+/// the Docker daemon failing.
+fn container_error(e: anyhow::Error) -> RaasError {
+    if e.is::<ComposeRejected>() {
+        RaasError::ComposeRejected(e)
+    } else {
+        RaasError::DockerUnavailable(e)
+    }
+}
+
+/// RPC endpoint of the node of `info` on this host
+fn node_rpc_url(info: &RollupInfo) -> Result<String> {
+    // Rollups created before ports were allocated only have theirs in the compose file
+    match info.rpc_port {
+        Some(_) => Ok(info.rpc_url()),
+        None => info.docker_manager().rpc_url(),
+    }
+}
+
+/// Drive `deployment` to completion, logging the transactions and output `progress` receives
+async fn follow_progress<T>(
+    rollup_id: &str,
+    deployment: impl Future<Output = T>,
+    progress: &mut mpsc::Receiver<DeployProgress>,
+) -> T {
+    let mut deployment = std::pin::pin!(deployment);
+    loop {
+        tokio::select! {
+            result = &mut deployment => return result,
+            Some(event) = progress.recv() => match event {
+                DeployProgress::Transaction(hash) => {
+                    info!("Deployment of rollup {} sent transaction {}", rollup_id, hash)
+                }
+                DeployProgress::Output(line) => debug!("[{}] {}", rollup_id, line),
+                DeployProgress::PhaseStarted(_)
+                | DeployProgress::PhaseFinished(_)
+                | DeployProgress::PhaseFailed(..) => {}
+            },
+        }
+    }
+}
+
+/// Read a status, accepting the phase-less `Creating` written by older versions
+fn deserialize_status<'de, D>(deserializer: D) -> std::result::Result<RollupStatus, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    if value == "Creating" {
+        return Ok(RollupStatus::Creating(CreationPhase::default()));
+    }
+    serde_json::from_value(value).map_err(serde::de::Error::custom)
+}
+
+/// Read an RFC 3339 timestamp in any offset
+///
+/// Older versions stored it as a free-form string, unreadable ones fall back to the Unix
+/// epoch so the rest of the state still loads.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> std::result::Result<DateTime<Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    Ok(DateTime::parse_from_rfc3339(&value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .unwrap_or_else(|e| {
+            warn!("Invalid timestamp {:?}, using the Unix epoch: {}", value, e);
+            DateTime::UNIX_EPOCH
+        }))
+}
+
+fn default_base_dir(state_file: &Path) -> PathBuf {
+    state_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_DIR))
 }
@@ -0,0 +1,93 @@
+use crate::docker::container::ServiceState;
+use crate::docker::espresso::EspressoDockerManager;
+use crate::docker::health::Endpoints;
+use anyhow::Result;
+use std::time::Duration;
+
+/// Runs the containers of one rollup
+///
+/// Implemented by [`EspressoDockerManager`] and, without any Docker daemon, [`MockRuntime`].
+///
+/// [`MockRuntime`]: crate::docker::mock::MockRuntime
+#[async_trait::async_trait]
+pub trait ContainerRuntime: Send + Sync {
+    /// Start the containers
+    async fn start(&mut self) -> Result<()>;
+
+    /// Wait for the node to come up, failing after `timeout`
+    async fn wait_until_healthy(&self, timeout: Duration, poll_interval: Duration) -> Result<()>;
+
+    /// Stop the containers
+    async fn stop(&self) -> Result<()>;
+
+    /// Status of the node container
+    async fn status(&self) -> Result<String>;
+
+    /// Status of every service the rollup needs
+    async fn service_statuses(&self) -> Result<Vec<(String, String)>>;
+
+    /// State of every container of the rollup
+    async fn container_states(&self) -> Result<Vec<ServiceState>>;
+
+    /// Remove the containers and everything they leave behind
+    async fn teardown(&self) -> Result<()>;
+
+    /// Pull the images of the containers without starting them
+    async fn pull_images(&self) -> Result<()>;
+
+    /// Run `command` in the node container, returning its output
+    async fn exec(&self, command: &[&str]) -> Result<String>;
+
+    /// Logs of the node container
+    async fn logs(&self) -> Result<String>;
+
+    /// Endpoints the node publishes on `host`
+    fn endpoints(&self, host: &str) -> Result<Endpoints>;
+}
+
+#[async_trait::async_trait]
+impl ContainerRuntime for EspressoDockerManager {
+    async fn start(&mut self) -> Result<()> {
+        EspressoDockerManager::start(self).await
+    }
+
+    async fn wait_until_healthy(&self, timeout: Duration, poll_interval: Duration) -> Result<()> {
+        EspressoDockerManager::wait_until_healthy(self, timeout, poll_interval).await
+    }
+
+    async fn stop(&self) -> Result<()> {
+        EspressoDockerManager::stop(self).await
+    }
+
+    async fn status(&self) -> Result<String> {
+        self.get_status().await
+    }
+
+    async fn service_statuses(&self) -> Result<Vec<(String, String)>> {
+        EspressoDockerManager::service_statuses(self).await
+    }
+
+    async fn container_states(&self) -> Result<Vec<ServiceState>> {
+        EspressoDockerManager::container_states(self).await
+    }
+
+    async fn teardown(&self) -> Result<()> {
+        EspressoDockerManager::teardown(self).await
+    }
+
+    async fn pull_images(&self) -> Result<()> {
+        EspressoDockerManager::pull_images(self).await
+    }
+
+    async fn exec(&self, command: &[&str]) -> Result<String> {
+        self.exec_command(command).await
+    }
+
+    async fn logs(&self) -> Result<String> {
+        self.get_logs().await
+    }
+
+    fn endpoints(&self, host: &str) -> Result<Endpoints> {
+        EspressoDockerManager::endpoints(self, host)
+    }
+}
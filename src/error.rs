@@ -0,0 +1,61 @@
+/// Errors from managing rollups
+///
+/// Lets callers tell failures apart, e.g. to only retry the transient ones.
+#[derive(Debug, thiserror::Error)]
+pub enum RaasError {
+    /// No rollup is registered under the identifier
+    #[error("Rollup not found: {0}")]
+    RollupNotFound(String),
+    /// Another operation is already running on the rollup
+    #[error("Rollup {0} is busy with another operation")]
+    RollupBusy(String),
+    /// The Docker daemon couldn't be reached or failed to start, stop or remove the containers
+    #[error("Docker unavailable: {0}")]
+    DockerUnavailable(anyhow::Error),
+    /// The rollup's containers started but its services didn't come up healthy
+    #[error("Containers unhealthy: {0}")]
+    ContainersUnhealthy(anyhow::Error),
+    /// The rollup's compose file or one of its images can't be deployed
+    #[error("Compose file rejected: {0}")]
+    ComposeRejected(anyhow::Error),
+    /// The creation of the rollup was cancelled
+    #[error("Creation of rollup {0} was cancelled")]
+    Cancelled(String),
+    /// The rollup contracts couldn't be deployed
+    #[error("Failed to deploy contracts: {0}")]
+    DeploymentFailed(anyhow::Error),
+    /// The node configuration couldn't be generated
+    #[error("Failed to generate config files: {0}")]
+    ConfigGenerationFailed(anyhow::Error),
+    /// The rollup config can't be deployed
+    #[error("Invalid rollup config: {0}")]
+    InvalidConfig(anyhow::Error),
+    /// Another rollup already uses the chain ID
+    #[error("Chain ID {chain_id} is already used by rollup {rollup_id}")]
+    ChainIdInUse { chain_id: u64, rollup_id: String },
+    /// Another rollup already publishes its RPC on the host port
+    #[error("RPC port {port} is already used by rollup {rollup_id}")]
+    RpcPortInUse { port: u16, rollup_id: String },
+    /// Another rollup already keeps its chain data in the directory
+    #[error("Data directory {path} is already used by rollup {rollup_id}")]
+    DataDirInUse { path: String, rollup_id: String },
+    /// The host lacks tools or services creating a rollup needs
+    #[error("Host is missing prerequisites: {0}")]
+    PreflightFailed(String),
+    /// The command isn't on the exec allowlist
+    #[error("Command {command} is not allowed, expected one of: {allowed}")]
+    CommandNotAllowed { command: String, allowed: String },
+    /// Anything else, such as failing to persist the rollup state
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl RaasError {
+    /// Whether retrying the operation later may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            RaasError::RollupBusy(_) | RaasError::DockerUnavailable(_)
+        )
+    }
+}
@@ -1,20 +1,31 @@
 use blueprint_sdk as sdk;
 use blueprint_sdk::tangle::extract::List;
 
+use crate::deployer::config::{
+    validate_extra_env, validate_image_reference, validate_sequencer_url,
+};
+use alloy::primitives::Address;
 use sdk::macros::context::{ServicesContext, TangleClientContext};
 use sdk::runner::config::BlueprintEnvironment;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 mod custom_serde;
 pub mod deployer;
+pub mod diagnostics;
 pub mod docker;
+pub mod error;
+pub mod metrics;
 
-pub use deployer::DeploymentResult;
+pub use deployer::{DeploymentResult, NodeFlags};
+pub use error::RaasError;
 
 // Re-export Docker functionality
 pub use docker::{
     EspressoDockerManager, RollupInfo, RollupManager, RollupStatus as DockerRollupStatus,
-    create_rollup, delete_rollup, get_rollup_status, list_rollups, start_rollup, stop_rollup,
+    create_rollup, delete_rollup, get_rollup_status, list_rollups, reconcile_rollups,
+    restart_rollup, shutdown_rollups, spawn_reconciler, start_rollup, stop_rollup,
 };
 
 // Service context for our blueprint
@@ -22,14 +33,30 @@ pub use docker::{
 pub struct ServiceContext {
     #[config]
     pub config: BlueprintEnvironment,
+    /// Manager the jobs operate on
+    pub rollups: Arc<RollupManager>,
 }
 
 impl ServiceContext {
+    /// Context using the manager configured from the environment
     pub fn new(config: BlueprintEnvironment) -> Self {
-        Self { config }
+        Self {
+            config,
+            rollups: docker::helpers::default_rollup_manager(),
+        }
+    }
+
+    /// Use `rollups` instead of the default manager, e.g. one rooted in a test directory
+    pub fn with_rollup_manager(mut self, rollups: Arc<RollupManager>) -> Self {
+        self.rollups = rollups;
+        self
     }
 }
 
+/// Environment variable setting the parent chain RPC of the rollup nodes, see
+/// [`NetworkType::node_rpc_url`]
+pub const NODE_RPC_URL_ENV_VAR: &str = "ARBITRUM_RPC_URL";
+
 /// Network type for the rollup
 #[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum NetworkType {
@@ -37,6 +64,8 @@ pub enum NetworkType {
     Geth,
     ArbitrumMainnet,
     ArbitrumSepolia,
+    BaseSepolia,
+    OptimismSepolia,
 }
 
 impl NetworkType {
@@ -45,13 +74,76 @@ impl NetworkType {
             NetworkType::Geth => "http://localhost:8545",
             NetworkType::ArbitrumMainnet => "https://arb1.arbitrum.io/rpc",
             NetworkType::ArbitrumSepolia => "https://sepolia-rollup.arbitrum.io/rpc",
+            NetworkType::BaseSepolia => "https://sepolia.base.org",
+            NetworkType::OptimismSepolia => "https://sepolia.optimism.io",
+        }
+    }
+
+    /// Environment variable overriding [`NetworkType::rpc_url`]
+    pub fn rpc_url_env_var(&self) -> &'static str {
+        match self {
+            NetworkType::Geth => "GETH_RPC_URL",
+            NetworkType::ArbitrumMainnet => "ARB1_RPC_URL",
+            NetworkType::ArbitrumSepolia => "ARB_SEPOLIA_RPC_URL",
+            NetworkType::BaseSepolia => "BASE_SEPOLIA_RPC_URL",
+            NetworkType::OptimismSepolia => "OP_SEPOLIA_RPC_URL",
         }
     }
 
+    /// RPC endpoint the contracts are deployed through
+    ///
+    /// Read from [`NetworkType::rpc_url_env_var`], falling back to the public one.
+    pub fn resolved_rpc_url(&self) -> String {
+        std::env::var(self.rpc_url_env_var())
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| self.rpc_url().to_string())
+    }
+
+    /// RPC endpoint the rollup nodes follow the parent chain on for as long as they run
+    ///
+    /// Read from [`NODE_RPC_URL_ENV_VAR`], so a rate-limited deployment endpoint doesn't
+    /// have to serve the nodes, falling back to [`NetworkType::resolved_rpc_url`].
+    pub fn node_rpc_url(&self) -> String {
+        std::env::var(NODE_RPC_URL_ENV_VAR)
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| self.resolved_rpc_url())
+    }
+
+    /// Chain ID served by [`NetworkType::rpc_url`]
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            NetworkType::Geth => 1337,
+            NetworkType::ArbitrumMainnet => 42161,
+            NetworkType::ArbitrumSepolia => 421614,
+            NetworkType::BaseSepolia => 84532,
+            NetworkType::OptimismSepolia => 11155420,
+        }
+    }
+
+    /// Name of the network in the nitro-contracts hardhat config
+    pub fn hardhat_network_name(&self) -> &'static str {
+        match self {
+            NetworkType::Geth => "geth",
+            NetworkType::ArbitrumMainnet => "arb1",
+            NetworkType::ArbitrumSepolia => "arbSepolia",
+            NetworkType::BaseSepolia => "baseSepolia",
+            NetworkType::OptimismSepolia => "opSepolia",
+        }
+    }
+
+    /// Whether deploying to this network spends real funds
+    pub fn is_mainnet(&self) -> bool {
+        matches!(self, NetworkType::ArbitrumMainnet)
+    }
+
     pub fn parent_chain_id(&self) -> u64 {
         match self {
             NetworkType::ArbitrumMainnet => 1,        // Ethereum Mainnet
             NetworkType::ArbitrumSepolia => 11155111, // Ethereum Sepolia
+            NetworkType::BaseSepolia => 84532,        // Base Sepolia
+            NetworkType::OptimismSepolia => 11155420, // OP Sepolia
             NetworkType::Geth => 1337,                // Geth
         }
     }
@@ -63,10 +155,30 @@ impl std::fmt::Display for NetworkType {
             NetworkType::Geth => write!(f, "geth"),
             NetworkType::ArbitrumMainnet => write!(f, "arb1"),
             NetworkType::ArbitrumSepolia => write!(f, "arbSepolia"),
+            NetworkType::BaseSepolia => write!(f, "baseSepolia"),
+            NetworkType::OptimismSepolia => write!(f, "opSepolia"),
         }
     }
 }
 
+impl std::str::FromStr for NetworkType {
+    type Err = anyhow::Error;
+
+    /// Parse the name [`NetworkType`] displays as, e.g. `arbSepolia`
+    fn from_str(name: &str) -> anyhow::Result<Self> {
+        [
+            NetworkType::Geth,
+            NetworkType::ArbitrumMainnet,
+            NetworkType::ArbitrumSepolia,
+            NetworkType::BaseSepolia,
+            NetworkType::OptimismSepolia,
+        ]
+        .into_iter()
+        .find(|network| network.to_string() == name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown network {:?}", name))
+    }
+}
+
 // Serializable rollup configuration for job parameters
 #[derive(Default, Serialize, Deserialize)]
 pub struct RollupConfigParams {
@@ -88,6 +200,30 @@ pub struct RollupConfigParams {
     pub is_mainnet: bool,
     /// Network
     pub network: NetworkType,
+    /// Nitro node flags
+    #[serde(default)]
+    pub node_flags: NodeFlags,
+    /// Build and configure everything without sending deployment transactions
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Client-chosen ID making retried creations return the rollup created first
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// Host port of the node RPC, allocated by the operator when unset
+    #[serde(default)]
+    pub rpc_host_port: Option<u16>,
+    /// Image reference of the node containers, the bundled nitro image when unset
+    #[serde(default)]
+    pub node_image: Option<String>,
+    /// Query service URL of a shared Espresso network, the Decaf testnet when unset
+    #[serde(default)]
+    pub espresso_sequencer_url: Option<String>,
+    /// Namespace of the rollup on the Espresso network, the chain ID when unset
+    #[serde(default)]
+    pub espresso_namespace: Option<u64>,
+    /// Extra environment variables of the node container, as `NAME=value` entries
+    #[serde(default)]
+    pub extra_env: Vec<String>,
 }
 
 impl std::fmt::Debug for RollupConfigParams {
@@ -117,6 +253,14 @@ impl std::fmt::Debug for RollupConfigParams {
             )
             .field("is_mainnet", &self.is_mainnet)
             .field("network", &self.network)
+            .field("node_flags", &self.node_flags)
+            .field("dry_run", &self.dry_run)
+            .field("request_id", &self.request_id)
+            .field("rpc_host_port", &self.rpc_host_port)
+            .field("node_image", &self.node_image)
+            .field("espresso_sequencer_url", &self.espresso_sequencer_url)
+            .field("espresso_namespace", &self.espresso_namespace)
+            .field("extra_env", &self.extra_env)
             .finish()
     }
 }
@@ -131,11 +275,21 @@ impl Clone for RollupConfigParams {
             batch_poster_manager: self.batch_poster_manager,
             is_mainnet: self.is_mainnet,
             network: self.network.clone(),
+            node_flags: self.node_flags.clone(),
+            dry_run: self.dry_run,
+            request_id: self.request_id.clone(),
+            rpc_host_port: self.rpc_host_port,
+            node_image: self.node_image.clone(),
+            espresso_sequencer_url: self.espresso_sequencer_url.clone(),
+            espresso_namespace: self.espresso_namespace,
+            extra_env: self.extra_env.clone(),
         }
     }
 }
 
 /// Rollup configuration
+///
+/// See [`RollupConfig::immutable_changes`] for the fields a deployed rollup can update.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RollupConfig {
     /// Chain ID
@@ -148,8 +302,33 @@ pub struct RollupConfig {
     pub batch_poster_address: [u8; 20],
     /// Batch poster manager
     pub batch_poster_manager: [u8; 20],
-    /// Is mainnet
+    /// Confirms that deploying to a mainnet network is intended
+    #[serde(default)]
+    pub is_mainnet: bool,
+    /// Network
     pub network: NetworkType,
+    /// Nitro node flags
+    #[serde(default)]
+    pub node_flags: NodeFlags,
+    /// Build and configure everything without sending deployment transactions
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Host port of the node RPC, allocated by the operator when unset
+    #[serde(default)]
+    pub rpc_host_port: Option<u16>,
+    /// Image reference of the node containers, e.g. from a private registry, the bundled
+    /// nitro image when unset
+    #[serde(default)]
+    pub node_image: Option<String>,
+    /// Query service URL of a shared Espresso network, the Decaf testnet when unset
+    #[serde(default)]
+    pub espresso_sequencer_url: Option<String>,
+    /// Namespace of the rollup on the Espresso network, the chain ID when unset
+    #[serde(default)]
+    pub espresso_namespace: Option<u64>,
+    /// Extra environment variables of the node container, see [`validate_extra_env`]
+    #[serde(default)]
+    pub extra_env: HashMap<String, String>,
 }
 
 /// Convert RollupConfigParams to RollupConfig
@@ -161,7 +340,363 @@ impl From<RollupConfigParams> for RollupConfig {
             validators: params.validators.0,
             batch_poster_address: params.batch_poster_address,
             batch_poster_manager: params.batch_poster_manager,
+            is_mainnet: params.is_mainnet,
             network: params.network,
+            node_flags: params.node_flags,
+            dry_run: params.dry_run,
+            rpc_host_port: params.rpc_host_port,
+            node_image: params.node_image,
+            espresso_sequencer_url: params.espresso_sequencer_url,
+            espresso_namespace: params.espresso_namespace,
+            extra_env: parse_env_entries(&params.extra_env),
+        }
+    }
+}
+
+/// Environment variables from `NAME=value` entries, an entry without `=` sets the variable empty
+fn parse_env_entries(entries: &[String]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let (name, value) = entry.split_once('=').unwrap_or((entry, ""));
+            (name.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// Node settings to regenerate the config of a deployed rollup with
+///
+/// Unset fields keep the rollup's current settings. Only settings that don't touch the
+/// deployed contracts can be overridden.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigOverrides {
+    /// Nitro node flags, replacing the current ones
+    #[serde(default)]
+    pub node_flags: Option<NodeFlags>,
+    /// Image reference of the node containers
+    #[serde(default)]
+    pub node_image: Option<String>,
+    /// Query service URL of the Espresso network
+    #[serde(default)]
+    pub espresso_sequencer_url: Option<String>,
+    /// Extra environment variables of the node container as `NAME=value` entries,
+    /// replacing the current ones
+    #[serde(default)]
+    pub extra_env: Option<Vec<String>>,
+}
+
+impl ConfigOverrides {
+    /// `config` with the overrides applied
+    pub fn apply(self, mut config: RollupConfig) -> RollupConfig {
+        if let Some(node_flags) = self.node_flags {
+            config.node_flags = node_flags;
+        }
+        if let Some(node_image) = self.node_image {
+            config.node_image = Some(node_image);
+        }
+        if let Some(url) = self.espresso_sequencer_url {
+            config.espresso_sequencer_url = Some(url);
+        }
+        if let Some(extra_env) = self.extra_env {
+            config.extra_env = parse_env_entries(&extra_env);
+        }
+        config
+    }
+}
+
+impl Default for RollupConfig {
+    /// The crate defaults, shared with [`RollupConfigParams::default`]
+    fn default() -> Self {
+        RollupConfigParams::default().into()
+    }
+}
+
+/// A config field whose value differs from the crate default
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldDiff {
+    /// Field name, dotted for nested fields
+    pub field: String,
+    /// Default value of the field
+    pub default: serde_json::Value,
+    /// Configured value of the field
+    pub value: serde_json::Value,
+}
+
+/// Smallest chain ID a rollup may use, lower ones are taken by public networks
+pub const MIN_CHAIN_ID: u64 = 1000;
+
+/// Chain IDs below this are accepted with a warning, most of them are registered already
+const SMALL_CHAIN_ID: u64 = 100_000;
+
+/// Chain IDs of public networks a rollup must not reuse
+pub const RESERVED_CHAIN_IDS: [(u64, &str); 14] = [
+    (1, "Ethereum"),
+    (10, "OP Mainnet"),
+    (56, "BNB Smart Chain"),
+    (137, "Polygon"),
+    (1337, "Geth devnets"),
+    (8453, "Base"),
+    (17000, "Holesky"),
+    (31337, "Hardhat"),
+    (42161, "Arbitrum One"),
+    (42170, "Arbitrum Nova"),
+    (84532, "Base Sepolia"),
+    (421614, "Arbitrum Sepolia"),
+    (11155111, "Sepolia"),
+    (11155420, "OP Sepolia"),
+];
+
+impl RollupConfig {
+    /// Fields fixed by the deployed contracts or the rollup's sequenced history
+    const IMMUTABLE_FIELDS: [&'static str; 7] = [
+        "chain_id",
+        "initial_chain_owner",
+        "is_mainnet",
+        "network",
+        "dry_run",
+        "rpc_host_port",
+        "espresso_namespace",
+    ];
+
+    /// List the fields that differ in `updated` but can't change on a deployed rollup
+    ///
+    /// `chain_id`, `initial_chain_owner`, `is_mainnet`, `network`, `dry_run`, `rpc_host_port`
+    /// and `espresso_namespace` are immutable.
+    /// `validators`, `batch_poster_address`, `batch_poster_manager`, `node_flags`,
+    /// `node_image`, `espresso_sequencer_url` and `extra_env` only go into the node config
+    /// and are hot-reloadable.
+    pub fn immutable_changes(&self, updated: &RollupConfig) -> Vec<String> {
+        self.fields()
+            .into_iter()
+            .zip(updated.fields())
+            .filter(|((field, value), (_, updated))| {
+                Self::IMMUTABLE_FIELDS.contains(&field.as_str()) && value != updated
+            })
+            .map(|((field, _), _)| field)
+            .collect()
+    }
+
+    /// Check that the config can be deployed, naming the offending field otherwise
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.validate_reserving(&[])
+    }
+
+    /// [`RollupConfig::validate`], also refusing the operator's `reserved_chain_ids`
+    pub fn validate_reserving(&self, reserved_chain_ids: &[u64]) -> anyhow::Result<()> {
+        self.validate_chain_id(reserved_chain_ids)?;
+
+        let addresses = [
+            ("initial_chain_owner", &self.initial_chain_owner),
+            ("batch_poster_address", &self.batch_poster_address),
+            ("batch_poster_manager", &self.batch_poster_manager),
+        ];
+        for (field, address) in addresses {
+            if *address == [0; 20] {
+                anyhow::bail!("{} must be a non-zero address", field);
+            }
         }
+
+        if self.validators.is_empty() {
+            anyhow::bail!("validators must contain at least one address");
+        }
+        for (index, validator) in self.validators.iter().enumerate() {
+            if *validator == [0; 20] {
+                anyhow::bail!("validators[{}] must be a non-zero address", index);
+            }
+        }
+
+        if let Some(image) = &self.node_image {
+            validate_image_reference(image).map_err(|e| anyhow::anyhow!("node_image: {}", e))?;
+        }
+        if let Some(url) = &self.espresso_sequencer_url {
+            validate_sequencer_url(url)
+                .map_err(|e| anyhow::anyhow!("espresso_sequencer_url: {}", e))?;
+        }
+        validate_extra_env(&self.extra_env).map_err(|e| anyhow::anyhow!("extra_env: {}", e))?;
+
+        self.node_flags.validate()
+    }
+
+    /// Whether deploying the rollup spends real funds
+    pub fn targets_mainnet(&self) -> bool {
+        self.is_mainnet || self.network.is_mainnet()
+    }
+
+    /// Refuse mainnet deployments unless the operator `allowed` them
+    pub fn check_mainnet_allowed(&self, allowed: bool) -> anyhow::Result<()> {
+        if self.targets_mainnet() && !allowed {
+            anyhow::bail!(
+                "Refusing to deploy to mainnet {}, set ESPRESSO_ALLOW_MAINNET=true to spend real funds",
+                self.network
+            );
+        }
+        Ok(())
+    }
+
+    /// Initial chain owner
+    pub fn owner_address(&self) -> Address {
+        Address::from(self.initial_chain_owner)
+    }
+
+    /// Validators, in order
+    pub fn validator_addresses(&self) -> Vec<Address> {
+        self.validators.iter().copied().map(Address::from).collect()
+    }
+
+    /// Batch poster
+    pub fn batch_poster(&self) -> Address {
+        Address::from(self.batch_poster_address)
+    }
+
+    /// Batch poster manager
+    pub fn batch_poster_manager_address(&self) -> Address {
+        Address::from(self.batch_poster_manager)
+    }
+
+    /// Set the initial chain owner
+    pub fn with_owner(mut self, owner: impl Into<Address>) -> Self {
+        self.initial_chain_owner = owner.into().into();
+        self
+    }
+
+    /// Replace the validators
+    pub fn with_validators<I, A>(mut self, validators: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<Address>,
+    {
+        self.validators = validators
+            .into_iter()
+            .map(|validator| validator.into().into())
+            .collect();
+        self
+    }
+
+    /// Set the batch poster
+    pub fn with_batch_poster(mut self, batch_poster: impl Into<Address>) -> Self {
+        self.batch_poster_address = batch_poster.into().into();
+        self
+    }
+
+    /// Set the batch poster manager
+    pub fn with_batch_poster_manager(mut self, manager: impl Into<Address>) -> Self {
+        self.batch_poster_manager = manager.into().into();
+        self
+    }
+
+    /// Check that the chain ID is custom, neither a public network's nor the parent chain's
+    fn validate_chain_id(&self, reserved_chain_ids: &[u64]) -> anyhow::Result<()> {
+        let chain_id = self.chain_id;
+        if chain_id == 0 {
+            anyhow::bail!(
+                "chain_id must be set to a custom chain ID of at least {}",
+                MIN_CHAIN_ID
+            );
+        }
+        if chain_id == self.network.chain_id() {
+            anyhow::bail!(
+                "chain_id {} is the chain ID of the parent chain {}, pick a custom one",
+                chain_id,
+                self.network
+            );
+        }
+        if let Some((_, network)) = RESERVED_CHAIN_IDS.iter().find(|(id, _)| *id == chain_id) {
+            anyhow::bail!(
+                "chain_id {} is used by {}, pick a custom one",
+                chain_id,
+                network
+            );
+        }
+        if reserved_chain_ids.contains(&chain_id) {
+            anyhow::bail!(
+                "chain_id {} is reserved by the operator, pick another one",
+                chain_id
+            );
+        }
+        if chain_id < MIN_CHAIN_ID {
+            anyhow::bail!(
+                "chain_id {} is below the minimum of {}, pick a custom chain ID",
+                chain_id,
+                MIN_CHAIN_ID
+            );
+        }
+        if chain_id < SMALL_CHAIN_ID {
+            sdk::warn!(
+                "chain_id {} is small and may collide with a public network, check chainlist.org",
+                chain_id
+            );
+        }
+        Ok(())
+    }
+
+    /// List the fields that differ from [`RollupConfig::default`]
+    pub fn diff_from_default(&self) -> Vec<FieldDiff> {
+        let defaults = Self::default().fields();
+        self.fields()
+            .into_iter()
+            .zip(defaults)
+            .filter(|((_, value), (_, default))| value != default)
+            .map(|((field, value), (_, default))| FieldDiff {
+                field,
+                default,
+                value,
+            })
+            .collect()
+    }
+
+    /// Every field with addresses rendered as hex, in a fixed order
+    fn fields(&self) -> Vec<(String, serde_json::Value)> {
+        let address = |address: &[u8; 20]| format!("0x{}", hex::encode(address));
+        let mut fields = vec![
+            ("chain_id".to_string(), self.chain_id.into()),
+            (
+                "initial_chain_owner".to_string(),
+                address(&self.initial_chain_owner).into(),
+            ),
+            (
+                "validators".to_string(),
+                self.validators
+                    .iter()
+                    .map(address)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            (
+                "batch_poster_address".to_string(),
+                address(&self.batch_poster_address).into(),
+            ),
+            (
+                "batch_poster_manager".to_string(),
+                address(&self.batch_poster_manager).into(),
+            ),
+            ("is_mainnet".to_string(), self.is_mainnet.into()),
+            ("network".to_string(), self.network.to_string().into()),
+            ("dry_run".to_string(), self.dry_run.into()),
+            ("rpc_host_port".to_string(), self.rpc_host_port.into()),
+            ("node_image".to_string(), self.node_image.clone().into()),
+            (
+                "espresso_sequencer_url".to_string(),
+                self.espresso_sequencer_url.clone().into(),
+            ),
+            (
+                "espresso_namespace".to_string(),
+                self.espresso_namespace.into(),
+            ),
+            (
+                "extra_env".to_string(),
+                serde_json::json!(self.extra_env.iter().collect::<BTreeMap<_, _>>()),
+            ),
+        ];
+
+        // Unset node flags serialize as null, so every flag is always listed
+        if let Ok(serde_json::Value::Object(flags)) = serde_json::to_value(&self.node_flags) {
+            fields.extend(
+                flags
+                    .into_iter()
+                    .map(|(flag, value)| (format!("node_flags.{}", flag), value)),
+            );
+        }
+
+        fields
     }
 }
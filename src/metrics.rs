@@ -0,0 +1,145 @@
+use blueprint_sdk as sdk;
+
+use crate::docker::RollupStatus;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{Histogram, exponential_buckets};
+use prometheus_client::registry::Registry;
+use sdk::{error, info};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Port the metrics endpoint listens on when `METRICS_PORT` isn't set
+pub const DEFAULT_METRICS_PORT: u16 = 9615;
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct StatusLabels {
+    status: String,
+}
+
+/// Prometheus metrics for the rollup lifecycle
+pub struct Metrics {
+    registry: Registry,
+    rollups: Family<StatusLabels, Gauge>,
+    deploy_duration: Histogram,
+    deploy_failures: Counter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let rollups = Family::<StatusLabels, Gauge>::default();
+        // Deployments take minutes, so buckets run from 30s to a bit over an hour
+        let deploy_duration = Histogram::new(exponential_buckets(30.0, 2.0, 8));
+        let deploy_failures = Counter::default();
+
+        let mut registry = Registry::default();
+        registry.register(
+            "espresso_rollups_total",
+            "Number of rollups by status",
+            rollups.clone(),
+        );
+        registry.register(
+            "espresso_deploy_duration_seconds",
+            "Duration of rollup contract deployments",
+            deploy_duration.clone(),
+        );
+        // The text encoder appends `_total` to counters
+        registry.register(
+            "espresso_deploy_failures",
+            "Number of failed rollup contract deployments",
+            deploy_failures.clone(),
+        );
+
+        Self {
+            registry,
+            rollups,
+            deploy_duration,
+            deploy_failures,
+        }
+    }
+
+    /// Set the rollup counts from every status in the registry
+    pub fn record_rollups<'a>(&self, statuses: impl IntoIterator<Item = &'a RollupStatus>) {
+        // Clear first so statuses no rollup has anymore drop to zero
+        self.rollups.clear();
+        for status in statuses {
+            self.rollups
+                .get_or_create(&StatusLabels {
+                    status: status.name().to_string(),
+                })
+                .inc();
+        }
+    }
+
+    /// Record a finished deployment
+    pub fn record_deploy(&self, duration: Duration, succeeded: bool) {
+        self.deploy_duration.observe(duration.as_secs_f64());
+        if !succeeded {
+            self.deploy_failures.inc();
+        }
+    }
+
+    /// Render the metrics in the Prometheus text format
+    pub fn encode(&self) -> String {
+        let mut buffer = String::new();
+        // Writing to a String can't fail
+        let _ = encode(&mut buffer, &self.registry);
+        buffer
+    }
+}
+
+/// The process-wide metrics
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Serve the metrics on `METRICS_PORT` in the background
+pub fn spawn_metrics_server() -> tokio::task::JoinHandle<()> {
+    let port = std::env::var("METRICS_PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_METRICS_PORT);
+
+    tokio::spawn(async move {
+        if let Err(e) = serve(port).await {
+            error!("Metrics server on port {} failed: {}", port, e);
+        }
+    })
+}
+
+/// Answer every `GET /metrics` on `port` with the current metrics
+pub async fn serve(port: u16) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    info!("Serving metrics on port {}", port);
+
+    loop {
+        let (mut socket, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            let mut request = [0u8; 1024];
+            let Ok(read) = socket.read(&mut request).await else {
+                return;
+            };
+
+            let request = String::from_utf8_lossy(&request[..read]);
+            let response = if request.starts_with("GET /metrics ") {
+                let body = metrics().encode();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+    }
+}
@@ -0,0 +1,91 @@
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Spawn a minimal HTTP JSON-RPC server answering each call with `handler(method, params)`
+///
+/// Returns the URL the server listens on.
+pub async fn spawn_mock_rpc<F>(handler: F) -> String
+where
+    F: Fn(&str, &Value) -> Value + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let _ = serve(stream, handler.as_ref()).await;
+            });
+        }
+    });
+    format!("http://{addr}")
+}
+
+/// A URL nothing is listening on
+pub async fn unreachable_rpc() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    format!("http://{addr}")
+}
+
+async fn serve<F>(mut stream: TcpStream, handler: &F) -> std::io::Result<()>
+where
+    F: Fn(&str, &Value) -> Value,
+{
+    let mut buf = Vec::new();
+    loop {
+        let header_end = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if !read_more(&mut stream, &mut buf).await? {
+                return Ok(());
+            }
+        };
+        let headers = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+        let content_length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|len| len.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        while buf.len() < header_end + content_length {
+            if !read_more(&mut stream, &mut buf).await? {
+                return Ok(());
+            }
+        }
+
+        let request: Value = serde_json::from_slice(&buf[header_end..header_end + content_length])?;
+        buf.drain(..header_end + content_length);
+
+        let respond = |call: &Value| {
+            let method = call["method"].as_str().unwrap_or_default();
+            json!({
+                "jsonrpc": "2.0",
+                "id": call["id"],
+                "result": handler(method, &call["params"]),
+            })
+        };
+        let response = match &request {
+            Value::Array(calls) => Value::Array(calls.iter().map(respond).collect()),
+            call => respond(call),
+        };
+        let body = response.to_string();
+        let reply = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(reply.as_bytes()).await?;
+    }
+}
+
+async fn read_more(stream: &mut TcpStream, buf: &mut Vec<u8>) -> std::io::Result<bool> {
+    let mut chunk = [0u8; 4096];
+    let n = stream.read(&mut chunk).await?;
+    buf.extend_from_slice(&chunk[..n]);
+    Ok(n > 0)
+}
@@ -0,0 +1,741 @@
+use alloy::primitives::Address;
+use blueprint_sdk::tangle::extract::List;
+use blueprint_sdk::testing::tempfile;
+use espresso_raas_blueprint::deployer::config::{
+    DEFAULT_ESPRESSO_SEQUENCER_URL, DEFAULT_NITRO_NODE_TAG, NITRO_NODE_IMAGE, NODE_DATA_MOUNT,
+    checksum_address, generate_jwt_secret, validate_extra_env, validate_image_reference,
+    validate_image_tag, validate_jwt_secret, validate_rpc_url, validate_sequencer_url,
+};
+use espresso_raas_blueprint::deployer::{ConfigGenerator, NodeFlags, PruneMode};
+use espresso_raas_blueprint::{DeploymentResult, NetworkType, RollupConfig, RollupConfigParams};
+use std::collections::HashMap;
+
+fn deployment() -> DeploymentResult {
+    DeploymentResult {
+        rollup_creator_address: "0x1111111111111111111111111111111111111111".to_string(),
+        rollup_proxy_address: "0x0987654321098765432109876543210987654321".to_string(),
+        upgrade_executor_address: "0x1234567890123456789012345678901234567890".to_string(),
+        deployment_block: 123456789,
+        chain_id: 42,
+        bridge_address: "0x2222222222222222222222222222222222222222".to_string(),
+        inbox_address: "0x3333333333333333333333333333333333333333".to_string(),
+        sequencer_inbox_address: "0x4444444444444444444444444444444444444444".to_string(),
+        validator_utils_address: "0x5555555555555555555555555555555555555555".to_string(),
+        validator_wallet_creator_address: "0x6666666666666666666666666666666666666666".to_string(),
+        artifact: None,
+    }
+}
+
+fn config_generator(root: &std::path::Path) -> ConfigGenerator {
+    network_config_generator(root, NetworkType::ArbitrumSepolia)
+}
+
+fn network_config_generator(root: &std::path::Path, network: NetworkType) -> ConfigGenerator {
+    ConfigGenerator::new(
+        root.join("config"),
+        root.join("workspace"),
+        network,
+        "0x7777777777777777777777777777777777777777".to_string(),
+        deployment(),
+        "validator-key".to_string(),
+        "batch-poster-key".to_string(),
+        "http://localhost:8545".to_string(),
+    )
+}
+
+#[test]
+fn node_flags_override_template_defaults() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let flags = NodeFlags {
+        rpc_modules: Some(vec!["eth".to_string(), "debug".to_string()]),
+        prune: Some(PruneMode::Full),
+        ..Default::default()
+    };
+    config_generator(temp_dir.path())
+        .with_node_flags(flags)
+        .generate_configs()?;
+
+    let full_node = std::fs::read_to_string(temp_dir.path().join("config/full_node.json"))?;
+    let full_node: serde_json::Value = serde_json::from_str(&full_node)?;
+
+    // The template exposes `net,web3,eth,arb`; the flag replaces it
+    assert_eq!(
+        full_node["http"]["api"],
+        serde_json::json!(["eth", "debug"])
+    );
+    assert_eq!(full_node["ws"]["api"], serde_json::json!(["eth", "debug"]));
+    assert_eq!(full_node["init"]["prune"], "full");
+    // Untouched template values are preserved
+    assert_eq!(full_node["http"]["addr"], "0.0.0.0");
+    Ok(())
+}
+
+#[test]
+fn node_signs_with_the_rollup_keys() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    ConfigGenerator::new(
+        temp_dir.path().join("config"),
+        temp_dir.path().join("workspace"),
+        NetworkType::ArbitrumSepolia,
+        "0x7777777777777777777777777777777777777777".to_string(),
+        deployment(),
+        "0xaa".to_string(),
+        "bb".to_string(),
+        "http://localhost:8545".to_string(),
+    )
+    .generate_configs()?;
+
+    let full_node = std::fs::read_to_string(temp_dir.path().join("config/full_node.json"))?;
+    let full_node: serde_json::Value = serde_json::from_str(&full_node)?;
+    // Nitro reads the keys without a `0x` prefix
+    assert_eq!(
+        full_node["node"]["staker"]["parent-chain-wallet"]["private-key"],
+        "aa"
+    );
+    assert_eq!(
+        full_node["node"]["batch-poster"]["parent-chain-wallet"]["private-key"],
+        "bb"
+    );
+    Ok(())
+}
+
+#[test]
+fn chain_info_uses_the_deployed_addresses() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    config_generator(temp_dir.path()).generate_configs()?;
+
+    let chain_info = std::fs::read_to_string(temp_dir.path().join("config/l2_chain_info.json"))?;
+    let chain_info: serde_json::Value = serde_json::from_str(&chain_info)?;
+    let chain_info = &chain_info[0];
+    let deployment = deployment();
+
+    assert_eq!(chain_info["chain-config"]["chainId"], 42);
+    assert_eq!(
+        chain_info["chain-config"]["arbitrum"]["InitialChainOwner"],
+        "0x7777777777777777777777777777777777777777"
+    );
+    let rollup = &chain_info["rollup"];
+    assert_eq!(rollup["deployed-at"], 123456789);
+    assert_eq!(rollup["rollup"], deployment.rollup_proxy_address);
+    assert_eq!(rollup["bridge"], deployment.bridge_address);
+    assert_eq!(rollup["inbox"], deployment.inbox_address);
+    assert_eq!(
+        rollup["sequencer-inbox"],
+        deployment.sequencer_inbox_address
+    );
+    assert_eq!(
+        rollup["upgrade-executor"],
+        deployment.upgrade_executor_address
+    );
+    assert_eq!(
+        rollup["validator-utils"],
+        deployment.validator_utils_address
+    );
+    assert_eq!(
+        rollup["validator-wallet-creator"],
+        deployment.validator_wallet_creator_address
+    );
+    Ok(())
+}
+
+#[test]
+fn missing_address_fails_config_generation() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let generator = |deployment| {
+        ConfigGenerator::new(
+            temp_dir.path().join("config"),
+            temp_dir.path().join("workspace"),
+            NetworkType::ArbitrumSepolia,
+            "0x7777777777777777777777777777777777777777".to_string(),
+            deployment,
+            "validator-key".to_string(),
+            "batch-poster-key".to_string(),
+            "http://localhost:8545".to_string(),
+        )
+    };
+
+    let error = generator(DeploymentResult {
+        bridge_address: String::new(),
+        ..deployment()
+    })
+    .generate_configs()
+    .unwrap_err();
+    assert!(error.to_string().contains("BRIDGE_ADDRESS"));
+
+    let error = generator(DeploymentResult {
+        validator_utils_address: "0x1234".to_string(),
+        ..deployment()
+    })
+    .generate_configs()
+    .unwrap_err();
+    assert!(error.to_string().contains("VALIDATOR_UTILS_ADDRESS"));
+
+    assert!(!temp_dir.path().join("config/l2_chain_info.json").exists());
+    Ok(())
+}
+
+#[test]
+fn only_geth_devnets_run_a_geth_service() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let compose = |network: NetworkType| -> anyhow::Result<String> {
+        let root = temp_dir.path().join(network.to_string());
+        network_config_generator(&root, network).generate_configs()?;
+        Ok(std::fs::read_to_string(root.join("docker-compose.yml"))?)
+    };
+
+    let geth = compose(NetworkType::Geth)?;
+    assert!(geth.contains("\n  geth:\n"));
+    assert!(geth.contains("\n  nitro:\n"));
+
+    for network in [NetworkType::ArbitrumSepolia, NetworkType::ArbitrumMainnet] {
+        let compose = compose(network)?;
+        assert!(!compose.contains("\n  geth:\n"));
+        assert!(compose.contains("\n  nitro:\n"));
+    }
+    Ok(())
+}
+
+#[test]
+fn compose_publishes_the_rpc_on_the_host_port() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    config_generator(temp_dir.path())
+        .with_rpc_host_port(18550)
+        .generate_configs()?;
+
+    let compose = std::fs::read_to_string(temp_dir.path().join("docker-compose.yml"))?;
+    assert!(compose.contains("\"18550:8547\""));
+    assert!(!compose.contains("RPC_HOST_PORT"));
+    Ok(())
+}
+
+#[test]
+fn compose_mounts_the_rollup_data_dir() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    config_generator(temp_dir.path()).generate_configs()?;
+    let compose = std::fs::read_to_string(temp_dir.path().join("docker-compose.yml"))?;
+    assert!(compose.contains(&format!("./database:{}", NODE_DATA_MOUNT)));
+    assert!(temp_dir.path().join("database").is_dir());
+
+    // The chain data outlives the container in the rollup's own directory
+    let data_dir = temp_dir.path().join("chains").join("rollup-1");
+    config_generator(temp_dir.path())
+        .with_data_dir(&data_dir)
+        .generate_configs()?;
+    let compose = std::fs::read_to_string(temp_dir.path().join("docker-compose.yml"))?;
+    assert!(compose.contains(&format!("{}:{}", data_dir.display(), NODE_DATA_MOUNT)));
+    assert!(!compose.contains("./database"));
+    assert!(data_dir.is_dir());
+    Ok(())
+}
+
+#[test]
+fn compose_runs_the_upgraded_node_image() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    config_generator(temp_dir.path())
+        .with_node_image_tag("v3.2.1-espresso".to_string())
+        .generate_configs()?;
+
+    let compose = std::fs::read_to_string(temp_dir.path().join("docker-compose.yml"))?;
+    let image = format!("{}:v3.2.1-espresso", NITRO_NODE_IMAGE);
+    // The validation node has to match the node
+    assert_eq!(compose.matches(&image).count(), 2);
+    assert!(!compose.contains(&format!("{}:{}", NITRO_NODE_IMAGE, DEFAULT_NITRO_NODE_TAG)));
+
+    for tag in ["", "-rc1", ".hidden", "v1 && rm -rf /", "owner/image:tag"] {
+        assert!(validate_image_tag(tag).is_err(), "{tag:?}");
+    }
+    assert!(validate_image_tag(&"a".repeat(129)).is_err());
+    assert!(validate_image_tag("_v1.0").is_ok());
+    Ok(())
+}
+
+#[test]
+fn compose_runs_the_configured_node_image() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let image = "registry.internal:5000/rollups/nitro-node:v3.2.1";
+    config_generator(temp_dir.path())
+        .with_node_image(image.to_string())
+        .generate_configs()?;
+    let compose = std::fs::read_to_string(temp_dir.path().join("docker-compose.yml"))?;
+    assert_eq!(compose.matches(image).count(), 2);
+    assert!(!compose.contains(NITRO_NODE_IMAGE));
+
+    // Upgrades retag the configured image rather than switching back to the bundled one
+    let generator = config_generator(temp_dir.path())
+        .with_node_image(image.to_string())
+        .with_node_image_tag("v3.3.0".to_string());
+    assert_eq!(
+        generator.node_image()?,
+        "registry.internal:5000/rollups/nitro-node:v3.3.0"
+    );
+
+    for valid in [
+        "nitro-node",
+        "localhost:5000/nitro-node",
+        "ghcr.io/espressosystems/nitro-node:integration",
+        &format!("nitro-node@sha256:{}", "a".repeat(64)),
+    ] {
+        assert!(validate_image_reference(valid).is_ok(), "{valid:?}");
+    }
+    for invalid in [
+        "",
+        "Nitro-Node",
+        "nitro-node:",
+        "nitro node",
+        "nitro-node:v1;rm -rf /",
+    ] {
+        assert!(validate_image_reference(invalid).is_err(), "{invalid:?}");
+    }
+
+    let config = RollupConfig {
+        chain_id: 412346,
+        initial_chain_owner: [1; 20],
+        validators: vec![[2; 20]],
+        batch_poster_address: [3; 20],
+        batch_poster_manager: [4; 20],
+        node_image: Some("https://ghcr.io/nitro-node".to_string()),
+        ..Default::default()
+    };
+    let error = config.validate().unwrap_err().to_string();
+    assert!(error.starts_with("node_image"), "{error}");
+    Ok(())
+}
+
+#[test]
+fn compose_passes_extra_env_to_the_node() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    config_generator(temp_dir.path())
+        .with_extra_env(HashMap::from([("EXTRA_FLAG".to_string(), "1".to_string())]))
+        .generate_configs()?;
+    let compose = std::fs::read_to_string(temp_dir.path().join("docker-compose.yml"))?;
+    assert!(compose.contains("    environment:\n      EXTRA_FLAG: \"1\"\n"));
+    assert_eq!(compose.matches("EXTRA_FLAG").count(), 1);
+
+    // The node's keys and JWT secret stay the ones generated for the rollup
+    for name in [
+        "NITRO_NODE_BATCH__POSTER_PARENT__CHAIN__WALLET_PRIVATE__KEY",
+        "NITRO_NODE_STAKER_PARENT__CHAIN__WALLET_PRIVATE__KEY",
+        "NITRO_NODE_PARENT__CHAIN__CONNECTION_JWTSECRET",
+        "NITRO_CONF_FILE",
+        "EXTRA FLAG",
+    ] {
+        let extra_env = HashMap::from([(name.to_string(), "x".to_string())]);
+        assert!(validate_extra_env(&extra_env).is_err(), "{name}");
+        assert!(
+            config_generator(temp_dir.path())
+                .with_extra_env(extra_env.clone())
+                .generate_configs()
+                .is_err(),
+            "{name}"
+        );
+        let config = RollupConfig {
+            chain_id: 412346,
+            initial_chain_owner: [0x01; 20],
+            validators: vec![[0x02; 20]],
+            batch_poster_address: [0x03; 20],
+            batch_poster_manager: [0x04; 20],
+            extra_env,
+            ..Default::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .starts_with("extra_env: ")
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn batches_are_posted_to_the_configured_espresso_network() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let read_batch_poster = || -> anyhow::Result<serde_json::Value> {
+        let config = std::fs::read_to_string(temp_dir.path().join("config/full_node.json"))?;
+        Ok(serde_json::from_str::<serde_json::Value>(&config)?["node"]["batch-poster"].clone())
+    };
+
+    config_generator(temp_dir.path()).generate_configs()?;
+    let batch_poster = read_batch_poster()?;
+    assert_eq!(batch_poster["hotshot-url"], DEFAULT_ESPRESSO_SEQUENCER_URL);
+    assert!(batch_poster.get("espresso-namespace").is_none());
+
+    let url = "https://query.shared.espresso.example/v0";
+    config_generator(temp_dir.path())
+        .with_espresso_sequencer_url(url.to_string())
+        .with_espresso_namespace(7)
+        .generate_configs()?;
+    let batch_poster = read_batch_poster()?;
+    assert_eq!(batch_poster["hotshot-url"], url);
+    assert_eq!(batch_poster["espresso-namespace"], 7);
+
+    for invalid in [
+        "",
+        "query.espresso.network/v0",
+        "ftp://query.espresso.network",
+        "http://",
+    ] {
+        assert!(validate_sequencer_url(invalid).is_err(), "{invalid:?}");
+    }
+    let config = RollupConfig {
+        chain_id: 412346,
+        initial_chain_owner: [1; 20],
+        validators: vec![[2; 20]],
+        batch_poster_address: [3; 20],
+        batch_poster_manager: [4; 20],
+        espresso_sequencer_url: Some("query.espresso.network".to_string()),
+        ..Default::default()
+    };
+    let error = config.validate().unwrap_err().to_string();
+    assert!(error.starts_with("espresso_sequencer_url"), "{error}");
+
+    // Moving to another namespace would orphan the transactions sequenced so far
+    let moved = RollupConfig {
+        espresso_sequencer_url: Some(url.to_string()),
+        espresso_namespace: Some(8),
+        ..config.clone()
+    };
+    assert_eq!(config.immutable_changes(&moved), ["espresso_namespace"]);
+    Ok(())
+}
+
+#[test]
+fn node_follows_its_own_parent_chain_rpc() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    config_generator(temp_dir.path()).generate_configs()?;
+    let config = std::fs::read_to_string(temp_dir.path().join("config/full_node.json"))?;
+    let config = serde_json::from_str::<serde_json::Value>(&config)?;
+    assert_eq!(
+        config["parent-chain"]["connection"]["url"],
+        "http://localhost:8545"
+    );
+
+    for valid in [
+        "https://arb-sepolia.example/v2/key",
+        "wss://arb-sepolia.example/ws",
+    ] {
+        assert!(validate_rpc_url(valid).is_ok(), "{valid:?}");
+    }
+    for invalid in [
+        "",
+        "localhost:8545",
+        "ftp://localhost:8545",
+        "WEBSOCKET_RPC_URL",
+    ] {
+        assert!(validate_rpc_url(invalid).is_err(), "{invalid:?}");
+    }
+
+    let error = ConfigGenerator::new(
+        temp_dir.path().join("config"),
+        temp_dir.path().join("workspace"),
+        NetworkType::ArbitrumSepolia,
+        "0x7777777777777777777777777777777777777777".to_string(),
+        deployment(),
+        "validator-key".to_string(),
+        "batch-poster-key".to_string(),
+        "arb-sepolia.example".to_string(),
+    )
+    .generate_configs()
+    .unwrap_err()
+    .to_string();
+    assert!(error.starts_with("Parent chain RPC of the node"), "{error}");
+    Ok(())
+}
+
+#[test]
+fn conflicting_node_flags_are_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let flags = NodeFlags {
+        archive: Some(true),
+        prune: Some(PruneMode::Minimal),
+        ..Default::default()
+    };
+    let result = config_generator(temp_dir.path())
+        .with_node_flags(flags)
+        .generate_configs();
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn diff_from_default_lists_only_overridden_fields() {
+    let config = RollupConfig {
+        chain_id: 42,
+        validators: vec![[0x11; 20], [0x22; 20]],
+        ..Default::default()
+    };
+
+    let diff = config.diff_from_default();
+    let fields: Vec<&str> = diff.iter().map(|diff| diff.field.as_str()).collect();
+    assert_eq!(fields, ["chain_id", "validators"]);
+
+    assert_eq!(diff[0].default, serde_json::json!(0));
+    assert_eq!(diff[0].value, serde_json::json!(42));
+    assert_eq!(diff[1].default, serde_json::json!([]));
+    assert_eq!(
+        diff[1].value,
+        serde_json::json!([
+            "0x1111111111111111111111111111111111111111",
+            "0x2222222222222222222222222222222222222222"
+        ])
+    );
+
+    assert!(RollupConfig::default().diff_from_default().is_empty());
+}
+
+#[test]
+fn validate_names_the_offending_field() {
+    let valid = RollupConfig {
+        chain_id: 412346,
+        initial_chain_owner: [0x01; 20],
+        validators: vec![[0x02; 20]],
+        batch_poster_address: [0x03; 20],
+        batch_poster_manager: [0x04; 20],
+        ..Default::default()
+    };
+    assert!(valid.validate().is_ok());
+
+    let error = |config: RollupConfig| config.validate().unwrap_err().to_string();
+    assert_eq!(
+        error(RollupConfig {
+            batch_poster_manager: [0; 20],
+            ..valid.clone()
+        }),
+        "batch_poster_manager must be a non-zero address"
+    );
+    assert_eq!(
+        error(RollupConfig {
+            validators: Vec::new(),
+            ..valid.clone()
+        }),
+        "validators must contain at least one address"
+    );
+    assert_eq!(
+        error(RollupConfig {
+            validators: vec![[0x02; 20], [0; 20]],
+            ..valid
+        }),
+        "validators[1] must be a non-zero address"
+    );
+}
+
+#[test]
+fn mainnet_deployments_need_the_operators_consent() {
+    let sepolia = RollupConfig {
+        network: NetworkType::ArbitrumSepolia,
+        ..Default::default()
+    };
+    assert!(sepolia.check_mainnet_allowed(false).is_ok());
+
+    for mainnet in [
+        RollupConfig {
+            network: NetworkType::ArbitrumMainnet,
+            ..Default::default()
+        },
+        RollupConfig {
+            is_mainnet: true,
+            ..sepolia
+        },
+    ] {
+        assert!(mainnet.targets_mainnet());
+        assert!(mainnet.check_mainnet_allowed(true).is_ok());
+        let error = mainnet
+            .check_mainnet_allowed(false)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("ESPRESSO_ALLOW_MAINNET=true"), "{error}");
+    }
+}
+
+#[test]
+fn chain_id_must_be_custom() {
+    let valid = RollupConfig {
+        chain_id: 412346,
+        initial_chain_owner: [0x01; 20],
+        validators: vec![[0x02; 20]],
+        batch_poster_address: [0x03; 20],
+        batch_poster_manager: [0x04; 20],
+        network: NetworkType::ArbitrumSepolia,
+        ..Default::default()
+    };
+    assert!(valid.validate().is_ok());
+    // Small IDs only warn
+    assert!(
+        RollupConfig {
+            chain_id: 4242,
+            ..valid.clone()
+        }
+        .validate()
+        .is_ok()
+    );
+
+    let error = |chain_id: u64, reserved: &[u64]| {
+        RollupConfig {
+            chain_id,
+            ..valid.clone()
+        }
+        .validate_reserving(reserved)
+        .unwrap_err()
+        .to_string()
+    };
+    assert_eq!(
+        error(0, &[]),
+        "chain_id must be set to a custom chain ID of at least 1000"
+    );
+    assert_eq!(
+        error(421614, &[]),
+        "chain_id 421614 is the chain ID of the parent chain arbSepolia, pick a custom one"
+    );
+    assert_eq!(
+        error(42161, &[]),
+        "chain_id 42161 is used by Arbitrum One, pick a custom one"
+    );
+    assert_eq!(
+        error(412346, &[412346]),
+        "chain_id 412346 is reserved by the operator, pick another one"
+    );
+    assert_eq!(
+        error(42, &[]),
+        "chain_id 42 is below the minimum of 1000, pick a custom chain ID"
+    );
+}
+
+#[test]
+fn validator_addresses_must_be_20_bytes_of_hex() -> anyhow::Result<()> {
+    let params = RollupConfigParams {
+        validators: List(vec![[0x02; 20], [0xab; 20]]),
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&params)?;
+    let parsed: RollupConfigParams = serde_json::from_value(json.clone())?;
+    assert_eq!(parsed.validators.0, params.validators.0);
+
+    // Prefixed addresses, as emitted by most Ethereum tooling, are accepted too
+    let mut prefixed = json.clone();
+    prefixed["validators"] = serde_json::json!([
+        format!("0x{}", "02".repeat(20)),
+        format!("0X{}", "ab".repeat(20)),
+    ]);
+    let parsed: RollupConfigParams = serde_json::from_value(prefixed)?;
+    assert_eq!(parsed.validators.0, params.validators.0);
+
+    let error = |validators: serde_json::Value| {
+        let mut json = json.clone();
+        json["validators"] = validators;
+        serde_json::from_value::<RollupConfigParams>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    let valid = "02".repeat(20);
+    assert_eq!(
+        error(serde_json::json!([valid, "0202"])),
+        "address at index 1 must be 20 bytes, got 2"
+    );
+    assert!(
+        error(serde_json::json!([format!("{}zz", "02".repeat(19))]))
+            .starts_with("address at index 0 is not valid hex")
+    );
+    Ok(())
+}
+
+#[test]
+fn op_stack_networks_round_trip() -> anyhow::Result<()> {
+    for (network, parent_chain_id, name) in [
+        (NetworkType::BaseSepolia, 84532, "baseSepolia"),
+        (NetworkType::OptimismSepolia, 11155420, "opSepolia"),
+    ] {
+        let json = serde_json::to_string(&network)?;
+        let parsed: NetworkType = serde_json::from_str(&json)?;
+        assert_eq!(parsed.parent_chain_id(), parent_chain_id);
+        assert_eq!(parsed.to_string(), name);
+        assert_eq!(parsed.hardhat_network_name(), name);
+    }
+    Ok(())
+}
+
+#[test]
+fn addresses_are_checksummed_per_eip_55() -> anyhow::Result<()> {
+    // Test vectors from the EIP-55 specification
+    for expected in [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ] {
+        let mut address = [0; 20];
+        hex::decode_to_slice(expected[2..].to_lowercase(), &mut address)?;
+        assert_eq!(checksum_address(&address), expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn addresses_convert_to_and_from_alloy() -> anyhow::Result<()> {
+    let owner: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse()?;
+    let validator: Address = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359".parse()?;
+    let poster: Address = "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB".parse()?;
+
+    let config = RollupConfig::default()
+        .with_owner(owner)
+        .with_validators([validator, owner])
+        .with_batch_poster(poster)
+        .with_batch_poster_manager(owner);
+    assert_eq!(config.initial_chain_owner, <[u8; 20]>::from(owner));
+    assert_eq!(config.owner_address(), owner);
+    assert_eq!(config.validator_addresses(), vec![validator, owner]);
+    assert_eq!(config.batch_poster(), poster);
+    assert_eq!(config.batch_poster_manager_address(), owner);
+
+    // Addresses keep serializing as byte arrays
+    let json = serde_json::to_value(&config)?;
+    assert_eq!(
+        json["initial_chain_owner"],
+        serde_json::json!(<[u8; 20]>::from(owner))
+    );
+    Ok(())
+}
+
+#[test]
+fn every_rollup_gets_its_own_jwt_secret() -> anyhow::Result<()> {
+    let first = generate_jwt_secret();
+    let second = generate_jwt_secret();
+    assert_ne!(first, second);
+    for secret in [&first, &second] {
+        assert_eq!(secret.len(), 64);
+        assert_eq!(hex::decode(secret)?.len(), 32);
+        validate_jwt_secret(secret)?;
+    }
+
+    let temp_dir = tempfile::TempDir::new()?;
+    let first_dir = temp_dir.path().join("first");
+    let second_dir = temp_dir.path().join("second");
+    config_generator(&first_dir).generate_configs()?;
+    config_generator(&second_dir).generate_configs()?;
+    let first = std::fs::read_to_string(first_dir.join("config/val_jwt.hex"))?;
+    let second = std::fs::read_to_string(second_dir.join("config/val_jwt.hex"))?;
+    assert_ne!(first, second);
+    validate_jwt_secret(&first)?;
+    Ok(())
+}
+
+#[test]
+fn provided_jwt_secret_must_be_32_bytes_of_hex() -> anyhow::Result<()> {
+    let secret = format!("0x{}", "ab".repeat(32));
+    assert!(validate_jwt_secret(&secret).is_ok());
+    assert!(validate_jwt_secret(&"ab".repeat(31)).is_err());
+    assert!(validate_jwt_secret(&"zz".repeat(32)).is_err());
+
+    let temp_dir = tempfile::TempDir::new()?;
+    config_generator(temp_dir.path())
+        .with_jwt_secret(secret.clone())
+        .generate_configs()?;
+    let written = std::fs::read_to_string(temp_dir.path().join("config/val_jwt.hex"))?;
+    assert_eq!(written, secret);
+
+    let result = config_generator(temp_dir.path())
+        .with_jwt_secret("not-a-secret".to_string())
+        .generate_configs();
+    assert!(result.is_err());
+    Ok(())
+}
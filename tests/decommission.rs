@@ -0,0 +1,42 @@
+use blueprint_sdk::testing::tempfile;
+use espresso_raas_blueprint::docker::decommission::archive_rollup_data;
+use std::fs;
+use std::process::Command;
+
+#[tokio::test]
+async fn decommission_archives_config_and_chain_data() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let rollup_root = temp_dir.path().join("docker-rollup-1");
+    let archive_dir = temp_dir.path().join("archive");
+    fs::create_dir_all(rollup_root.join("workspace"))?;
+    fs::create_dir_all(rollup_root.join("config"))?;
+    fs::create_dir_all(rollup_root.join("database/nitro"))?;
+    fs::write(rollup_root.join("config/full_node.json"), "{}")?;
+    fs::write(rollup_root.join("database/nitro/LOCK"), "")?;
+
+    let archive = archive_rollup_data(&rollup_root, &archive_dir, "rollup-1").await?;
+    assert!(archive.starts_with(&archive_dir));
+    assert!(fs::metadata(&archive)?.len() > 0);
+
+    let listing = Command::new("tar").arg("-tzf").arg(&archive).output()?;
+    let listing = String::from_utf8(listing.stdout)?;
+    assert!(listing.contains("config/full_node.json"));
+    assert!(listing.contains("database/nitro/LOCK"));
+    // The workspace only holds the contracts checkout and isn't archived
+    assert!(!listing.contains("workspace"));
+    assert!(rollup_root.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn archive_requires_rollup_data() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let rollup_root = temp_dir.path().join("docker-rollup-2");
+    fs::create_dir_all(rollup_root.join("config"))?;
+
+    let result =
+        archive_rollup_data(&rollup_root, &temp_dir.path().join("archive"), "rollup-2").await;
+    assert!(result.is_err());
+    assert!(rollup_root.exists());
+    Ok(())
+}
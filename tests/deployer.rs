@@ -0,0 +1,522 @@
+use blueprint_sdk::testing::tempfile;
+use espresso_raas_blueprint::deployer::rollup::{
+    DEFAULT_MIN_DEPLOYER_BALANCE, DEPLOYMENT_ARTIFACT_FILE_NAME, DeployCancelled, DeployState,
+    DeploymentConfig, EspressoDeployments, RetryPolicy, RollupDeployer, check_balance,
+    command_error, extract_deployment_block, extract_rollup_creator_address,
+    extract_rollup_proxy_address, is_retryable, strip_ansi, transaction_hash, validate_fees,
+    wait_for_confirmations,
+};
+use espresso_raas_blueprint::{NetworkType, RollupConfig};
+use std::path::Path;
+use std::time::Duration;
+
+/// `npx hardhat run scripts/deployment.ts` output, with hardhat's color codes
+const DEPLOYMENT_OUTPUT: &str = "\
+Compiled 1 Solidity file successfully (evm target: paris).
+\x1b[33mDeploying contracts with maxDataSize: 117964\x1b[0m
+Bridge deployed to: 0x5FbDB2315678afecb367f032d93F642f64180aa3
+SequencerInbox deployed to: 0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512
+\x1b[32mRollupCreator deployed to: \x1b[1m0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0\x1b[0m
+Verification skipped on local network
+";
+
+/// `npx hardhat run scripts/createEthRollup.ts` output
+const ROLLUP_OUTPUT: &str = "\
+Calling createRollup to generate a new rollup ...
+\x1b[32mCongratulations! 🎉🎉🎉 All DONE! Here's your addresses:\x1b[0m
+RollupProxy Contract created at address: 0xCf7Ed3AccA5a467e9e704C703E8D87F634fB0Fc9
+Wait a minute before starting the contract verification
+Inbox (proxy) Contract created at address: 0xDc64a140Aa3E981100a9becA4E685f962f0cF6C9
+All deployed at block number: 1234567
+";
+
+#[test]
+fn parses_addresses_through_ansi_codes() -> anyhow::Result<()> {
+    assert_eq!(
+        extract_rollup_creator_address(DEPLOYMENT_OUTPUT)?,
+        "0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0"
+    );
+    assert_eq!(
+        extract_rollup_proxy_address(ROLLUP_OUTPUT)?,
+        "0xCf7Ed3AccA5a467e9e704C703E8D87F634fB0Fc9"
+    );
+    assert_eq!(extract_deployment_block(ROLLUP_OUTPUT)?, 1234567);
+    Ok(())
+}
+
+#[test]
+fn strips_ansi_escapes() {
+    assert_eq!(
+        strip_ansi("\x1b[32mok\x1b[0m \x1b[1;31mdone\x1b[0m\x1b[2K"),
+        "ok done"
+    );
+}
+
+#[test]
+fn missing_match_reports_output_tail() {
+    let output: String = (1..=30).map(|line| format!("line {}\n", line)).collect();
+
+    let error = extract_rollup_proxy_address(&output)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("rollup proxy address"));
+    assert!(error.contains("line 11"));
+    assert!(error.contains("line 30"));
+    assert!(!error.contains("line 10\n"));
+
+    // A missing block number is an error rather than block 0
+    assert!(extract_deployment_block(DEPLOYMENT_OUTPUT).is_err());
+}
+
+#[test]
+fn reads_deployments_file() -> anyhow::Result<()> {
+    let fixture =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/espresso-deployments.json");
+    let deployments = EspressoDeployments::load(&fixture)?.expect("fixture exists");
+
+    assert_eq!(deployments, EspressoDeployments {
+        rollup_creator: Some("0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0".to_string()),
+        upgrade_executor: Some("0xDc64a140Aa3E981100a9becA4E685f962f0cF6C9".to_string()),
+        rollup_proxy: Some("0xCf7Ed3AccA5a467e9e704C703E8D87F634fB0Fc9".to_string()),
+        deployment_block: Some(1234567),
+        bridge: Some("0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string()),
+        inbox: Some("0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512".to_string()),
+        sequencer_inbox: Some("0xCf7Ed3AccA5a467e9e704C703E8D87F634fB0Fc8".to_string()),
+        validator_utils: Some("0xa513E6E4b8f2a923D98304ec87F64353C4D5C853".to_string()),
+        validator_wallet_creator: Some("0x2279B7A0a67DB372996a5FaB50D91eAA73d2eBe6".to_string()),
+    });
+
+    let result = deployments
+        .clone()
+        .into_deployment_result("0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0".to_string(), 42)?;
+    assert_eq!(result.chain_id, 42);
+    assert_eq!(result.bridge_address, deployments.bridge.unwrap());
+    assert_eq!(
+        result.validator_wallet_creator_address,
+        deployments.validator_wallet_creator.unwrap()
+    );
+    Ok(())
+}
+
+#[test]
+fn falls_back_to_script_output() -> anyhow::Result<()> {
+    let missing = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/missing.json");
+    assert_eq!(EspressoDeployments::load(&missing)?, None);
+
+    let deployments =
+        EspressoDeployments::from_output(&format!("{}{}", DEPLOYMENT_OUTPUT, ROLLUP_OUTPUT));
+    assert_eq!(
+        deployments.rollup_creator.as_deref(),
+        Some("0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0")
+    );
+    assert_eq!(
+        deployments.rollup_proxy.as_deref(),
+        Some("0xCf7Ed3AccA5a467e9e704C703E8D87F634fB0Fc9")
+    );
+    assert_eq!(deployments.deployment_block, Some(1234567));
+    // `Inbox` doesn't match inside `SequencerInbox`
+    assert_eq!(
+        deployments.inbox.as_deref(),
+        Some("0xDc64a140Aa3E981100a9becA4E685f962f0cF6C9")
+    );
+    assert_eq!(deployments.sequencer_inbox, None);
+
+    // Missing core contracts fail the deployment instead of ending up empty
+    let deployments = EspressoDeployments {
+        upgrade_executor: Some("0xDc64a140Aa3E981100a9becA4E685f962f0cF6C9".to_string()),
+        ..deployments
+    };
+    let error = deployments
+        .into_deployment_result("0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0".to_string(), 42)
+        .unwrap_err();
+    assert!(error.to_string().contains("bridge address"));
+    Ok(())
+}
+
+#[test]
+fn deployment_artifact_is_kept_in_the_config_dir() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let config = RollupConfig::default();
+    let deployment =
+        DeploymentConfig::new(&config, "key", "api-key", temp_dir.path().join("workspace"));
+    let deployer = RollupDeployer::new(deployment);
+    let config_dir = temp_dir.path().join("config");
+
+    // Deployments that only printed their addresses have nothing to keep
+    assert_eq!(deployer.copy_deployment_artifact(&config_dir)?, None);
+
+    let artifact = deployer.deployment_artifact_path();
+    assert!(artifact.ends_with(format!(
+        "nitro-contracts/espresso-deployments/{}.json",
+        config.network.hardhat_network_name()
+    )));
+    std::fs::create_dir_all(artifact.parent().unwrap())?;
+    let fixture =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/espresso-deployments.json");
+    std::fs::copy(&fixture, &artifact)?;
+
+    let copy = deployer.copy_deployment_artifact(&config_dir)?;
+    assert_eq!(copy, Some(config_dir.join(DEPLOYMENT_ARTIFACT_FILE_NAME)));
+    assert_eq!(
+        std::fs::read_to_string(copy.unwrap())?,
+        std::fs::read_to_string(&fixture)?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn retried_deployments_resume_after_the_completed_phases() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let workspace_dir = temp_dir.path().join("workspace");
+    let mut deployment = DeploymentConfig::new(
+        &RollupConfig::default(),
+        "key",
+        "api-key",
+        workspace_dir.clone(),
+    );
+    deployment.validators = vec![[2; 20]];
+    deployment.min_deployer_balance = Default::default();
+    // Cloning this would fail, so only a resumed deployment gets past `prepare`
+    deployment.contracts_repo = temp_dir.path().join("missing-repo").display().to_string();
+
+    let deployer = RollupDeployer::new(deployment.clone());
+    std::fs::create_dir_all(workspace_dir.join("nitro-contracts"))?;
+    DeployState {
+        repo_cloned: true,
+        contracts_built: true,
+        rollup_creator_address: Some("0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0".to_string()),
+        ..DeployState::for_config(&deployment)
+    }
+    .save(&deployer.deploy_state_path())?;
+
+    deployer.prepare().await?;
+    deployer.build().await?;
+    assert_eq!(
+        deployer.deploy_creator().await?,
+        "0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0"
+    );
+
+    // Phases recorded for other contracts don't count
+    let mut other_ref = deployment.clone();
+    other_ref.contracts_ref = "v2.1.0".to_string();
+    assert_eq!(
+        RollupDeployer::new(other_ref.clone()).deploy_state(),
+        DeployState::for_config(&other_ref)
+    );
+
+    // Starting over forgets the phases and the clone
+    deployment.force_clean = true;
+    let deployer = RollupDeployer::new(deployment);
+    assert!(deployer.prepare().await.is_err());
+    assert!(!deployer.deploy_state_path().exists());
+    assert!(!workspace_dir.join("nitro-contracts").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn mainnet_deployment_requires_confirmation() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let workspace_dir = temp_dir.path().join("workspace");
+    let config = RollupConfig {
+        network: NetworkType::ArbitrumMainnet,
+        ..Default::default()
+    };
+    assert_eq!(config.network.hardhat_network_name(), "arb1");
+
+    let deployment = DeploymentConfig::new(&config, "key", "api-key", workspace_dir.clone());
+    let error = RollupDeployer::new(deployment)
+        .deploy()
+        .await
+        .expect_err("mainnet deployment without is_mainnet must fail");
+    assert!(error.to_string().contains("is_mainnet"));
+    // Nothing was cloned or built
+    assert!(!workspace_dir.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn deployment_without_validators_fails_cleanly() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let workspace_dir = temp_dir.path().join("workspace");
+    let mut deployment = DeploymentConfig::new(
+        &RollupConfig::default(),
+        "key",
+        "api-key",
+        workspace_dir.clone(),
+    );
+    deployment.validators.clear();
+
+    let error = RollupDeployer::new(deployment)
+        .deploy()
+        .await
+        .expect_err("deployment without validators must fail");
+    assert_eq!(error.to_string(), "at least one validator is required");
+    assert!(!workspace_dir.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancelled_deployments_stop_before_the_next_phase() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let workspace_dir = temp_dir.path().join("workspace");
+    let deployment = DeploymentConfig::new(
+        &RollupConfig::default(),
+        "key",
+        "api-key",
+        workspace_dir.clone(),
+    );
+    let (cancel, cancelled) = tokio::sync::watch::channel(false);
+    cancel.send_replace(true);
+
+    let error = RollupDeployer::new(deployment)
+        .with_cancellation(cancelled)
+        .deploy()
+        .await
+        .expect_err("a cancelled deployment must fail");
+    assert!(error.is::<DeployCancelled>());
+    assert!(!workspace_dir.exists());
+    Ok(())
+}
+
+#[test]
+fn only_transient_script_errors_are_retried() {
+    assert!(is_retryable("Error: ETIMEDOUT connecting to RPC"));
+    assert!(is_retryable("ProviderError: nonce too low"));
+    assert!(is_retryable("HTTP 429 Too Many Requests"));
+    assert!(!is_retryable("Error: execution reverted: not owner"));
+    assert!(!is_retryable("insufficient funds for gas * price + value"));
+    // A revert wins over the network noise around it
+    assert!(!is_retryable(
+        "transaction reverted after request timed out"
+    ));
+    assert!(!is_retryable("HardhatError: HH700: Artifact not found"));
+
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.max_attempts, 3);
+    assert_eq!(policy.delay(1), Duration::from_secs(5));
+    assert_eq!(policy.delay(2), Duration::from_secs(10));
+    assert_eq!(policy.delay(3), Duration::from_secs(20));
+}
+
+#[tokio::test]
+async fn invalid_tee_verifier_override_fails_before_cloning() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let workspace_dir = temp_dir.path().join("workspace");
+    let config = RollupConfig {
+        validators: vec![[0x02; 20]],
+        ..Default::default()
+    };
+    let mut deployment = DeploymentConfig::new(&config, "key", "api-key", workspace_dir.clone());
+
+    deployment.tee_verifier_address =
+        Some("0x8354db765810dF8F24f1477B06e91E5b17a408bA".to_string());
+    assert_eq!(
+        deployment.tee_verifier_address()?,
+        "0x8354db765810dF8F24f1477B06e91E5b17a408bA"
+    );
+
+    deployment.tee_verifier_address = Some("0x1234".to_string());
+    let error = RollupDeployer::new(deployment)
+        .deploy()
+        .await
+        .expect_err("an invalid TEE verifier must fail");
+    assert!(error.to_string().contains("Invalid TEE verifier address"));
+    assert!(!workspace_dir.exists());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn failed_commands_report_the_end_of_their_output() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let stdout: String = (1..=30)
+        .map(|line| format!("build line {}\n", line))
+        .collect();
+    let output = std::process::Output {
+        // Exit code 1, in the wait status encoding
+        status: std::process::ExitStatus::from_raw(1 << 8),
+        stdout: stdout.into_bytes(),
+        stderr: b"error TS2304: Cannot find name 'foo'\n".to_vec(),
+    };
+
+    let error = command_error("yarn build:all", &output).to_string();
+
+    assert!(
+        error.starts_with("Command 'yarn build:all' failed with exit status: 1"),
+        "{error}"
+    );
+    assert!(error.contains("Last 20 lines of stdout"), "{error}");
+    assert!(error.contains("build line 30") && !error.contains("build line 10\n"));
+    assert!(error.contains("Cannot find name 'foo'"), "{error}");
+}
+
+#[tokio::test]
+async fn deployment_block_waits_for_its_confirmations() -> anyhow::Result<()> {
+    // The head advances by one block per poll, with an RPC hiccup in between
+    let polls = std::cell::Cell::new(0);
+    let head = || {
+        polls.set(polls.get() + 1);
+        let poll = polls.get();
+        async move {
+            match poll {
+                2 => Err(anyhow::anyhow!("connection reset")),
+                poll => Ok(99 + poll),
+            }
+        }
+    };
+    wait_for_confirmations(100, 5, Duration::from_secs(5), Duration::ZERO, head).await?;
+    // Blocks 100 to 104 make five confirmations
+    assert_eq!(polls.get(), 5);
+
+    let stuck = || async { Ok(101) };
+    let error = wait_for_confirmations(
+        100,
+        5,
+        Duration::from_millis(20),
+        Duration::from_millis(5),
+        stuck,
+    )
+    .await
+    .unwrap_err()
+    .to_string();
+    assert!(error.contains("latest block: 101"), "{error}");
+    Ok(())
+}
+
+#[test]
+fn env_files_need_every_required_key_and_no_unknown_ones() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("deploy.env");
+    let env = "\
+DEPLOYER_PRIVATE_KEY=0xabc
+ARBISCAN_API_KEY=key
+CHAIN_ID=412346
+NETWORK=arbSepolia
+INITIAL_CHAIN_OWNER=0x1111111111111111111111111111111111111111
+VALIDATORS=0x2222222222222222222222222222222222222222,0x3333333333333333333333333333333333333333
+BATCH_POSTER_ADDRESS=0x4444444444444444444444444444444444444444
+BATCH_POSTER_MANAGER=0x5555555555555555555555555555555555555555
+WORKSPACE_DIR=/tmp/rollup
+COMMAND_TIMEOUT_SECS=60
+";
+    std::fs::write(&path, env)?;
+    let config = DeploymentConfig::from_env_file(&path)?;
+    assert_eq!(config.chain_id, 412346);
+    assert!(matches!(config.network, NetworkType::ArbitrumSepolia));
+    assert_eq!(config.rpc_url, NetworkType::ArbitrumSepolia.rpc_url());
+    assert_eq!(config.initial_chain_owner, [0x11; 20]);
+    assert_eq!(config.validators, vec![[0x22; 20], [0x33; 20]]);
+    assert_eq!(config.batch_poster_manager, [0x55; 20]);
+    assert_eq!(config.command_timeout, Duration::from_secs(60));
+    assert!(!config.dry_run);
+
+    std::fs::write(&path, format!("{env}CHAIN_OWNER=0x1\n"))?;
+    let error = DeploymentConfig::from_env_file(&path)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("Unknown keys") && error.contains("CHAIN_OWNER"),
+        "{error}"
+    );
+
+    std::fs::write(&path, env.replace("VALIDATORS=", "# VALIDATORS="))?;
+    let error = DeploymentConfig::from_env_file(&path)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("Missing keys") && error.contains("VALIDATORS"),
+        "{error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn fee_caps_must_be_nonzero_with_the_priority_fee_below_the_max() -> anyhow::Result<()> {
+    assert!(validate_fees(None, None).is_ok());
+    assert!(validate_fees(Some(50_000_000_000), None).is_ok());
+    assert!(validate_fees(Some(50_000_000_000), Some(2_000_000_000)).is_ok());
+    assert!(validate_fees(Some(0), None).is_err());
+    assert!(validate_fees(None, Some(0)).is_err());
+    let error = validate_fees(Some(1_000), Some(2_000))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("exceeds"), "{error}");
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("deploy.env");
+    let env = "\
+DEPLOYER_PRIVATE_KEY=0xabc
+ARBISCAN_API_KEY=key
+CHAIN_ID=412346
+NETWORK=arbSepolia
+INITIAL_CHAIN_OWNER=0x1111111111111111111111111111111111111111
+VALIDATORS=0x2222222222222222222222222222222222222222
+BATCH_POSTER_ADDRESS=0x4444444444444444444444444444444444444444
+BATCH_POSTER_MANAGER=0x5555555555555555555555555555555555555555
+WORKSPACE_DIR=/tmp/rollup
+";
+    std::fs::write(&path, env)?;
+    let config = DeploymentConfig::from_env_file(&path)?;
+    assert_eq!(config.max_fee_per_gas, None);
+    assert_eq!(config.max_priority_fee_per_gas, None);
+
+    std::fs::write(
+        &path,
+        format!(
+            "{env}ESPRESSO_MAX_FEE_PER_GAS_WEI=50000000000\nESPRESSO_MAX_PRIORITY_FEE_PER_GAS_WEI=2000000000\n"
+        ),
+    )?;
+    let config = DeploymentConfig::from_env_file(&path)?;
+    assert_eq!(config.max_fee_per_gas, Some(50_000_000_000));
+    assert_eq!(config.max_priority_fee_per_gas, Some(2_000_000_000));
+
+    std::fs::write(
+        &path,
+        format!("{env}ESPRESSO_MAX_FEE_PER_GAS_WEI=1\nESPRESSO_MAX_PRIORITY_FEE_PER_GAS_WEI=2\n"),
+    )?;
+    assert!(DeploymentConfig::from_env_file(&path).is_err());
+    Ok(())
+}
+
+#[test]
+fn transaction_hashes_are_picked_out_of_script_output() {
+    let hash = "0x8c0a5b3e1f9d2c4b6a7e8f9012345678abcdef0123456789abcdef0123456789";
+    assert_eq!(
+        transaction_hash(&format!("\x1b[32mtx hash:\x1b[0m {hash}")),
+        Some(hash.to_string())
+    );
+    assert_eq!(
+        transaction_hash(&format!("Transaction sent: {hash} (nonce 4)")),
+        Some(hash.to_string())
+    );
+    // Addresses are shorter than hashes, and hashes need a transaction to refer to
+    assert_eq!(
+        transaction_hash("Bridge deployed to: 0x5FbDB2315678afecb367f032d93F642f64180aa3"),
+        None
+    );
+    assert_eq!(transaction_hash(&format!("wasmModuleRoot: {hash}")), None);
+}
+
+#[test]
+fn unfunded_deployers_are_named_with_their_balance() {
+    let deployer = alloy::primitives::Address::repeat_byte(0xaa);
+    assert!(
+        check_balance(
+            deployer,
+            DEFAULT_MIN_DEPLOYER_BALANCE,
+            DEFAULT_MIN_DEPLOYER_BALANCE
+        )
+        .is_ok()
+    );
+
+    let balance = alloy::primitives::U256::from(10_000_000_000_000_000u64);
+    let error = check_balance(deployer, balance, DEFAULT_MIN_DEPLOYER_BALANCE)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains(&deployer.to_string()), "{error}");
+    assert!(
+        error.contains("has 0.010000000000000000 ETH, needs at least 0.050000000000000000 ETH"),
+        "{error}"
+    );
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use alloy::primitives::Address;
+use blueprint_sdk::testing::tempfile;
+use espresso_raas_blueprint::deployer::MockDeployer;
+use espresso_raas_blueprint::diagnostics::{diagnose_network, preflight};
+use espresso_raas_blueprint::docker::MockRuntime;
+use espresso_raas_blueprint::{NetworkType, RollupManager};
+use serde_json::{Value, json};
+
+fn passed(report: &espresso_raas_blueprint::diagnostics::NetworkDiagnostics, name: &str) -> bool {
+    report.check(name).unwrap().passed
+}
+
+#[tokio::test]
+async fn diagnostics_report_each_check() {
+    // Arbitrum Sepolia chain id, contracts present, unfunded deployer
+    let rpc_url = common::spawn_mock_rpc(|method, _params: &Value| match method {
+        "eth_chainId" => json!("0x66eee"),
+        "eth_getCode" => json!("0x6080604052"),
+        "eth_getBalance" => json!("0x0"),
+        _ => Value::Null,
+    })
+    .await;
+
+    let report = diagnose_network(
+        NetworkType::ArbitrumSepolia,
+        Some(rpc_url.clone()),
+        Some(Address::repeat_byte(0x11)),
+    )
+    .await;
+
+    assert_eq!(report.rpc_url, rpc_url);
+    assert!(passed(&report, "rpc_reachable"));
+    assert!(passed(&report, "chain_id"));
+    assert!(passed(&report, "tee_verifier"));
+    assert!(passed(&report, "light_client"));
+    assert!(!passed(&report, "deployer_funded"));
+    assert!(!report.passed());
+}
+
+#[tokio::test]
+async fn diagnostics_detect_wrong_chain_and_missing_contracts() {
+    let rpc_url = common::spawn_mock_rpc(|method, _params: &Value| match method {
+        "eth_chainId" => json!("0x1"),
+        "eth_getCode" => json!("0x"),
+        "eth_getBalance" => json!("0xde0b6b3a7640000"),
+        _ => Value::Null,
+    })
+    .await;
+
+    let report = diagnose_network(
+        NetworkType::ArbitrumSepolia,
+        Some(rpc_url),
+        Some(Address::repeat_byte(0x11)),
+    )
+    .await;
+
+    assert!(passed(&report, "rpc_reachable"));
+    assert!(!passed(&report, "chain_id"));
+    assert!(!passed(&report, "tee_verifier"));
+    assert!(!passed(&report, "light_client"));
+    assert!(passed(&report, "deployer_funded"));
+}
+
+#[tokio::test]
+async fn diagnostics_fail_when_rpc_unreachable() {
+    let rpc_url = common::unreachable_rpc().await;
+
+    let report = diagnose_network(NetworkType::Geth, Some(rpc_url), None).await;
+
+    assert_eq!(report.checks.len(), 5);
+    assert!(report.checks.iter().all(|check| !check.passed));
+}
+
+#[tokio::test]
+async fn preflight_lists_every_missing_tool() -> anyhow::Result<()> {
+    // Cargo is on the PATH of whatever runs the tests
+    let report = preflight(&["cargo", "missing-tool-a", "missing-tool-b"], false).await;
+    let cargo = report.check("cargo").unwrap();
+    assert!(cargo.passed);
+    assert!(cargo.detail.starts_with("cargo "));
+    assert_eq!(report.failures(), [
+        "missing-tool-a: missing-tool-a not found on PATH",
+        "missing-tool-b: missing-tool-b not found on PATH",
+    ]);
+    assert!(report.check("docker").is_none());
+    assert!(!report.passed());
+
+    // A configured deployer and runtime need nothing from the host
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf())
+        .with_deployer(Box::new(MockDeployer::default()))
+        .with_runtime(|info| Box::new(MockRuntime::default().for_rollup(info)));
+    let report = manager.preflight().await;
+    assert!(report.checks.is_empty());
+    assert!(report.passed());
+    Ok(())
+}
@@ -0,0 +1,1739 @@
+mod common;
+
+use blueprint_sdk::tangle::extract::List;
+use blueprint_sdk::testing::tempfile;
+use espresso_raas_blueprint::deployer::MockDeployer;
+use espresso_raas_blueprint::deployer::RollupKey;
+use espresso_raas_blueprint::deployer::rollup::RetryPolicy;
+use espresso_raas_blueprint::docker::container::{
+    Adoption, ExistingResource, OPTIONAL_LABEL, PROJECT_LABEL, ServiceState, compose_parse_error,
+    connect_with_retry, plan_adoption, project_compose_config, required_services, service_failures,
+    validate_compose,
+};
+use espresso_raas_blueprint::docker::espresso::{
+    DEFAULT_PROJECT_PREFIX, EspressoDockerManager, validate_project_prefix,
+};
+use espresso_raas_blueprint::docker::helpers::{
+    get_rollup_info, get_rollup_status_by_id, list_service_rollups_page,
+};
+use espresso_raas_blueprint::docker::logs::{LogsEncoding, LogsEnvelope};
+use espresso_raas_blueprint::docker::mock::MockRuntime;
+use espresso_raas_blueprint::docker::rollup::{
+    CANCELLED_REASON, CreateRollupResult, CreationPhase, ListFilter, MAX_LIST_PAGE_SIZE,
+    RollupInfo, RollupManager, RollupStatus, reconciled_status,
+};
+use espresso_raas_blueprint::docker::runtime::ContainerRuntime;
+use espresso_raas_blueprint::docker::{ExecAllowlist, ImageAllowlist};
+use espresso_raas_blueprint::{
+    ConfigOverrides, DeploymentResult, NetworkType, RaasError, RollupConfig, RollupConfigParams,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+fn resource(name: &str, project: Option<&str>) -> ExistingResource {
+    let mut labels = HashMap::new();
+    if let Some(project) = project {
+        labels.insert(PROJECT_LABEL.to_string(), project.to_string());
+    }
+    ExistingResource {
+        name: name.to_string(),
+        labels,
+    }
+}
+
+#[test]
+fn labeled_network_and_volume_are_adopted() {
+    let project = "espresso-docker-rollup-1";
+    let existing = vec![
+        resource("network-espresso-docker-rollup-1", Some(project)),
+        resource("nitro-data", Some(project)),
+    ];
+
+    assert_eq!(
+        plan_adoption(&existing, "network-espresso-docker-rollup-1", project),
+        Adoption::Adopt
+    );
+    assert_eq!(
+        plan_adoption(&existing, "nitro-data", project),
+        Adoption::Adopt
+    );
+    assert_eq!(
+        plan_adoption(&existing, "validation-data", project),
+        Adoption::Create
+    );
+}
+
+#[test]
+fn foreign_resources_are_not_adopted() {
+    let existing = vec![
+        resource("nitro-data", Some("espresso-other")),
+        resource("unlabeled", None),
+    ];
+
+    assert!(matches!(
+        plan_adoption(&existing, "nitro-data", "espresso-mine"),
+        Adoption::Conflict(_)
+    ));
+    assert!(matches!(
+        plan_adoption(&existing, "unlabeled", "espresso-mine"),
+        Adoption::Conflict(_)
+    ));
+}
+
+#[test]
+fn project_config_keeps_every_service_field() -> anyhow::Result<()> {
+    let parsed: dockworker::ComposeConfig = serde_json::from_value(serde_json::json!({
+        "version": "",
+        "services": {
+            "nitro": {
+                "image": "nitro-node:integration",
+                "user": "root",
+                "platform": "linux/amd64",
+                "networks": ["internal", "public"],
+                "labels": {"team": "rollups"},
+            },
+            "validation_node": {
+                "image": "nitro-node:integration",
+            },
+        },
+        "volumes": {},
+    }))?;
+    let labels = HashMap::from([(PROJECT_LABEL.to_string(), "espresso-vm".to_string())]);
+
+    let config = project_compose_config(&parsed, "espresso-vm", &labels);
+
+    // Only the networks and labels change, whatever other fields the service has
+    let mut expected = serde_json::to_value(&parsed.services["nitro"])?;
+    expected["networks"] =
+        serde_json::json!(["network-espresso-vm-internal", "network-espresso-vm-public"]);
+    expected["labels"] = serde_json::json!({"team": "rollups", PROJECT_LABEL: "espresso-vm"});
+    assert_eq!(serde_json::to_value(&config.services["nitro"])?, expected);
+    assert_eq!(config.version, "3");
+
+    // Services without networks of their own join the default one
+    assert_eq!(
+        config.services["validation_node"].networks,
+        Some(vec!["network-espresso-vm".to_string()])
+    );
+    Ok(())
+}
+
+#[test]
+fn down_services_are_broken_down_unless_optional() -> anyhow::Result<()> {
+    let parsed: dockworker::ComposeConfig = serde_json::from_value(serde_json::json!({
+        "version": "",
+        "services": {
+            "nitro": {"image": "nitro-node:integration"},
+            "validation_node": {"image": "nitro-node:integration", "labels": {OPTIONAL_LABEL: "false"}},
+            "explorer": {"image": "blockscout", "labels": {OPTIONAL_LABEL: "true"}},
+        },
+        "volumes": {},
+    }))?;
+    assert_eq!(required_services(&parsed), ["nitro", "validation_node"]);
+
+    let status = |name: &str, status: &str| (name.to_string(), status.to_string());
+    assert_eq!(
+        service_failures(&[
+            status("nitro", "starting"),
+            status("validation_node", "running")
+        ]),
+        None
+    );
+    assert_eq!(
+        service_failures(&[
+            status("nitro", "healthy"),
+            status("validation_node", "exited"),
+            status("sequencer", "unknown (Container ID not found)"),
+        ])
+        .as_deref(),
+        Some("validation_node: exited, sequencer: unknown (Container ID not found)")
+    );
+    Ok(())
+}
+
+#[test]
+fn compose_files_are_checked_before_starting() -> anyhow::Result<()> {
+    let compose = |services: serde_json::Value| -> anyhow::Result<dockworker::ComposeConfig> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "version": "",
+            "services": services,
+            "volumes": {"nitro-data": ""},
+        }))?)
+    };
+
+    let valid = compose(serde_json::json!({
+        "nitro": {
+            "image": "nitro-node:integration",
+            "volumes": ["./config:/config", "nitro-data:/home/user/.arbitrum"],
+        },
+        "validation_node": {"image": "nitro-node:integration", "volumes": ["/tmp/wasm:/wasm:ro"]},
+    }))?;
+    validate_compose(&valid, &ImageAllowlist::default())?;
+
+    let without_node = compose(serde_json::json!({
+        "validation_node": {"image": "nitro-node:integration"},
+    }))?;
+    let error = validate_compose(&without_node, &ImageAllowlist::default())
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("no `nitro` service"), "{error}");
+
+    let without_image = compose(serde_json::json!({
+        "nitro": {"image": "nitro-node:integration"},
+        "validation_node": {"image": " "},
+    }))?;
+    let error = validate_compose(&without_image, &ImageAllowlist::default())
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("`validation_node` has no image"), "{error}");
+
+    let undeclared_volume = compose(serde_json::json!({
+        "nitro": {"image": "nitro-node:integration", "volumes": ["chain-data:/data"]},
+    }))?;
+    let error = validate_compose(&undeclared_volume, &ImageAllowlist::default())
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("undeclared volume `chain-data`"), "{error}");
+    Ok(())
+}
+
+#[test]
+fn compose_parse_errors_quote_the_offending_lines() {
+    let path = std::path::Path::new("/rollups/rollup-0/docker-compose.yml");
+    let content = "services:\n  nitro:\n    image: nitro\n    ports: [\n  geth:\n    image: geth\n";
+    let error = compose_parse_error(
+        path,
+        content,
+        "did not find expected ',' or ']' at line 5 column 3",
+    );
+    assert_eq!(
+        error.to_string(),
+        "Failed to parse compose file /rollups/rollup-0/docker-compose.yml: \
+         did not find expected ',' or ']' at line 5 column 3\n  \
+         3 |     image: nitro\n  \
+         4 |     ports: [\n\
+         > 5 |   geth:\n  \
+         6 |     image: geth"
+    );
+
+    // Without a line to point at, or one past the end, only the file is named
+    for error in ["invalid type: string", "unexpected end at line 40 column 1"] {
+        assert_eq!(
+            compose_parse_error(path, content, error).to_string(),
+            format!(
+                "Failed to parse compose file /rollups/rollup-0/docker-compose.yml: {}",
+                error
+            )
+        );
+    }
+}
+
+#[test]
+fn only_allowlisted_images_are_run() -> anyhow::Result<()> {
+    let config: dockworker::ComposeConfig = serde_json::from_value(serde_json::json!({
+        "version": "",
+        "services": {
+            "nitro": {"image": "ghcr.io/espressosystems/nitro-espresso-integration/nitro-node:v3"},
+            "miner": {"image": "docker.io/evil/miner:latest"},
+        },
+        "volumes": {},
+    }))?;
+
+    // Unset, every image is allowed as before
+    assert_eq!(
+        ImageAllowlist::from_prefixes(" , "),
+        ImageAllowlist::default()
+    );
+    let error = validate_compose(&config, &ImageAllowlist::default());
+    assert!(error.is_ok(), "{error:?}");
+
+    let allowlist = ImageAllowlist::from_prefixes("ghcr.io/espressosystems/, offchainlabs/");
+    assert!(allowlist.allows("offchainlabs/nitro-node:v3.5.0"));
+    assert!(!allowlist.allows("docker.io/offchainlabs/nitro-node:v3.5.0"));
+    let error = validate_compose(&config, &allowlist)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("`miner` uses image `docker.io/evil/miner:latest`"),
+        "{error}"
+    );
+
+    let allowlist = ImageAllowlist::new(["ghcr.io/espressosystems/", "docker.io/evil/"]);
+    validate_compose(&config, &allowlist)?;
+    Ok(())
+}
+
+#[test]
+fn project_names_keep_the_prefix_the_rollup_was_created_with() -> anyhow::Result<()> {
+    let manager = EspressoDockerManager::new("/tmp/workspace", "/tmp/config", "vm-1");
+    assert_eq!(manager.project_name(), "espresso-vm-1");
+    let manager = manager.with_project_prefix("staging");
+    assert_eq!(manager.project_name(), "staging-vm-1");
+
+    // Rollups persisted before the prefix was configurable used the default one
+    let mut persisted = serde_json::to_value(running_rollup(1))?;
+    persisted.as_object_mut().unwrap().remove("project_prefix");
+    let mut rollup: RollupInfo = serde_json::from_value(persisted)?;
+    assert_eq!(
+        rollup.docker_manager().project_name(),
+        "espresso-docker-rollup-0-1"
+    );
+    rollup.project_prefix = "prod".to_string();
+    assert_eq!(
+        rollup.docker_manager().project_name(),
+        "prod-docker-rollup-0-1"
+    );
+
+    assert!(validate_project_prefix("espresso_prod-2").is_ok());
+    for invalid in ["", "Prod", "-prod", "prod.eu"] {
+        assert!(validate_project_prefix(invalid).is_err(), "{invalid}");
+    }
+    Ok(())
+}
+
+fn service(name: &str, state: &str) -> ServiceState {
+    ServiceState {
+        name: name.to_string(),
+        state: state.to_string(),
+    }
+}
+
+#[test]
+fn reconcile_maps_container_state_to_status() {
+    let all_up = [
+        service("nitro", "running"),
+        service("validation", "running"),
+    ];
+    let all_down = [service("nitro", "exited"), service("validation", "exited")];
+    let partial = [service("nitro", "running"), service("validation", "exited")];
+
+    assert_eq!(
+        reconciled_status(&RollupStatus::Stopped, &all_up),
+        Some(RollupStatus::Running)
+    );
+    assert_eq!(reconciled_status(&RollupStatus::Running, &all_up), None);
+    assert_eq!(
+        reconciled_status(&RollupStatus::Running, &all_down),
+        Some(RollupStatus::Stopped)
+    );
+    assert!(matches!(
+        reconciled_status(&RollupStatus::Running, &partial),
+        Some(RollupStatus::Failed(reason)) if reason.contains("validation exited")
+    ));
+    // A failure isn't turned into a clean stop by the containers it left exited
+    let failed = RollupStatus::Failed("Containers unhealthy".to_string());
+    assert_eq!(reconciled_status(&failed, &all_down), None);
+}
+
+#[test]
+fn reconcile_marks_missing_containers_stopped() {
+    assert_eq!(
+        reconciled_status(&RollupStatus::Running, &[]),
+        Some(RollupStatus::Stopped)
+    );
+    // Never-started rollups have no containers by design
+    assert_eq!(reconciled_status(&RollupStatus::Created, &[]), None);
+}
+
+fn running_rollup(index: usize) -> RollupInfo {
+    RollupInfo {
+        service_id: 0,
+        rollup_id: format!("rollup-{}", index),
+        vm_id: format!("docker-rollup-0-{}", index),
+        config: RollupConfig {
+            chain_id: 412346,
+            initial_chain_owner: [1; 20],
+            validators: vec![[2; 20]],
+            batch_poster_address: [3; 20],
+            batch_poster_manager: [4; 20],
+            is_mainnet: false,
+            network: NetworkType::Geth,
+            node_flags: Default::default(),
+            dry_run: false,
+            rpc_host_port: None,
+            node_image: None,
+            espresso_sequencer_url: None,
+            espresso_namespace: None,
+            extra_env: Default::default(),
+        },
+        status: RollupStatus::Running,
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+        workspace_dir: format!("/tmp/espresso/{}/workspace", index).into(),
+        config_dir: format!("/tmp/espresso/{}/config", index).into(),
+        deployment: None,
+        request_id: None,
+        rpc_port: None,
+        ws_port: None,
+        feed_port: None,
+        validation_port: None,
+        node_image_tag: None,
+        project_prefix: DEFAULT_PROJECT_PREFIX.to_string(),
+        data_dir: None,
+        node_keys: None,
+        sync_status: None,
+    }
+}
+
+#[tokio::test]
+async fn reconcile_many_rollups_within_one_interval() -> anyhow::Result<()> {
+    const ROLLUPS: usize = 100;
+    const CONCURRENCY: usize = 20;
+    const INSPECTION: Duration = Duration::from_millis(100);
+    // Inspecting serially would take ROLLUPS * INSPECTION = 10s
+    const INTERVAL: Duration = Duration::from_secs(3);
+
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollups: HashMap<String, RollupInfo> = (0..ROLLUPS)
+        .map(running_rollup)
+        .map(|rollup| (rollup.rollup_id.clone(), rollup))
+        .collect();
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let in_flight = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let started = Instant::now();
+    manager
+        .reconcile_with(CONCURRENCY, |_| async {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(INSPECTION).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            // Every container is gone
+            Ok(Vec::new())
+        })
+        .await?;
+
+    assert!(started.elapsed() < INTERVAL);
+    assert!(peak.load(Ordering::SeqCst) <= CONCURRENCY);
+    for rollup in manager.list_rollups().await {
+        assert_eq!(rollup.status, RollupStatus::Stopped);
+    }
+    Ok(())
+}
+
+#[test]
+fn rollup_dirs_live_under_base_dir() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf());
+
+    let (workspace_dir, config_dir) = manager.rollup_dirs("docker-rollup-0-abc");
+    assert_eq!(
+        workspace_dir,
+        temp_dir.path().join("docker-rollup-0-abc/workspace")
+    );
+    assert_eq!(
+        config_dir,
+        temp_dir.path().join("docker-rollup-0-abc/config")
+    );
+    assert_eq!(manager.state_file(), temp_dir.path().join("state.json"));
+    Ok(())
+}
+
+#[test]
+fn rollup_paths_are_keyed_on_the_rollup_id() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf());
+
+    let paths = manager.paths("rollup-0");
+    assert_eq!(paths, manager.paths("rollup-0"));
+    assert_eq!(paths.root, temp_dir.path().join("rollup-0"));
+    assert_eq!(paths.workspace, temp_dir.path().join("rollup-0/workspace"));
+    assert_eq!(paths.config, temp_dir.path().join("rollup-0/config"));
+    assert_eq!(paths.data, temp_dir.path().join("rollup-0/database"));
+    assert_eq!(paths.keys, temp_dir.path().join("rollup-0/keys"));
+
+    // Rollups laid out under their VM ID keep their directories
+    let legacy = running_rollup(0);
+    let legacy_paths = legacy.paths();
+    assert_eq!(legacy_paths.workspace, legacy.workspace_dir);
+    assert_eq!(legacy_paths.config, legacy.config_dir);
+    assert_eq!(legacy_paths.data, legacy_paths.root.join("database"));
+    assert_ne!(legacy_paths.root, manager.paths(&legacy.rollup_id).root);
+    Ok(())
+}
+
+#[tokio::test]
+async fn restart_leaves_rollup_failed_when_stop_fails() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    // Without a workspace the compose project can't be located, so stopping fails
+    let mut rollup = running_rollup(0);
+    rollup.workspace_dir = Default::default();
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    // The start must not be attempted
+    let error = manager.restart_rollup("rollup-0").await.unwrap_err();
+
+    let status = manager.get_rollup("rollup-0").await.unwrap().status;
+    assert_eq!(status, RollupStatus::Failed(error.to_string()));
+    assert!(!manager.is_busy("rollup-0"));
+    Ok(())
+}
+
+fn stopped_rollup(dir: &std::path::Path) -> RollupInfo {
+    let mut rollup = running_rollup(0);
+    rollup.status = RollupStatus::Stopped;
+    rollup.workspace_dir = dir.join("rollup-0").join("workspace");
+    rollup.config_dir = dir.join("rollup-0").join("config");
+    rollup
+}
+
+#[tokio::test]
+async fn rollups_move_through_their_states_on_the_runtime() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = stopped_rollup(temp_dir.path());
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup.clone())]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let runtime = MockRuntime::default();
+    let rollup_runtime = runtime.clone();
+    let manager = RollupManager::load(&state_file)?
+        .with_runtime(move |info| Box::new(rollup_runtime.for_rollup(info)));
+
+    manager.start_rollup("rollup-0").await?;
+    let status = manager.get_rollup("rollup-0").await.unwrap().status;
+    assert_eq!(status, RollupStatus::Running);
+    assert!(runtime.is_running(&rollup.vm_id));
+
+    // Containers that went away behind the manager's back are noticed
+    runtime.for_rollup(&rollup).stop().await?;
+    manager.reconcile(1).await?;
+    let status = manager.get_rollup("rollup-0").await.unwrap().status;
+    assert_eq!(status, RollupStatus::Stopped);
+
+    manager.restart_rollup("rollup-0").await?;
+    assert!(runtime.is_running(&rollup.vm_id));
+    manager.stop_rollup("rollup-0").await?;
+    let status = manager.get_rollup("rollup-0").await.unwrap().status;
+    assert_eq!(status, RollupStatus::Stopped);
+    assert!(!runtime.is_running(&rollup.vm_id));
+
+    // The compose file and wasm modules next to the workspace go with the rollup
+    let root = temp_dir.path().join("rollup-0");
+    std::fs::create_dir_all(root.join("wasm"))?;
+    std::fs::write(root.join("docker-compose.yml"), "services: {}\n")?;
+    manager.delete_rollup("rollup-0").await?;
+    assert!(manager.get_rollup("rollup-0").await.is_none());
+    assert!(!root.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn decommissioned_rollups_leave_nothing_behind() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = stopped_rollup(temp_dir.path());
+    let root = temp_dir.path().join("rollup-0");
+    std::fs::create_dir_all(&rollup.workspace_dir)?;
+    std::fs::create_dir_all(rollup.data_dir())?;
+    std::fs::write(root.join("docker-compose.yml"), "services: {}\n")?;
+    // Only paths inside the data directory are removed
+    let outside = tempfile::TempDir::new()?;
+    let mut foreign = running_rollup(1);
+    foreign.workspace_dir = outside.path().join("workspace");
+    std::fs::create_dir_all(&foreign.workspace_dir)?;
+    let rollups = HashMap::from([
+        (rollup.rollup_id.clone(), rollup),
+        (foreign.rollup_id.clone(), foreign.clone()),
+    ]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?
+        .with_runtime(|info| Box::new(MockRuntime::default().for_rollup(info)));
+
+    let receipt = manager.decommission_rollup("rollup-0", None).await?;
+    assert_eq!(receipt.rollup_id, "rollup-0");
+    assert!(receipt.archive_path.is_none());
+    assert!(!root.exists());
+    assert!(manager.get_rollup("rollup-0").await.is_none());
+
+    manager.decommission_rollup("rollup-1", None).await?;
+    assert!(foreign.workspace_dir.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn refused_starts_leave_the_rollup_failed() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = stopped_rollup(temp_dir.path());
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?
+        .with_runtime(|info| Box::new(MockRuntime::failing("no space left").for_rollup(info)));
+
+    let error = manager.start_rollup("rollup-0").await.unwrap_err();
+    assert!(error.to_string().contains("no space left"));
+    let status = manager.get_rollup("rollup-0").await.unwrap().status;
+    assert_eq!(status, RollupStatus::Failed(error.to_string()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn start_failures_tell_the_container_from_the_daemon() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = stopped_rollup(temp_dir.path());
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    for (runtime, unavailable) in [
+        (MockRuntime::failing("connection refused"), true),
+        (MockRuntime::rejecting("unknown image"), false),
+        (MockRuntime::unhealthy(), false),
+    ] {
+        std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+        let manager = RollupManager::load(&state_file)?
+            .with_runtime(move |info| Box::new(runtime.for_rollup(info)));
+
+        let error = manager.start_rollup("rollup-0").await.unwrap_err();
+        assert_eq!(
+            matches!(error, RaasError::DockerUnavailable(_)),
+            unavailable,
+            "{error}"
+        );
+        assert_eq!(error.is_transient(), unavailable, "{error}");
+    }
+    Ok(())
+}
+
+fn deployment() -> DeploymentResult {
+    DeploymentResult {
+        rollup_creator_address: "0x1111111111111111111111111111111111111111".to_string(),
+        rollup_proxy_address: "0x2222222222222222222222222222222222222222".to_string(),
+        upgrade_executor_address: "0x3333333333333333333333333333333333333333".to_string(),
+        deployment_block: 1234,
+        chain_id: 412346,
+        bridge_address: "0x4444444444444444444444444444444444444444".to_string(),
+        inbox_address: "0x5555555555555555555555555555555555555555".to_string(),
+        sequencer_inbox_address: "0x6666666666666666666666666666666666666666".to_string(),
+        validator_utils_address: "0x7777777777777777777777777777777777777777".to_string(),
+        validator_wallet_creator_address: "0x8888888888888888888888888888888888888888".to_string(),
+        artifact: None,
+    }
+}
+
+#[tokio::test]
+async fn deployment_is_persisted_with_the_rollup() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let mut deployed = running_rollup(0);
+    deployed.deployment = Some(deployment());
+
+    // State written before deployments were recorded has no such field
+    let mut legacy = serde_json::to_value(running_rollup(1))?;
+    legacy.as_object_mut().unwrap().remove("deployment");
+
+    let state = serde_json::json!({ "rollup-0": deployed, "rollup-1": legacy });
+    std::fs::write(&state_file, serde_json::to_vec(&state)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let deployment = manager.get_deployment("rollup-0").await.unwrap();
+    assert_eq!(
+        deployment.rollup_proxy_address,
+        "0x2222222222222222222222222222222222222222"
+    );
+    assert_eq!(deployment.deployment_block, 1234);
+    assert!(manager.get_deployment("rollup-1").await.is_none());
+    Ok(())
+}
+
+#[test]
+fn creation_phase_is_shown_and_legacy_status_loads() -> anyhow::Result<()> {
+    let status = RollupStatus::Creating(CreationPhase::BuildingContracts);
+    assert_eq!(status.to_string(), "Creating: BuildingContracts");
+
+    // Older state files stored the status without a phase
+    let mut legacy = serde_json::to_value(running_rollup(0))?;
+    legacy["status"] = serde_json::json!("Creating");
+    let rollup: RollupInfo = serde_json::from_value(legacy)?;
+    assert_eq!(
+        rollup.status,
+        RollupStatus::Creating(CreationPhase::CloningRepo)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn legacy_timestamps_load_and_status_changes_are_timed() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    // Older state files had a free-form created_at and no updated_at
+    let mut rollups = serde_json::Map::new();
+    for (index, created_at) in [(0, "2025-01-01T02:00:00+02:00"), (1, "yesterday")] {
+        let mut legacy = serde_json::to_value(running_rollup(index))?;
+        legacy["created_at"] = serde_json::json!(created_at);
+        legacy.as_object_mut().unwrap().remove("updated_at");
+        rollups.insert(format!("rollup-{}", index), legacy);
+    }
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let rollup = manager.get_rollup("rollup-0").await.unwrap();
+    assert_eq!(rollup.created_at.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+    assert_eq!(rollup.updated_at, rollup.created_at);
+    let garbage = manager.get_rollup("rollup-1").await.unwrap();
+    assert_eq!(garbage.created_at, chrono::DateTime::UNIX_EPOCH);
+    // Unreadable timestamps sort as the oldest
+    assert_eq!(manager.list_rollups().await[1].rollup_id, "rollup-1");
+
+    manager
+        .update_rollup_status("rollup-0", RollupStatus::Stopped)
+        .await?;
+    let rollup = manager.get_rollup("rollup-0").await.unwrap();
+    assert!(rollup.updated_at > rollup.created_at);
+    Ok(())
+}
+
+#[test]
+fn only_paths_inside_the_data_dir_are_owned() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf());
+    let (workspace_dir, config_dir) = manager.rollup_dirs("docker-rollup-0-abc");
+
+    assert!(manager.owns_path(&workspace_dir));
+    assert!(manager.owns_path(&config_dir));
+    assert!(!manager.owns_path(temp_dir.path()));
+    assert!(!manager.owns_path(&workspace_dir.join("../../..")));
+    assert!(!manager.owns_path(std::path::Path::new("/tmp/espresso/0/workspace")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn errors_name_the_failure() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let existing = running_rollup(0);
+    let config = existing.config.clone();
+    let rollups = HashMap::from([(existing.rollup_id.clone(), existing)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let error = manager.stop_rollup("missing").await.unwrap_err();
+    assert!(matches!(error, RaasError::RollupNotFound(ref id) if id == "missing"));
+    assert!(!error.is_transient());
+
+    // The chain ID is checked before anything is deployed
+    let error = manager
+        .create_rollup(
+            0,
+            "rollup-1",
+            "docker-rollup-0-1",
+            config,
+            temp_dir.path().join("workspace"),
+            temp_dir.path().join("config"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        RaasError::ChainIdInUse { chain_id: 412346, ref rollup_id } if rollup_id == "rollup-0"
+    ));
+    assert!(manager.get_rollup("rollup-1").await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollups_of_other_services_are_not_found() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = running_rollup(0);
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    assert_eq!(
+        get_rollup_info(&manager, 0, "rollup-0").await?.vm_id,
+        "docker-rollup-0-0"
+    );
+    let error = get_rollup_info(&manager, 1, "rollup-0").await.unwrap_err();
+    assert!(matches!(error, RaasError::RollupNotFound(ref id) if id == "rollup-0"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_rollups_filters_and_pages() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollups: HashMap<String, RollupInfo> = (0..5)
+        .map(|index| {
+            let mut rollup = running_rollup(index);
+            rollup.service_id = (index % 2) as u64;
+            rollup.created_at = format!("2025-01-0{}T00:00:00+00:00", index + 1)
+                .parse()
+                .unwrap();
+            if index == 3 {
+                rollup.status = RollupStatus::Failed("boom".to_string());
+            }
+            (rollup.rollup_id.clone(), rollup)
+        })
+        .collect();
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let ids = |rollups: Vec<RollupInfo>| -> Vec<String> {
+        rollups.into_iter().map(|rollup| rollup.rollup_id).collect()
+    };
+
+    let service = ListFilter {
+        service_id: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(ids(manager.list_rollups_filtered(service.clone()).await), [
+        "rollup-4", "rollup-2", "rollup-0"
+    ]);
+    let page = ListFilter {
+        offset: Some(1),
+        limit: Some(2),
+        oldest_first: true,
+        ..service
+    };
+    assert_eq!(ids(manager.list_rollups_filtered(page).await), [
+        "rollup-2", "rollup-4"
+    ]);
+
+    // Failure reasons don't have to match
+    let failed = ListFilter {
+        status: Some(RollupStatus::Failed(String::new())),
+        ..Default::default()
+    };
+    assert_eq!(ids(manager.list_rollups_filtered(failed).await), [
+        "rollup-3"
+    ]);
+    assert_eq!(manager.list_rollups().await.len(), 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn service_pages_are_capped_and_report_the_total() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollups: HashMap<String, RollupInfo> = (0..MAX_LIST_PAGE_SIZE + 10)
+        .map(|index| {
+            let mut rollup = running_rollup(index);
+            rollup.service_id = if index < 5 { 1 } else { 0 };
+            (rollup.rollup_id.clone(), rollup)
+        })
+        .collect();
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let (page, total) = list_service_rollups_page(&manager, 0, 0, usize::MAX).await;
+    assert_eq!(page.len(), MAX_LIST_PAGE_SIZE);
+    assert_eq!(total, MAX_LIST_PAGE_SIZE + 5);
+
+    let (page, total) = list_service_rollups_page(&manager, 0, MAX_LIST_PAGE_SIZE, 10).await;
+    assert_eq!(page.len(), 5);
+    assert_eq!(total, MAX_LIST_PAGE_SIZE + 5);
+
+    let (page, total) = list_service_rollups_page(&manager, 1, 0, 2).await;
+    assert_eq!(page.len(), 2);
+    assert!(page.iter().all(|rollup| rollup.service_id == 1));
+    assert_eq!(total, 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn stop_all_only_touches_running_rollups() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    // Without a workspace the compose project can't be located, so stopping fails
+    let mut running = running_rollup(0);
+    running.workspace_dir = Default::default();
+    let mut stopped = running_rollup(1);
+    stopped.status = RollupStatus::Stopped;
+    let rollups = HashMap::from([
+        (running.rollup_id.clone(), running),
+        (stopped.rollup_id.clone(), stopped),
+    ]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    assert!(manager.stop_all().await.is_empty());
+
+    // The failed stop is recorded rather than leaving a stale Running status
+    let status = manager.get_rollup("rollup-0").await.unwrap().status;
+    assert_eq!(status.name(), "Failed");
+    let status = manager.get_rollup("rollup-1").await.unwrap().status;
+    assert_eq!(status, RollupStatus::Stopped);
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_config_refuses_immutable_changes() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = running_rollup(0);
+    let config = rollup.config.clone();
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let params = RollupConfigParams {
+        chain_id: 412347,
+        initial_chain_owner: config.initial_chain_owner,
+        validators: List(vec![[5; 20]]),
+        batch_poster_address: config.batch_poster_address,
+        batch_poster_manager: config.batch_poster_manager,
+        is_mainnet: false,
+        network: NetworkType::ArbitrumSepolia,
+        node_flags: Default::default(),
+        dry_run: false,
+        request_id: None,
+        rpc_host_port: None,
+        node_image: None,
+        espresso_sequencer_url: None,
+        espresso_namespace: None,
+        extra_env: Default::default(),
+    };
+    let error = manager.update_config("rollup-0", params).await.unwrap_err();
+
+    // Only the immutable fields are named, and nothing is changed
+    assert!(matches!(error, RaasError::InvalidConfig(_)));
+    assert!(
+        error
+            .to_string()
+            .contains("chain_id, network can't be changed")
+    );
+    let updated = manager.get_rollup("rollup-0").await.unwrap().config;
+    assert_eq!(updated.chain_id, 412346);
+    assert_eq!(updated.validators, config.validators);
+    assert!(!manager.is_busy("rollup-0"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn retried_creation_returns_the_first_rollup() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let mut created = running_rollup(0);
+    created.deployment = Some(deployment());
+    created.request_id = Some("request-0".to_string());
+    let config = created.config.clone();
+    let rollups = HashMap::from([(created.rollup_id.clone(), created)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    // Nothing is deployed, so no deployer key is needed
+    let retried = manager
+        .create_rollup(
+            0,
+            "rollup-1",
+            "docker-rollup-0-1",
+            config,
+            temp_dir.path().join("workspace"),
+            temp_dir.path().join("config"),
+            Some("request-0".to_string()),
+        )
+        .await?;
+    assert_eq!(retried.rollup_id, "rollup-0");
+    assert_eq!(
+        retried.rollup_proxy_address,
+        "0x2222222222222222222222222222222222222222"
+    );
+    assert!(manager.get_rollup("rollup-1").await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn request_ids_of_other_services_are_not_retries() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let mut created = running_rollup(0);
+    created.deployment = Some(deployment());
+    created.request_id = Some("request-0".to_string());
+    let mut config = created.config.clone();
+    let rollups = HashMap::from([(created.rollup_id.clone(), created)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?
+        .with_deployer(Box::new(MockDeployer::default()))
+        .with_per_rollup_keys(true);
+
+    config.chain_id = 412347;
+    let other = manager
+        .create_rollup(
+            1,
+            "rollup-1",
+            "docker-rollup-1-1",
+            config,
+            temp_dir.path().join("rollup-1").join("workspace"),
+            temp_dir.path().join("rollup-1").join("config"),
+            Some("request-0".to_string()),
+        )
+        .await?;
+    assert_eq!(other.rollup_id, "rollup-1");
+    assert_eq!(manager.get_rollup("rollup-1").await.unwrap().service_id, 1);
+    Ok(())
+}
+
+async fn create(
+    manager: &RollupManager,
+    dir: &std::path::Path,
+    rollup_id: &str,
+) -> Result<CreateRollupResult, RaasError> {
+    manager
+        .create_rollup(
+            0,
+            rollup_id,
+            &format!("docker-{}", rollup_id),
+            running_rollup(0).config,
+            dir.join("workspace").join(rollup_id),
+            dir.join("config").join(rollup_id),
+            None,
+        )
+        .await
+}
+
+#[tokio::test]
+async fn creation_deploys_with_the_configured_deployer() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+
+    // No deployer key is needed, the mock stands in for the toolchain
+    let manager = RollupManager::with_base_dir(temp_dir.path().join("ok"))
+        .with_deployer(Box::new(MockDeployer::default()));
+    let result = create(&manager, temp_dir.path(), "rollup-0").await;
+    assert!(!matches!(result, Err(RaasError::DeploymentFailed(_))));
+
+    let manager = RollupManager::with_base_dir(temp_dir.path().join("failing"))
+        .with_deployer(Box::new(MockDeployer::failing("out of gas")));
+    let Err(RaasError::DeploymentFailed(e)) = create(&manager, temp_dir.path(), "rollup-1").await
+    else {
+        panic!("a failing deployer must fail the creation");
+    };
+    assert!(e.to_string().contains("out of gas"));
+    let info = manager.get_rollup("rollup-1").await.unwrap();
+    assert!(matches!(info.status, RollupStatus::Failed(_)));
+
+    // The failed rollup never took the chain ID
+    let result = create(&manager, temp_dir.path(), "rollup-2").await;
+    assert!(!matches!(result, Err(RaasError::ChainIdInUse { .. })));
+    Ok(())
+}
+
+#[tokio::test]
+async fn creations_in_progress_can_be_cancelled() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = Arc::new(
+        RollupManager::with_base_dir(temp_dir.path().to_path_buf())
+            .with_deployer(Box::new(
+                MockDeployer::default().with_delay(Duration::from_secs(600)),
+            ))
+            .with_runtime(|info| Box::new(MockRuntime::default().for_rollup(info))),
+    );
+    let deploying = |rollup_id: &'static str| {
+        let manager = manager.clone();
+        async move {
+            loop {
+                let status = manager.get_rollup(rollup_id).await.map(|info| info.status);
+                if status == Some(RollupStatus::Creating(CreationPhase::DeployingContracts)) {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    };
+
+    let creation = tokio::spawn({
+        let manager = manager.clone();
+        let dir = temp_dir.path().to_path_buf();
+        async move { create(&manager, &dir, "rollup-0").await }
+    });
+    tokio::time::timeout(Duration::from_secs(5), deploying("rollup-0")).await?;
+    tokio::time::timeout(Duration::from_secs(5), manager.cancel_creation("rollup-0")).await??;
+    assert!(matches!(
+        creation.await?,
+        Err(RaasError::Cancelled(ref rollup_id)) if rollup_id == "rollup-0"
+    ));
+    let info = manager.get_rollup("rollup-0").await.unwrap();
+    assert_eq!(
+        info.status,
+        RollupStatus::Failed(CANCELLED_REASON.to_string())
+    );
+    assert!(!manager.is_busy("rollup-0"));
+    assert!(matches!(
+        manager.cancel_creation("rollup-0").await,
+        Err(RaasError::InvalidConfig(_))
+    ));
+    manager.delete_rollup("rollup-0").await?;
+
+    // Deleting a rollup being created cancels the creation instead of failing as busy
+    let creation = tokio::spawn({
+        let manager = manager.clone();
+        let dir = temp_dir.path().to_path_buf();
+        async move { create(&manager, &dir, "rollup-1").await }
+    });
+    tokio::time::timeout(Duration::from_secs(5), deploying("rollup-1")).await?;
+    tokio::time::timeout(Duration::from_secs(5), manager.delete_rollup("rollup-1")).await??;
+    assert!(matches!(creation.await?, Err(RaasError::Cancelled(_))));
+    assert!(manager.get_rollup("rollup-1").await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn configs_are_regenerated_without_redeploying() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf())
+        .with_deployer(Box::new(MockDeployer::default()))
+        .with_per_rollup_keys(true);
+    create(&manager, temp_dir.path(), "rollup-0").await?;
+
+    // Any deployment would fail, the recorded addresses are reused
+    let manager = RollupManager::load(manager.state_file())?
+        .with_deployer(Box::new(MockDeployer::failing("no redeploys")))
+        .with_runtime(|info| Box::new(MockRuntime::default().for_rollup(info)));
+    let overrides = ConfigOverrides {
+        extra_env: Some(vec!["EXTRA_FLAG=1".to_string()]),
+        ..Default::default()
+    };
+    manager.regenerate_config("rollup-0", overrides).await?;
+
+    let info = manager.get_rollup("rollup-0").await.unwrap();
+    let compose = std::fs::read_to_string(
+        info.workspace_dir
+            .parent()
+            .unwrap()
+            .join("docker-compose.yml"),
+    )?;
+    assert!(compose.contains("EXTRA_FLAG: \"1\""));
+    assert_eq!(
+        info.config.extra_env,
+        HashMap::from([("EXTRA_FLAG".to_string(), "1".to_string())])
+    );
+    assert!(!manager.is_busy("rollup-0"));
+
+    // Without recorded addresses there is nothing to render the config against
+    let state_file = temp_dir.path().join("undeployed.json");
+    let rollup = running_rollup(1);
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+    assert!(matches!(
+        manager
+            .regenerate_config("rollup-1", ConfigOverrides::default())
+            .await,
+        Err(RaasError::ConfigGenerationFailed(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollups_get_and_rotate_keys_of_their_own() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf())
+        .with_deployer(Box::new(MockDeployer::default()))
+        .with_per_rollup_keys(true);
+    create(&manager, temp_dir.path(), "rollup-0").await?;
+
+    // The rollup validates and posts batches with its generated keys, not the configured ones
+    let info = manager.get_rollup("rollup-0").await.unwrap();
+    let keys = info.node_keys.clone().unwrap();
+    let addresses = keys.addresses()?;
+    assert_eq!(
+        format!("0x{}", hex::encode(info.config.batch_poster_address)),
+        addresses.batch_poster.to_lowercase()
+    );
+    assert_eq!(info.config.validators.len(), 1);
+    let state = std::fs::read_to_string(manager.state_file())?;
+    assert!(!state.contains(keys.load(RollupKey::BatchPoster)?.trim_start_matches("0x")));
+
+    let full_node = || -> anyhow::Result<serde_json::Value> {
+        let full_node = std::fs::read_to_string(info.config_dir.join("full_node.json"))?;
+        Ok(serde_json::from_str(&full_node)?)
+    };
+    let batch_poster_key = |full_node: serde_json::Value| {
+        full_node["node"]["batch-poster"]["parent-chain-wallet"]["private-key"].clone()
+    };
+    let previous_key = keys.load(RollupKey::BatchPoster)?;
+    assert_eq!(
+        batch_poster_key(full_node()?),
+        previous_key.trim_start_matches("0x")
+    );
+
+    let rotated = manager.rotate_keys("rollup-0").await?;
+    assert_ne!(rotated, addresses);
+    assert_eq!(rotated, keys.addresses()?);
+    let key = keys.load(RollupKey::BatchPoster)?;
+    assert_eq!(batch_poster_key(full_node()?), key.trim_start_matches("0x"));
+
+    // Rollups on the operator's keys rotate them in the keystore instead
+    let state_file = temp_dir.path().join("shared").join("state.json");
+    std::fs::create_dir_all(temp_dir.path().join("shared"))?;
+    let rollup = running_rollup(0);
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+    assert!(matches!(
+        manager.rotate_keys("rollup-0").await,
+        Err(RaasError::InvalidConfig(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollups_never_share_an_rpc_port() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let mut existing = running_rollup(0);
+    existing.rpc_port = Some(18547);
+    let mut config = existing.config.clone();
+    config.chain_id = 412347;
+    let rollups = HashMap::from([(existing.rollup_id.clone(), existing)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?.with_rpc_port_range(18547..=18547);
+
+    let create = |rpc_host_port| {
+        manager.create_rollup(
+            0,
+            "rollup-1",
+            "docker-rollup-0-1",
+            RollupConfig {
+                rpc_host_port,
+                ..config.clone()
+            },
+            temp_dir.path().join("workspace"),
+            temp_dir.path().join("config"),
+            None,
+        )
+    };
+
+    // Both are refused before anything is deployed
+    let error = create(Some(18547)).await.unwrap_err();
+    assert!(matches!(
+        error,
+        RaasError::RpcPortInUse { port: 18547, ref rollup_id } if rollup_id == "rollup-0"
+    ));
+    let error = create(None).await.unwrap_err();
+    assert!(error.to_string().contains("No free RPC port"));
+    assert!(manager.get_rollup("rollup-1").await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollups_publish_every_endpoint_on_ports_of_their_own() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf())
+        .with_deployer(Box::new(MockDeployer::default()))
+        .with_per_rollup_keys(true)
+        .with_rpc_port_range(18547..=18554);
+    let (manager, dir) = (&manager, temp_dir.path());
+    let create = |index: u64| async move {
+        let rollup_id = format!("rollup-{}", index);
+        manager
+            .create_rollup(
+                0,
+                &rollup_id,
+                &format!("docker-{}", rollup_id),
+                RollupConfig {
+                    chain_id: 412346 + index,
+                    ..running_rollup(0).config
+                },
+                dir.join(&rollup_id).join("workspace"),
+                dir.join(&rollup_id).join("config"),
+                None,
+            )
+            .await
+    };
+    create(0).await?;
+    create(1).await?;
+
+    let mut ports = Vec::new();
+    for rollup_id in ["rollup-0", "rollup-1"] {
+        let info = manager.get_rollup(rollup_id).await.unwrap();
+        let compose = std::fs::read_to_string(
+            info.workspace_dir
+                .parent()
+                .unwrap()
+                .join("docker-compose.yml"),
+        )?;
+        for (port, container_port) in [
+            (info.rpc_port, 8547),
+            (info.ws_port, 8548),
+            (info.feed_port, 9642),
+            (info.validation_port, 8549),
+        ] {
+            let port = port.unwrap();
+            assert!(compose.contains(&format!("\"{}:{}\"", port, container_port)));
+            ports.push(port);
+        }
+        // Compose scopes the container names to the rollup's project
+        assert!(!compose.contains("container_name"));
+    }
+    ports.sort();
+    assert_eq!(ports, (18547..=18554).collect::<Vec<_>>());
+
+    let error = create(2).await.unwrap_err();
+    assert!(error.to_string().contains("No free RPC port"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollups_never_share_a_data_dir() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let manager = RollupManager::new(&state_file);
+    assert_ne!(
+        manager.paths("rollup-0").data,
+        manager.paths("rollup-1").data
+    );
+
+    // A rollup from before the layout was keyed on the rollup ID, whose VM ID clashes
+    let mut existing = running_rollup(0);
+    existing.data_dir = Some(manager.paths("rollup-1").data);
+    let mut config = existing.config.clone();
+    config.chain_id = 412347;
+    let vm_id = existing.vm_id.clone();
+    let rollups = HashMap::from([(existing.rollup_id.clone(), existing)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    // Reusing the directory would start the new rollup from the existing chain
+    let error = manager
+        .create_rollup(
+            0,
+            "rollup-1",
+            &vm_id,
+            config,
+            temp_dir.path().join("workspace"),
+            temp_dir.path().join("config"),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        RaasError::DataDirInUse { ref rollup_id, .. } if rollup_id == "rollup-0"
+    ));
+    assert!(manager.get_rollup("rollup-1").await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn running_rollups_report_how_far_they_synced() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rpc_url = common::spawn_mock_rpc(|method, _params| match method {
+        "eth_blockNumber" => serde_json::json!("0x2a"),
+        "eth_syncing" => serde_json::json!({"batchProcessed": 45, "batchSeen": 100}),
+        _ => serde_json::Value::Null,
+    })
+    .await;
+    let mut syncing = running_rollup(0);
+    syncing.rpc_port = Some(rpc_url.rsplit(':').next().unwrap().parse()?);
+    let mut down = running_rollup(1);
+    down.rpc_port = Some(
+        common::unreachable_rpc()
+            .await
+            .rsplit(':')
+            .next()
+            .unwrap()
+            .parse()?,
+    );
+    let rollups: HashMap<String, RollupInfo> = [syncing, down]
+        .into_iter()
+        .map(|rollup| (rollup.rollup_id.clone(), rollup))
+        .collect();
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    // Unknown until checked
+    assert_eq!(
+        get_rollup_status_by_id(&manager, "rollup-0").await?,
+        "Running"
+    );
+    assert!(manager.health_check("rollup-0").await?.is_healthy());
+    assert_eq!(
+        get_rollup_status_by_id(&manager, "rollup-0").await?,
+        "Running (syncing 45%)"
+    );
+    let persisted = RollupManager::load(&state_file)?;
+    let info = persisted.get_rollup("rollup-0").await.unwrap();
+    assert_eq!(info.sync_status.map(|sync| sync.sync_progress), Some(45));
+
+    manager.check_sync_all(4).await;
+    assert!(
+        manager
+            .get_rollup("rollup-1")
+            .await
+            .unwrap()
+            .sync_status
+            .is_none()
+    );
+
+    // A stopped node's sync is out of date
+    manager
+        .update_rollup_status("rollup-0", RollupStatus::Stopped)
+        .await?;
+    assert_eq!(
+        get_rollup_status_by_id(&manager, "rollup-0").await?,
+        "Stopped"
+    );
+    assert!(
+        manager
+            .get_rollup("rollup-0")
+            .await
+            .unwrap()
+            .sync_status
+            .is_none()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn service_batch_stop_reports_each_rollup() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    // Without a workspace the compose project can't be located, so stopping fails
+    let mut failing = running_rollup(0);
+    failing.workspace_dir = Default::default();
+    let mut stopped = running_rollup(1);
+    stopped.status = RollupStatus::Stopped;
+    let mut other_service = running_rollup(2);
+    other_service.service_id = 1;
+    let rollups: HashMap<String, RollupInfo> = [failing, stopped, other_service]
+        .into_iter()
+        .map(|rollup| (rollup.rollup_id.clone(), rollup))
+        .collect();
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let results = manager.stop_all_for_service(0).await;
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0, "rollup-0");
+    assert!(results[0].1.is_err());
+    let status = manager.get_rollup("rollup-2").await.unwrap().status;
+    assert_eq!(status, RollupStatus::Running);
+    Ok(())
+}
+
+#[tokio::test]
+async fn status_watchers_see_each_transition() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = running_rollup(0);
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let mut status = manager.watch_status("rollup-0").await?;
+    assert_eq!(*status.borrow_and_update(), RollupStatus::Running);
+
+    manager
+        .update_rollup_status("rollup-0", RollupStatus::Stopping)
+        .await?;
+    status.changed().await?;
+    assert_eq!(*status.borrow_and_update(), RollupStatus::Stopping);
+
+    let stopped = tokio::spawn({
+        let mut status = status.clone();
+        async move {
+            status
+                .wait_for(|status| *status == RollupStatus::Stopped)
+                .await
+                .map(|_| ())
+        }
+    });
+    manager
+        .update_rollup_status("rollup-0", RollupStatus::Stopped)
+        .await?;
+    tokio::time::timeout(Duration::from_secs(5), stopped).await???;
+
+    assert!(matches!(
+        manager.watch_status("missing").await,
+        Err(RaasError::RollupNotFound(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn only_long_failed_rollups_are_pruned() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let long_ago = chrono::Utc::now() - chrono::Duration::days(30);
+    let rollups: HashMap<String, RollupInfo> = [
+        RollupStatus::Failed("Contract deployment failed".to_string()),
+        RollupStatus::Failed("Just now".to_string()),
+        RollupStatus::Running,
+        RollupStatus::Created,
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(index, status)| {
+        let mut rollup = running_rollup(index);
+        let root = temp_dir.path().join(&rollup.vm_id);
+        rollup.workspace_dir = root.join("workspace");
+        rollup.config_dir = root.join("config");
+        rollup.status = status;
+        rollup.created_at = long_ago;
+        if index != 1 {
+            rollup.updated_at = long_ago;
+        }
+        (rollup.rollup_id.clone(), rollup)
+    })
+    .collect();
+    for rollup in rollups.values() {
+        std::fs::create_dir_all(&rollup.config_dir)?;
+    }
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?
+        .with_runtime(|info| Box::new(MockRuntime::default().for_rollup(info)));
+
+    // Another service's failed rollups are out of reach
+    assert!(
+        manager
+            .prune_for_service(1, Duration::from_secs(3600))
+            .await
+            .is_empty()
+    );
+
+    let pruned = manager.prune(Duration::from_secs(3600)).await;
+    assert_eq!(pruned, ["rollup-0"]);
+    assert!(manager.get_rollup("rollup-0").await.is_none());
+    assert!(!rollups["rollup-0"].config_dir.exists());
+    for rollup_id in ["rollup-1", "rollup-2", "rollup-3"] {
+        assert!(manager.get_rollup(rollup_id).await.is_some(), "{rollup_id}");
+        assert!(rollups[rollup_id].config_dir.exists(), "{rollup_id}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn status_callbacks_see_each_change_outside_the_lock() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = stopped_rollup(temp_dir.path());
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = Arc::new(RollupManager::load(&state_file)?);
+
+    let changes = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = changes.clone();
+    let callback_manager = manager.clone();
+    manager.on_status_change(Box::new(move |rollup_id, old, new| {
+        // Reading the registry would deadlock if the callback ran under the write lock
+        let current = futures::executor::block_on(callback_manager.get_rollup(rollup_id));
+        assert_eq!(current.unwrap().status, *new);
+        seen.lock()
+            .unwrap()
+            .push((rollup_id.to_string(), old.clone(), new.clone()));
+    }));
+
+    manager
+        .update_rollup_status("rollup-0", RollupStatus::Starting)
+        .await?;
+    manager
+        .update_rollup_status("rollup-0", RollupStatus::Starting)
+        .await?;
+    manager
+        .update_rollup_status("rollup-0", RollupStatus::Running)
+        .await?;
+
+    let changes = changes.lock().unwrap().clone();
+    assert_eq!(changes, [
+        (
+            "rollup-0".to_string(),
+            RollupStatus::Stopped,
+            RollupStatus::Starting
+        ),
+        (
+            "rollup-0".to_string(),
+            RollupStatus::Starting,
+            RollupStatus::Running
+        ),
+    ]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn exec_only_runs_allowlisted_commands() -> anyhow::Result<()> {
+    let allowlist = ExecAllowlist::default().with_commands_from("peers=nitro --peers; broken=;=ls");
+    assert_eq!(allowlist.names().collect::<Vec<_>>(), [
+        "disk-usage",
+        "peers",
+        "version"
+    ]);
+    assert_eq!(allowlist.get("peers").unwrap(), ["nitro", "--peers"]);
+
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let rollup = running_rollup(0);
+    let rollups = HashMap::from([(rollup.rollup_id.clone(), rollup)]);
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?.with_exec_allowlist(allowlist);
+
+    let error = manager
+        .exec_command("rollup-0", "rm -rf /home/user/.arbitrum")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, RaasError::CommandNotAllowed { .. }));
+    assert_eq!(
+        error.to_string(),
+        "Command rm -rf /home/user/.arbitrum is not allowed, expected one of: disk-usage, peers, version"
+    );
+    assert!(matches!(
+        manager.exec_command("missing", "version").await,
+        Err(RaasError::RollupNotFound(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn rollup_info_json_carries_no_key_material() -> anyhow::Result<()> {
+    let mut rollup = running_rollup(0);
+    rollup.deployment = Some(deployment());
+    let json = serde_json::to_value(&rollup)?;
+
+    assert_eq!(json["config"]["chain_id"], 412346);
+    assert_eq!(json["status"], "Running");
+
+    // Addresses are 20 bytes, a 32-byte hex string could only be a private key
+    fn strings(value: &serde_json::Value, found: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(string) => found.push(string.clone()),
+            serde_json::Value::Array(values) => values.iter().for_each(|v| strings(v, found)),
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    found.push(key.clone());
+                    strings(value, found);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut found = Vec::new();
+    strings(&json, &mut found);
+    for string in found {
+        let lower = string.to_lowercase();
+        assert!(
+            !lower.contains("private") && !lower.contains("privkey"),
+            "{string}"
+        );
+        let hex = string.trim_start_matches("0x");
+        assert!(
+            !(hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())),
+            "{string}"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn docker_connection_is_retried_until_the_daemon_is_up() -> anyhow::Result<()> {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(10),
+    };
+
+    let attempts = AtomicUsize::new(0);
+    let connection = connect_with_retry(policy, || async {
+        match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => Err("Cannot connect to the Docker daemon"),
+            _ => Ok("connected"),
+        }
+    })
+    .await?;
+    assert_eq!(connection, "connected");
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    let error = connect_with_retry(policy, || async {
+        Err::<(), _>("Cannot connect to the Docker daemon")
+    })
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Failed to connect to the Docker daemon after 3 attempts: Cannot connect to the Docker daemon"
+    );
+    Ok(())
+}
+
+#[test]
+fn large_logs_are_compressed_small_ones_are_not() -> anyhow::Result<()> {
+    let small = LogsEnvelope::encode("INFO started", 1024)?;
+    assert_eq!(
+        serde_json::to_value(&small)?,
+        serde_json::json!({"encoding": "plain", "data": "INFO started"})
+    );
+
+    let logs = "INFO [10-16|12:00:00.000] Created block number=42\n".repeat(1000);
+    let large = LogsEnvelope::encode(&logs, 1024)?;
+    assert_eq!(large.encoding, LogsEncoding::GzipBase64);
+    assert!(large.data.len() < logs.len() / 10);
+    assert_eq!(serde_json::to_value(&large)?["encoding"], "gzip+base64");
+    assert_eq!(large.decode()?, logs);
+    Ok(())
+}
+
+#[tokio::test]
+async fn node_operations_go_through_the_runtime() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let runtime = MockRuntime::default();
+    let manager = RollupManager::with_base_dir(temp_dir.path().to_path_buf())
+        .with_deployer(Box::new(MockDeployer::default()))
+        .with_runtime(move |info| Box::new(runtime.for_rollup(info)))
+        .with_exec_allowlist(ExecAllowlist::default().with_commands_from("peers=nitro --peers"))
+        .with_per_rollup_keys(true);
+    create(&manager, temp_dir.path(), "rollup-0").await?;
+    manager.start_rollup("rollup-0").await?;
+
+    assert_eq!(
+        manager.exec_command("rollup-0", "peers").await?,
+        "nitro --peers"
+    );
+    assert_eq!(manager.logs("rollup-0").await?, "");
+    let info = manager.get_rollup("rollup-0").await.unwrap();
+    let endpoints = manager.endpoints("rollup-0").await?;
+    assert_eq!(
+        endpoints.rpc,
+        format!("http://localhost:{}", info.rpc_port.unwrap())
+    );
+
+    manager.upgrade_rollup("rollup-0", "v3.5.0").await?;
+    let info = manager.get_rollup("rollup-0").await.unwrap();
+    assert_eq!(info.status, RollupStatus::Running);
+    assert_eq!(info.node_image_tag.as_deref(), Some("v3.5.0"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn upgrades_need_a_valid_tag_and_a_settled_rollup() -> anyhow::Result<()> {
+    let temp_dir = tempfile::TempDir::new()?;
+    let state_file = temp_dir.path().join("state.json");
+    let mut starting = running_rollup(1);
+    starting.status = RollupStatus::Starting;
+    let rollups: HashMap<String, RollupInfo> = [running_rollup(0), starting]
+        .into_iter()
+        .map(|rollup| (rollup.rollup_id.clone(), rollup))
+        .collect();
+    std::fs::write(&state_file, serde_json::to_vec(&rollups)?)?;
+    let manager = RollupManager::load(&state_file)?;
+
+    let error = manager
+        .upgrade_rollup("rollup-0", "v1; docker rm -f nitro-node")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, RaasError::InvalidConfig(_)), "{error}");
+
+    let error = manager.upgrade_rollup("rollup-1", "v2").await.unwrap_err();
+    assert!(
+        error.to_string().contains("while it is Starting"),
+        "{error}"
+    );
+
+    // Neither rollup was touched
+    assert_eq!(
+        manager.get_rollup_status("rollup-0").await?,
+        RollupStatus::Running
+    );
+    assert_eq!(
+        manager.get_rollup_status("rollup-1").await?,
+        RollupStatus::Starting
+    );
+    assert_eq!(RollupStatus::Upgrading.to_string(), "Upgrading");
+    Ok(())
+}
@@ -1,15 +1,25 @@
 use blueprint_sdk as sdk;
 use blueprint_sdk::testing::utils::tangle::harness::SetupServicesOpts;
+use espresso_raas_blueprint::deployer::MockDeployer;
+use espresso_raas_blueprint::docker::mock::MockRuntime;
 use espresso_raas_blueprint::docker::{
-    create_docker_rollup, delete_docker_rollup, start_docker_rollup, stop_docker_rollup,
+    CreateRollupResult, check_docker_rollup_health, create_docker_rollup,
+    decommission_docker_rollup, delete_docker_rollup, diagnose_host, diagnose_network,
+    exec_docker_rollup, get_docker_rollup_config_diff, get_docker_rollup_endpoints,
+    get_docker_rollup_info, get_docker_rollup_logs, get_docker_rollup_metrics,
+    get_docker_rollup_status, list_docker_rollups, list_docker_rollups_paged, prune_docker_rollups,
+    regenerate_docker_rollup_config, restart_docker_rollup, rotate_docker_rollup_keys,
+    start_docker_rollup, start_docker_rollups_for_service, stop_docker_rollup,
+    stop_docker_rollups_for_service, update_docker_rollup, upgrade_docker_rollup,
 };
-use espresso_raas_blueprint::{NetworkType, RollupConfigParams, ServiceContext};
+use espresso_raas_blueprint::{NetworkType, RollupConfigParams, RollupManager, ServiceContext};
 use hex_literal::hex;
 
 use sdk::Job;
 use sdk::tangle::layers::TangleLayer;
 use sdk::tangle::serde::{from_field, to_field};
 use sdk::testing::{tempfile, utils::*};
+use std::sync::Arc;
 
 #[tokio::test]
 async fn test_rollup_creation() -> color_eyre::Result<()> {
@@ -21,7 +31,7 @@ async fn test_rollup_creation() -> color_eyre::Result<()> {
 
     // Create a sample rollup configuration
     let rollup_config = RollupConfigParams {
-        chain_id: 42,
+        chain_id: 412350,
         initial_chain_owner: hex!("123456789abcdef0123456789abcdef012345678"),
         validators: vec![
             hex!("abcdef0123456789abcdef0123456789abcdef01"),
@@ -32,6 +42,14 @@ async fn test_rollup_creation() -> color_eyre::Result<()> {
         batch_poster_manager: hex!("1357bdf91357bdf91357bdf91357bdf91357bdf9"),
         is_mainnet: false,
         network: NetworkType::Geth,
+        node_flags: Default::default(),
+        dry_run: false,
+        request_id: None,
+        rpc_host_port: None,
+        node_image: None,
+        espresso_sequencer_url: None,
+        espresso_namespace: None,
+        extra_env: Default::default(),
     };
     // Setup service
     let (mut test_env, service_id, _) = harness
@@ -45,12 +63,21 @@ async fn test_rollup_creation() -> color_eyre::Result<()> {
     // Register the job handlers for Docker rollups
     let handles = test_env.node_handles().await;
     let mut contexts = Vec::new();
+    // Keep the rollups of this test apart from any other manager on the host
+    let data_dir = tempfile::TempDir::new()?;
+    let runtime = MockRuntime::default();
+    let rollups = Arc::new(
+        RollupManager::with_base_dir(data_dir.path().to_path_buf())
+            .with_deployer(Box::new(MockDeployer::default()))
+            .with_runtime(move |info| Box::new(runtime.for_rollup(info))),
+    );
     for handle in handles {
         let config = handle.gadget_config().await;
 
         // Create a context for the jobs
         let context = ServiceContext {
             config: config.clone(),
+            rollups: rollups.clone(),
         };
 
         // Register each job handler
@@ -62,6 +89,61 @@ async fn test_rollup_creation() -> color_eyre::Result<()> {
         handle
             .add_job(delete_docker_rollup.layer(TangleLayer))
             .await;
+        handle
+            .add_job(get_docker_rollup_status.layer(TangleLayer))
+            .await;
+        handle.add_job(diagnose_network.layer(TangleLayer)).await;
+        handle
+            .add_job(decommission_docker_rollup.layer(TangleLayer))
+            .await;
+        handle.add_job(list_docker_rollups.layer(TangleLayer)).await;
+        handle
+            .add_job(get_docker_rollup_config_diff.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(restart_docker_rollup.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(update_docker_rollup.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(check_docker_rollup_health.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(start_docker_rollups_for_service.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(stop_docker_rollups_for_service.layer(TangleLayer))
+            .await;
+        handle.add_job(exec_docker_rollup.layer(TangleLayer)).await;
+        handle
+            .add_job(get_docker_rollup_info.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(list_docker_rollups_paged.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(get_docker_rollup_logs.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(upgrade_docker_rollup.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(get_docker_rollup_endpoints.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(prune_docker_rollups.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(rotate_docker_rollup_keys.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(get_docker_rollup_metrics.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(regenerate_docker_rollup_config.layer(TangleLayer))
+            .await;
+        handle.add_job(diagnose_host.layer(TangleLayer)).await;
 
         contexts.push(context);
     }
@@ -81,7 +163,9 @@ async fn test_rollup_creation() -> color_eyre::Result<()> {
 
     // Expecting a successful creation of the rollup
     let success_field = results.result[0].clone();
-    let rollup_id: String = from_field(success_field)?;
+    let created: String = from_field(success_field)?;
+    let created: CreateRollupResult = serde_json::from_str(&created)?;
+    let rollup_id = created.rollup_id;
     println!("Rollup ID: {}", rollup_id);
 
     // Start the rollup
@@ -92,3 +176,144 @@ async fn test_rollup_creation() -> color_eyre::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_rollup_status() -> color_eyre::Result<()> {
+    setup_log();
+
+    // Initialize test harness (node, keys, deployment)
+    let temp_dir = tempfile::TempDir::new()?;
+    let harness = tangle::TangleTestHarness::setup(temp_dir).await?;
+
+    // Create a sample rollup configuration
+    let rollup_config = RollupConfigParams {
+        chain_id: 412351,
+        initial_chain_owner: hex!("123456789abcdef0123456789abcdef012345678"),
+        validators: vec![hex!("abcdef0123456789abcdef0123456789abcdef01")].into(),
+        batch_poster_address: hex!("2468ace02468ace02468ace02468ace02468ace0"),
+        batch_poster_manager: hex!("1357bdf91357bdf91357bdf91357bdf91357bdf9"),
+        is_mainnet: false,
+        network: NetworkType::Geth,
+        node_flags: Default::default(),
+        dry_run: false,
+        request_id: None,
+        rpc_host_port: None,
+        node_image: None,
+        espresso_sequencer_url: None,
+        espresso_namespace: None,
+        extra_env: Default::default(),
+    };
+    // Setup service
+    let (mut test_env, service_id, _) = harness
+        .setup_services_with_options::<1>(SetupServicesOpts {
+            exit_after_registration: false,
+            ..Default::default()
+        })
+        .await?;
+    test_env.initialize().await?;
+
+    // Register the job handlers for Docker rollups
+    let handles = test_env.node_handles().await;
+    let mut contexts = Vec::new();
+    let data_dir = tempfile::TempDir::new()?;
+    let runtime = MockRuntime::default();
+    let rollups = Arc::new(
+        RollupManager::with_base_dir(data_dir.path().to_path_buf())
+            .with_deployer(Box::new(MockDeployer::default()))
+            .with_runtime(move |info| Box::new(runtime.for_rollup(info))),
+    );
+    for handle in handles {
+        let config = handle.gadget_config().await;
+        let context = ServiceContext {
+            config: config.clone(),
+            rollups: rollups.clone(),
+        };
+
+        handle
+            .add_job(create_docker_rollup.layer(TangleLayer))
+            .await;
+        handle.add_job(start_docker_rollup.layer(TangleLayer)).await;
+        handle.add_job(stop_docker_rollup.layer(TangleLayer)).await;
+        handle
+            .add_job(delete_docker_rollup.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(get_docker_rollup_status.layer(TangleLayer))
+            .await;
+        handle.add_job(diagnose_network.layer(TangleLayer)).await;
+        handle
+            .add_job(decommission_docker_rollup.layer(TangleLayer))
+            .await;
+        handle.add_job(list_docker_rollups.layer(TangleLayer)).await;
+        handle
+            .add_job(get_docker_rollup_config_diff.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(restart_docker_rollup.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(update_docker_rollup.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(check_docker_rollup_health.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(start_docker_rollups_for_service.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(stop_docker_rollups_for_service.layer(TangleLayer))
+            .await;
+        handle.add_job(exec_docker_rollup.layer(TangleLayer)).await;
+        handle
+            .add_job(get_docker_rollup_info.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(list_docker_rollups_paged.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(get_docker_rollup_logs.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(upgrade_docker_rollup.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(get_docker_rollup_endpoints.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(prune_docker_rollups.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(rotate_docker_rollup_keys.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(get_docker_rollup_metrics.layer(TangleLayer))
+            .await;
+        handle
+            .add_job(regenerate_docker_rollup_config.layer(TangleLayer))
+            .await;
+        handle.add_job(diagnose_host.layer(TangleLayer)).await;
+
+        contexts.push(context);
+    }
+
+    // Start the test environment
+    test_env.start_with_contexts(contexts).await?;
+
+    // Create the rollup
+    let job_inputs = vec![to_field(rollup_config).unwrap()];
+    let job = harness.submit_job(service_id, 0, job_inputs).await?;
+    let results = harness.wait_for_job_execution(service_id, job).await?;
+    let created: String = from_field(results.result[0].clone())?;
+    let rollup_id = serde_json::from_str::<CreateRollupResult>(&created)?.rollup_id;
+
+    // Query its status
+    let job_inputs = vec![to_field(rollup_id).unwrap()];
+    let job = harness.submit_job(service_id, 4, job_inputs).await?;
+    let results = harness.wait_for_job_execution(service_id, job).await?;
+    assert_eq!(results.service_id, service_id);
+
+    let status: String = from_field(results.result[0].clone())?;
+    assert_eq!(status, "Created");
+
+    Ok(())
+}
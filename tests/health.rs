@@ -0,0 +1,154 @@
+mod common;
+
+use espresso_raas_blueprint::docker::health::{
+    ChainMetrics, Endpoints, RollupHealth, SyncStatus, check_rpc, host_port, node_metrics,
+    node_sync_status, posted_batches,
+};
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+#[test]
+fn host_port_reads_compose_port_mappings() {
+    assert_eq!(host_port("8547:8547", 8547), Some(8547));
+    assert_eq!(host_port("18547:8547/tcp", 8547), Some(18547));
+    assert_eq!(host_port("127.0.0.1:28547:8547", 8547), Some(28547));
+    assert_eq!(host_port("8548:8548", 8547), None);
+    assert_eq!(host_port("8547", 8547), None);
+}
+
+#[test]
+fn endpoints_follow_the_published_node_ports() -> anyhow::Result<()> {
+    let compose = |ports: Value| -> anyhow::Result<dockworker::ComposeConfig> {
+        Ok(serde_json::from_value(json!({
+            "version": "",
+            "services": {"nitro": {"ports": ports}},
+            "volumes": {},
+        }))?)
+    };
+
+    let endpoints = Endpoints::from_compose(
+        &compose(json!(["18547:8547", "18548:8548", "19642:9642"]))?,
+        "rollups.example.com",
+    );
+    assert_eq!(
+        endpoints,
+        Some(Endpoints {
+            rpc: "http://rollups.example.com:18547".to_string(),
+            ws: Some("ws://rollups.example.com:18548".to_string()),
+            feed: Some("ws://rollups.example.com:19642".to_string()),
+        })
+    );
+
+    // Unpublished ports have no endpoint, and there's nothing without the RPC
+    let endpoints = Endpoints::from_compose(&compose(json!(["18547:8547"]))?, "localhost");
+    assert_eq!(endpoints.and_then(|endpoints| endpoints.ws), None);
+    assert_eq!(
+        Endpoints::from_compose(&compose(json!(["18548:8548"]))?, "localhost"),
+        None
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn sync_progress_compares_the_node_with_the_parent_chain() -> anyhow::Result<()> {
+    let synced = SyncStatus::from_syncing(&json!(false));
+    assert_eq!((synced.synced, synced.sync_progress), (true, 100));
+    assert_eq!(synced.to_string(), "synced");
+
+    // Nitro counts the batches it processed against those posted on the parent chain
+    let nitro = SyncStatus::from_syncing(&json!({"batchProcessed": 45, "batchSeen": 100}));
+    assert_eq!((nitro.synced, nitro.sync_progress), (false, 45));
+    assert_eq!(nitro.to_string(), "syncing 45%");
+    let geth = SyncStatus::from_syncing(&json!({"currentBlock": "0x3e7", "highestBlock": "0x3e8"}));
+    assert_eq!(geth.sync_progress, 99);
+    assert_eq!(SyncStatus::from_syncing(&json!({})).sync_progress, 0);
+
+    let rpc_url = common::spawn_mock_rpc(|method, _params: &Value| match method {
+        "eth_syncing" => json!({"batchProcessed": 1, "batchSeen": 4}),
+        _ => Value::Null,
+    })
+    .await;
+    let sync_status = node_sync_status(&rpc_url, Duration::from_secs(5)).await?;
+    assert_eq!(sync_status.to_string(), "syncing 25%");
+    Ok(())
+}
+
+#[tokio::test]
+async fn health_reports_latest_block() {
+    let rpc_url = common::spawn_mock_rpc(|method, _params: &Value| match method {
+        "eth_blockNumber" => json!("0x2a"),
+        _ => Value::Null,
+    })
+    .await;
+
+    let health = check_rpc(&rpc_url, Duration::from_secs(5)).await;
+
+    assert!(
+        matches!(health, RollupHealth::Healthy {
+            latest_block: 42,
+            ..
+        }),
+        "{health:?}"
+    );
+}
+
+#[tokio::test]
+async fn metrics_report_height_peers_sync_state_and_batches() -> anyhow::Result<()> {
+    let rpc_url = common::spawn_mock_rpc(|method, params: &Value| match method {
+        "eth_blockNumber" => json!("0x2a"),
+        "net_peerCount" => json!("0x3"),
+        "eth_syncing" => json!({"currentBlock": "0x2a", "highestBlock": "0x100"}),
+        // `batchCount()` of the sequencer inbox
+        "eth_call" if params[0]["data"] == "0x06f13056" => {
+            json!(format!("0x{:064x}", 7))
+        }
+        _ => Value::Null,
+    })
+    .await;
+
+    let metrics = node_metrics(&rpc_url, Duration::from_secs(5)).await?;
+    assert_eq!(metrics, ChainMetrics {
+        rpc_url: rpc_url.clone(),
+        block_number: 42,
+        peer_count: 3,
+        syncing: true,
+        batch_count: None,
+    });
+    let inbox = "0x4444444444444444444444444444444444444444";
+    assert_eq!(
+        posted_batches(&rpc_url, inbox, Duration::from_secs(5)).await?,
+        7
+    );
+
+    // A synced node reports `false`, and a node that's down has no metrics
+    let synced = common::spawn_mock_rpc(|method, _params: &Value| match method {
+        "eth_syncing" => json!(false),
+        _ => json!("0x0"),
+    })
+    .await;
+    assert!(!node_metrics(&synced, Duration::from_secs(5)).await?.syncing);
+    let down = common::unreachable_rpc().await;
+    assert!(node_metrics(&down, Duration::from_secs(5)).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn refused_connections_are_told_apart_from_timeouts() {
+    let rpc_url = common::unreachable_rpc().await;
+    let health = check_rpc(&rpc_url, Duration::from_secs(5)).await;
+    assert!(
+        matches!(health, RollupHealth::ConnectionRefused { .. }),
+        "{health:?}"
+    );
+
+    // Accepts connections but never answers
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+    let health = check_rpc(&rpc_url, Duration::from_millis(200)).await;
+    assert!(!health.is_healthy());
+    assert_eq!(health, RollupHealth::Timeout {
+        rpc_url,
+        timeout_ms: 200
+    });
+}
@@ -0,0 +1,29 @@
+use espresso_raas_blueprint::docker::{CreationPhase, RollupStatus};
+use espresso_raas_blueprint::metrics::metrics;
+use std::time::Duration;
+
+#[test]
+fn encodes_rollup_and_deploy_metrics() {
+    metrics().record_rollups(&[
+        RollupStatus::Running,
+        RollupStatus::Running,
+        RollupStatus::Creating(CreationPhase::BuildingContracts),
+        RollupStatus::Failed("boom".to_string()),
+    ]);
+    metrics().record_deploy(Duration::from_secs(90), true);
+    metrics().record_deploy(Duration::from_secs(45), false);
+
+    let encoded = metrics().encode();
+    assert!(encoded.contains("espresso_rollups_total{status=\"Running\"} 2"));
+    assert!(encoded.contains("espresso_rollups_total{status=\"Creating\"} 1"));
+    // Failure reasons stay out of the labels
+    assert!(encoded.contains("espresso_rollups_total{status=\"Failed\"} 1"));
+    assert!(encoded.contains("espresso_deploy_duration_seconds_count 2"));
+    assert!(encoded.contains("espresso_deploy_failures_total 1"));
+
+    // Statuses no rollup has anymore are dropped
+    metrics().record_rollups(&[RollupStatus::Stopped]);
+    let encoded = metrics().encode();
+    assert!(!encoded.contains("status=\"Running\""));
+    assert!(encoded.contains("espresso_rollups_total{status=\"Stopped\"} 1"));
+}